@@ -1,19 +1,28 @@
-use criterion::{criterion_group, criterion_main, Criterion};
-use rust_assignment::common::data_types::{SensorData, SensorType};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rust_assignment::common::data_types::{ActuatorCommand, SensorData, SensorId, SensorType};
+use rust_assignment::common::wire_format::SerializationFormat;
+use rust_assignment::sensor::generator::SensorGenerator;
 use rust_assignment::sensor::processor::DataProcessor;
 use std::hint::black_box;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
 
 pub fn benchmark_processor(c: &mut Criterion) {
     let mut processor = DataProcessor::new(10);
     c.bench_function("sensor_processor_process", |b| {
         b.iter(|| {
             let data = black_box(SensorData {
-                sensor_id: "S1".to_string(),
+                sensor_id: SensorId::new("S1"),
                 reading_type: SensorType::Force,
                 value: 10.0,
                 timestamp: 0,
                 is_anomaly: false,
                 confidence: 1.0,
+                confidence_lower: None,
+                confidence_upper: None,
+                seq: 0,
+                rejected: false,
             });
             let _ = processor.process(data);
         });
@@ -24,12 +33,16 @@ pub fn benchmark_serialization(c: &mut Criterion) {
     // Benchmark JSON serialization (what transmitter does)
     c.bench_function("json_serialization", |b| {
         let data = SensorData {
-            sensor_id: "S1".to_string(),
+            sensor_id: SensorId::new("S1"),
             reading_type: SensorType::Force,
             value: 10.0,
             timestamp: 0,
             is_anomaly: false,
             confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq: 0,
+            rejected: false,
         };
         
         b.iter(|| {
@@ -49,5 +62,215 @@ pub fn benchmark_serialization(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, benchmark_processor, benchmark_serialization);
+pub fn benchmark_pipeline(c: &mut Criterion) {
+    // Seeded so every run sees the same readings (and the same anomalies)
+    let mut generator =
+        SensorGenerator::new("S1", SensorType::Force, 1, 10.0, 0.2, 0.01).with_seed(42);
+    let mut processor = DataProcessor::new(10).with_anomaly_logging(false);
+
+    let mut group = c.benchmark_group("pipeline");
+    group.throughput(Throughput::Elements(1)); // Reported as readings/sec
+    group.bench_function("generate_process_command", |b| {
+        b.iter(|| {
+            let (data, _metrics) = generator.generate_reading();
+            let (processed, _metrics) = processor.process(black_box(data));
+            black_box(processor.generate_actuator_command(&processed));
+        });
+    });
+    group.finish();
+}
+
+// Build a stream where every `spike_every`-th reading is a large spike (0 = no spikes)
+fn anomaly_stream(len: usize, spike_every: usize) -> Vec<SensorData> {
+    (0..len)
+        .map(|i| {
+            let spike = spike_every > 0 && i % spike_every == 0;
+            SensorData {
+                sensor_id: SensorId::new("S1"),
+                reading_type: SensorType::Force,
+                value: if spike {
+                    40.0
+                } else {
+                    10.0 + (i as f64 * 0.1).sin() * 0.2
+                },
+                timestamp: i as u128,
+                is_anomaly: spike,
+                confidence: 1.0,
+                confidence_lower: None,
+                confidence_upper: None,
+                seq: 0,
+                rejected: false,
+            }
+        })
+        .collect()
+}
+
+pub fn benchmark_anomaly_detection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("anomaly_detection");
+
+    for (label, spike_every) in [("normal_0pct", 0), ("anomalous_50pct", 2)] {
+        let stream = anomaly_stream(1000, spike_every);
+        group.throughput(Throughput::Elements(stream.len() as u64));
+        group.bench_function(label, |b| {
+            b.iter_batched(
+                || (DataProcessor::new(10).with_anomaly_logging(false), stream.clone()),
+                |(mut processor, stream)| {
+                    for data in stream {
+                        black_box(processor.process(data));
+                    }
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
+
+    // The flagged branch of detect_anomaly in isolation
+    for (label, value) in [("detect_normal", 10.1), ("detect_anomalous", 40.0)] {
+        group.bench_function(label, |b| {
+            let data = anomaly_stream(1, 0).remove(0);
+            b.iter(|| {
+                let mut data = data.clone();
+                data.value = black_box(value);
+                data.detect_anomaly(black_box(10.0), black_box(0.2), black_box(2.5));
+                black_box(data.is_anomaly);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+pub fn benchmark_wire_formats(c: &mut Criterion) {
+    let data = SensorData {
+        sensor_id: SensorId::new("S1"),
+        reading_type: SensorType::Force,
+        value: 10.0,
+        timestamp: 0,
+        is_anomaly: false,
+        confidence: 1.0,
+        confidence_lower: None,
+        confidence_upper: None,
+        seq: 0,
+        rejected: false,
+    };
+    let command = ActuatorCommand::from_sensor_data(&data);
+
+    let mut group = c.benchmark_group("wire_format");
+    for format in SerializationFormat::ALL {
+        // Criterion only measures time, so report the encoded sizes alongside
+        println!(
+            "{}: SensorData = {} bytes, ActuatorCommand = {} bytes",
+            format.name(),
+            format.encode(&data).unwrap().len(),
+            format.encode(&command).unwrap().len()
+        );
+
+        group.bench_function(format!("{}_encode", format.name()), |b| {
+            b.iter(|| black_box(format.encode(black_box(&data)).unwrap()));
+        });
+
+        let encoded = format.encode(&data).unwrap();
+        group.bench_function(format!("{}_decode", format.name()), |b| {
+            b.iter(|| {
+                let decoded: SensorData = format.decode(black_box(&encoded)).unwrap();
+                black_box(decoded);
+            });
+        });
+    }
+    group.finish();
+}
+
+// Commands delivered per measured iteration
+const DELIVERY_BATCH: usize = 1000;
+
+// Actuator side of the TCP path: decode framed commands and write one ack byte
+// per complete batch, so the sender measures end-to-end delivery
+fn spawn_tcp_consumer(
+    listener: TcpListener,
+    format: SerializationFormat,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 8192];
+        let mut received = 0;
+        loop {
+            let n = match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => n,
+            };
+            buffer.extend_from_slice(&chunk[..n]);
+            while let Some(frame) = format.take_frame(&mut buffer) {
+                let command: ActuatorCommand = format.decode(&frame).unwrap();
+                black_box(command);
+                received += 1;
+                if received == DELIVERY_BATCH {
+                    received = 0;
+                    stream.write_all(&[1]).unwrap();
+                }
+            }
+        }
+    })
+}
+
+// Throughput of delivering ActuatorCommands over the in-process channel versus a
+// loopback TCP connection. There is no broker transport in this tree, so TCP is
+// the networked path whose overhead is being measured.
+pub fn benchmark_command_delivery(c: &mut Criterion) {
+    let data = anomaly_stream(1, 0).remove(0);
+    let command = ActuatorCommand::from_sensor_data(&data);
+
+    let mut group = c.benchmark_group("command_delivery");
+    group.throughput(Throughput::Elements(DELIVERY_BATCH as u64)); // Reported as commands/sec
+
+    group.bench_function("channel", |b| {
+        b.iter(|| {
+            let (tx, rx) = crossbeam_channel::bounded::<ActuatorCommand>(100);
+            thread::scope(|scope| {
+                scope.spawn(|| {
+                    for _ in 0..DELIVERY_BATCH {
+                        black_box(rx.recv().unwrap());
+                    }
+                });
+                for _ in 0..DELIVERY_BATCH {
+                    tx.send(command.clone()).unwrap();
+                }
+            });
+        });
+    });
+
+    for format in SerializationFormat::ALL {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let consumer = spawn_tcp_consumer(listener, format);
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_nodelay(true).unwrap();
+
+        group.bench_function(format!("tcp_{}", format.name()), |b| {
+            let mut ack = [0u8; 1];
+            b.iter(|| {
+                for _ in 0..DELIVERY_BATCH {
+                    let frame = format.encode_frame(&command).unwrap();
+                    stream.write_all(&frame).unwrap();
+                }
+                stream.read_exact(&mut ack).unwrap();
+            });
+        });
+
+        drop(stream);
+        let _ = consumer.join();
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_processor,
+    benchmark_serialization,
+    benchmark_pipeline,
+    benchmark_anomaly_detection,
+    benchmark_wire_formats,
+    benchmark_command_delivery
+);
 criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,116 @@
+use crate::common::data_types::ControlCommand;
+use crate::config::CommandLimits;
+use log::warn;
+use std::collections::HashMap;
+
+// Keeps command values within per-command_type limits before they reach the
+// executor, so a misbehaving controller can't drive the hardware out of range
+pub struct CommandClamp {
+    limits: HashMap<String, CommandLimits>, // Keyed by command_type
+    last_executed: HashMap<String, (f64, u128)>, // Last clamped value and timestamp per command_type
+}
+
+impl CommandClamp {
+    pub fn new(limits: HashMap<String, CommandLimits>) -> Self {
+        Self {
+            limits,
+            last_executed: HashMap::new(),
+        }
+    }
+
+    // Clamp `command.value` to its command_type's range and rate limit, logging
+    // a warning when it changes. Commands of unlimited types pass through.
+    pub fn apply(&mut self, command: &mut ControlCommand) {
+        let Some(limits) = self.limits.get(&command.command_type) else {
+            return;
+        };
+        let requested = command.value;
+        let mut value = requested;
+
+        if let Some(max_rate) = limits.max_rate_of_change {
+            if let Some(&(prev_value, prev_timestamp)) =
+                self.last_executed.get(&command.command_type)
+            {
+                let dt_s = command.timestamp.saturating_sub(prev_timestamp) as f64 / 1000.0;
+                let max_step = max_rate * dt_s;
+                value = value.clamp(prev_value - max_step, prev_value + max_step);
+            }
+        }
+        // The range wins over the rate limit
+        value = limits.min.map_or(value, |min| value.max(min));
+        value = limits.max.map_or(value, |max| value.min(max));
+
+        if value != requested {
+            warn!(
+                "Clamped {} command value from {:.4} to {:.4}",
+                command.command_type, requested, value
+            );
+            command.value = value;
+        }
+        self.last_executed
+            .insert(command.command_type.clone(), (value, command.timestamp));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_log;
+
+    fn command(command_type: &str, value: f64, timestamp: u128) -> ControlCommand {
+        ControlCommand {
+            command_type: command_type.to_string(),
+            payload: None,
+            timestamp,
+            value,
+        }
+    }
+
+    #[test]
+    fn out_of_range_value_is_clamped_with_a_warning() {
+        test_log::capture();
+        let limits = CommandLimits {
+            min: Some(-5.0),
+            max: Some(5.0),
+            max_rate_of_change: None,
+        };
+        let mut clamp = CommandClamp::new(HashMap::from([("clamped_range".to_string(), limits)]));
+
+        let mut high = command("clamped_range", 12.0, 1_000);
+        clamp.apply(&mut high);
+        assert_eq!(high.value, 5.0);
+        let mut within = command("clamped_range", 2.0, 1_010);
+        clamp.apply(&mut within);
+        assert_eq!(within.value, 2.0);
+
+        let warnings = test_log::records_starting_with("Clamped clamped_range command");
+        assert_eq!(
+            warnings,
+            [(
+                log::Level::Warn,
+                "Clamped clamped_range command value from 12.0000 to 5.0000".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn rate_limit_caps_the_step_between_commands() {
+        let limits = CommandLimits {
+            min: None,
+            max: None,
+            max_rate_of_change: Some(100.0),
+        };
+        let mut clamp = CommandClamp::new(HashMap::from([("rate_limited".to_string(), limits)]));
+
+        let mut first = command("rate_limited", 0.0, 1_000);
+        clamp.apply(&mut first);
+        // 10ms at 100/s allows a step of 1
+        let mut jump = command("rate_limited", 50.0, 1_010);
+        clamp.apply(&mut jump);
+        assert!((jump.value - 1.0).abs() < 1e-9, "{}", jump.value);
+        // Other command types pass through untouched
+        let mut other = command("unlimited", 50.0, 1_010);
+        clamp.apply(&mut other);
+        assert_eq!(other.value, 50.0);
+    }
+}
@@ -1,6 +1,4 @@
-use std::time::SystemTime;
-use std::time::UNIX_EPOCH;
-
+use crate::common::clock::now_millis;
 use crate::common::data_types::ControlCommand;
 
 pub struct PIDController {
@@ -23,6 +21,13 @@ impl PIDController {
         }
     }
 
+    /// Replace the gains without resetting the accumulated state
+    pub fn set_gains(&mut self, kp: f64, ki: f64, kd: f64) {
+        self.kp = kp;
+        self.ki = ki;
+        self.kd = kd;
+    }
+
     /// Compute the PID control command based on setpoint, current measurement, and elapsed time dt
     pub fn compute(&mut self, setpoint: f64, measurement: f64, dt: f64) -> ControlCommand {
         let error = setpoint - measurement;
@@ -32,14 +37,11 @@ impl PIDController {
 
         let output = self.kp * error + self.ki * self.integral + self.kd * derivative;
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_millis();
+        let timestamp = now_millis();
 
         ControlCommand {
             command_type: "PID_OUTPUT".to_string(),
-            payload: None, // Optional additional info, can be Some(String)
+            payload: None, // Optional additional info, can be Some(CommandPayload)
             timestamp,
             value: output,
         }
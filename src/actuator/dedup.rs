@@ -0,0 +1,55 @@
+use std::collections::{HashSet, VecDeque};
+
+// How many command ids are remembered unless told otherwise
+pub const DEFAULT_DEDUP_WINDOW: usize = 1024;
+
+// The most recently seen command ids, forgetting the oldest once `capacity` is reached
+pub struct RecentCommands {
+    capacity: usize,
+    order: VecDeque<String>,
+    seen: HashSet<String>,
+}
+
+impl RecentCommands {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            seen: HashSet::with_capacity(capacity),
+        }
+    }
+
+    // Remember `command_id`, returning false if it was already in the window
+    pub fn insert(&mut self, command_id: &str) -> bool {
+        if self.seen.contains(command_id) {
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.order.push_back(command_id.to_string());
+        self.seen.insert(command_id.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_ids_are_rejected_within_the_window() {
+        let mut recent = RecentCommands::new(2);
+        assert!(recent.insert("a#1"));
+        assert!(!recent.insert("a#1"));
+        assert!(recent.insert("a#2"));
+
+        // "a#1" is the oldest, so a third id pushes it out of the window
+        assert!(recent.insert("a#3"));
+        assert!(recent.insert("a#1"));
+        assert!(!recent.insert("a#3"));
+    }
+}
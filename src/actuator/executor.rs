@@ -1,5 +1,6 @@
 use crate::common::data_types::ControlCommand;
 
+#[derive(Default)]
 pub struct Executor;
 
 impl Executor {
@@ -14,7 +15,7 @@ impl Executor {
         );
 
         if let Some(payload) = &command.payload {
-            println!("Payload: {}", payload);
+            println!("Payload: {:?}", payload);
         }
     }
 }
@@ -1,5 +1,11 @@
+pub mod clamp;
 pub mod controller;
+pub mod dedup;
 pub mod executor;
+pub mod pending;
 pub mod receiver;
+pub mod router;
 pub mod scheduler;
+pub mod setpoint;
 pub mod system;
+pub mod tcp_server;
@@ -0,0 +1,151 @@
+use crate::common::data_types::ActuatorCommand;
+use crate::config::SchedulingPolicy;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::time::Instant;
+
+// What a pending command is ordered by; a queue only ever holds one variant
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Urgency {
+    Arrival,
+    Priority(Reverse<u8>),
+    Deadline(Instant),
+}
+
+struct Pending {
+    urgency: Urgency,
+    seq: u64, // Arrival order, so ties go first come first served
+    command: ActuatorCommand,
+}
+
+// BinaryHeap pops the greatest element, so the most urgent compares greatest
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&other.urgency, other.seq).cmp(&(&self.urgency, self.seq))
+    }
+}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+// Commands waiting to run, handed out most urgent first under `policy`
+pub struct PendingCommands {
+    policy: SchedulingPolicy,
+    heap: BinaryHeap<Pending>,
+    next_seq: u64,
+}
+
+impl PendingCommands {
+    pub fn new(policy: SchedulingPolicy) -> Self {
+        Self {
+            policy,
+            heap: BinaryHeap::new(),
+            next_seq: 0,
+        }
+    }
+
+    pub fn push(&mut self, command: ActuatorCommand) {
+        let urgency = match self.policy {
+            SchedulingPolicy::Fifo => Urgency::Arrival,
+            SchedulingPolicy::Priority => Urgency::Priority(Reverse(command.priority)),
+            SchedulingPolicy::Edf => Urgency::Deadline(command.deadline),
+        };
+        self.heap.push(Pending {
+            urgency,
+            seq: self.next_seq,
+            command,
+        });
+        self.next_seq += 1;
+    }
+
+    // Take the most urgent command
+    pub fn pop(&mut self) -> Option<ActuatorCommand> {
+        self.heap.pop().map(|pending| pending.command)
+    }
+}
+
+impl Extend<ActuatorCommand> for PendingCommands {
+    fn extend<I: IntoIterator<Item = ActuatorCommand>>(&mut self, commands: I) {
+        for command in commands {
+            self.push(command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data_types::{SensorData, SensorId, SensorType};
+    use std::time::Duration;
+
+    // Command tagged with `seq`, to tell the order they come out in
+    fn command(seq: u64, priority: u8, deadline: Instant) -> ActuatorCommand {
+        let data = SensorData {
+            timestamp: 1_000,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Force,
+            value: 1.0,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        };
+        let mut command = ActuatorCommand::from_sensor_data(&data);
+        command.priority = priority;
+        command.deadline = deadline;
+        command
+    }
+
+    // Queue (seq, priority, deadline offset in ms) commands under `policy` and
+    // return their seqs in the order they're popped
+    fn drain(policy: SchedulingPolicy, commands: &[(u64, u8, u64)]) -> Vec<u64> {
+        let now = Instant::now();
+        let mut pending = PendingCommands::new(policy);
+        pending.extend(commands.iter().map(|&(seq, priority, offset_ms)| {
+            command(seq, priority, now + Duration::from_millis(offset_ms))
+        }));
+        std::iter::from_fn(|| pending.pop())
+            .map(|command| command.command_id.unwrap())
+            .map(|id| id.rsplit('#').next().unwrap().parse().unwrap())
+            .collect()
+    }
+
+    const COMMANDS: [(u64, u8, u64); 5] = [
+        (1, 5, 300),
+        (2, 10, 200),
+        (3, 5, 100),
+        (4, 10, 200),
+        (5, 1, 100),
+    ];
+
+    #[test]
+    fn fifo_keeps_arrival_order() {
+        assert_eq!(drain(SchedulingPolicy::Fifo, &COMMANDS), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn priority_runs_highest_first_then_by_arrival() {
+        assert_eq!(
+            drain(SchedulingPolicy::Priority, &COMMANDS),
+            [2, 4, 1, 3, 5]
+        );
+    }
+
+    #[test]
+    fn edf_runs_earliest_deadline_first_then_by_arrival() {
+        assert_eq!(drain(SchedulingPolicy::Edf, &COMMANDS), [3, 5, 2, 4, 1]);
+    }
+}
@@ -1,15 +1,24 @@
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{select, Receiver, Sender};
+use log::{debug, info};
 
 use crate::common::{
-    data_types::{PerformanceMetrics, SensorData},
+    data_types::{Heartbeat, PerformanceMetrics, SensorData},
     metrics::MetricsCollector,
 };
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// How often an idle receiver checks for shutdown
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 pub struct ReceiverTask {
     rx: Receiver<SensorData>,
     metrics_collector: Arc<MetricsCollector>, // Use Arc for shared ownership
     shared_sensor_data: Arc<Mutex<Option<SensorData>>>,
+    shutdown: Arc<AtomicBool>,
+    heartbeat_rx: Receiver<Heartbeat>,
+    heartbeat_ack_tx: Option<Sender<Heartbeat>>,
 }
 
 impl ReceiverTask {
@@ -22,12 +31,50 @@ impl ReceiverTask {
             rx,
             metrics_collector,
             shared_sensor_data,
+            shutdown: Arc::new(AtomicBool::new(false)),
+            heartbeat_rx: crossbeam_channel::never(),
+            heartbeat_ack_tx: None,
         }
     }
 
-    pub fn run(&mut self) {
-        println!("Actuator receiver started.");
-        while let Ok(sensor_data) = self.rx.recv() {
+    // Stop pulling readings once `shutdown` is set. The reading in hand is
+    // finished first; anything not yet pulled stays queued in the channel.
+    pub fn with_shutdown(mut self, shutdown: Arc<AtomicBool>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    // Echo heartbeats from `heartbeat_rx` back on `ack_tx`, so the sensor side
+    // can tell the receiver is still alive
+    pub fn with_heartbeat(
+        mut self,
+        heartbeat_rx: Receiver<Heartbeat>,
+        ack_tx: Sender<Heartbeat>,
+    ) -> Self {
+        self.heartbeat_rx = heartbeat_rx;
+        self.heartbeat_ack_tx = Some(ack_tx);
+        self
+    }
+
+    // Run until the channel closes or shutdown is requested, then hand the
+    // receiver back so unprocessed readings can be picked up by someone else
+    pub fn run(mut self) -> Receiver<SensorData> {
+        info!("Actuator receiver started.");
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let sensor_data = select! {
+                recv(self.rx) -> sensor_data => match sensor_data {
+                    Ok(sensor_data) => sensor_data,
+                    Err(_) => {
+                        info!("Receiver channel closed, stopping receiver.");
+                        return self.rx;
+                    }
+                },
+                recv(self.heartbeat_rx) -> heartbeat => {
+                    self.acknowledge_heartbeat(heartbeat.ok());
+                    continue;
+                },
+                default(SHUTDOWN_POLL_INTERVAL) => continue,
+            };
             let start_time = std::time::Instant::now();
             self.metrics_collector.record_sensor_data(&sensor_data);
 
@@ -38,7 +85,7 @@ impl ReceiverTask {
             }
 
             // Process sensor_data here
-            println!("Received sensor data: {:?}", sensor_data);
+            debug!("Received sensor data: {:?}", sensor_data);
 
             // Calculate end_time and duration
             let end_time = std::time::Instant::now();
@@ -50,11 +97,102 @@ impl ReceiverTask {
                 end_time: Some(end_time),
                 duration_ms: Some(duration),
                 success: true,
+                attempts: 1,
+                error_kind: None,
+                label: Some(sensor_data.sensor_id.to_string()),
+                count: 1,
             };
 
             // Add metrics to collector
             self.metrics_collector.add_metrics(perf_metrics);
         }
-        println!("Receiver channel closed, stopping receiver.");
+        info!(
+            "Shutdown requested, stopping receiver with {} readings still queued.",
+            self.rx.len()
+        );
+        self.rx
+    }
+
+    // Echo a heartbeat, or stop listening for them once their channel closes
+    fn acknowledge_heartbeat(&mut self, heartbeat: Option<Heartbeat>) {
+        let acked = match (heartbeat, &self.heartbeat_ack_tx) {
+            (Some(heartbeat), Some(ack_tx)) => ack_tx.send(heartbeat).is_ok(),
+            _ => false,
+        };
+        if !acked {
+            self.heartbeat_rx = crossbeam_channel::never();
+            self.heartbeat_ack_tx = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data_types::{SensorId, SensorType};
+    use crate::config::Config;
+
+    fn reading(seq: u64) -> SensorData {
+        SensorData {
+            timestamp: 1_000 + seq as u128,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Force,
+            value: seq as f64,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        }
+    }
+
+    fn receiver_task(
+        rx: Receiver<SensorData>,
+    ) -> (
+        ReceiverTask,
+        Arc<MetricsCollector>,
+        Arc<Mutex<Option<SensorData>>>,
+    ) {
+        let metrics = Arc::new(MetricsCollector::new(&Config::default().metrics));
+        let latest = Arc::new(Mutex::new(None));
+        let task = ReceiverTask::new(rx, Arc::clone(&metrics), Arc::clone(&latest));
+        (task, metrics, latest)
+    }
+
+    #[test]
+    fn readings_left_at_shutdown_stay_queued() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        for seq in 1..=3 {
+            tx.send(reading(seq)).unwrap();
+        }
+        let (task, metrics, latest) = receiver_task(rx);
+
+        let rx = task.with_shutdown(Arc::new(AtomicBool::new(true))).run();
+
+        // Nothing was pulled, so nothing was recorded or lost
+        assert!(latest.lock().unwrap().is_none());
+        assert!(!metrics.generate_report().contains_key("sensor_receive"));
+        let queued: Vec<_> = rx.try_iter().map(|r| r.seq).collect();
+        assert_eq!(queued, [1, 2, 3]);
+    }
+
+    #[test]
+    fn every_reading_is_processed_before_the_channel_closes() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        for seq in 1..=3 {
+            tx.send(reading(seq)).unwrap();
+        }
+        drop(tx);
+        let (task, metrics, latest) = receiver_task(rx);
+
+        let rx = task.run();
+
+        assert_eq!(latest.lock().unwrap().as_ref().unwrap().seq, 3);
+        assert_eq!(
+            metrics.generate_report()["sensor_receive"].total_operations,
+            3
+        );
+        assert!(rx.is_empty());
     }
 }
@@ -0,0 +1,177 @@
+use crate::actuator::pending::PendingCommands;
+use crate::common::control::ActuatorControl;
+use crate::common::data_types::{ActuatorCommand, SensorType};
+use crate::config::SchedulingPolicy;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::time::Instant;
+use thiserror::Error;
+
+// Route used for command types with no entry in the routing map
+pub const DEFAULT_ROUTE: &str = "default";
+
+// A command's route has no receiver left
+#[derive(Debug, Error)]
+#[error("Route {route} closed")]
+pub struct RouteClosed {
+    pub route: String,
+}
+
+// Dispatches actuator commands to one channel per route, chosen by command_type
+pub struct CommandRouter {
+    routes: HashMap<String, String>, // command_type -> route name
+    senders: HashMap<String, Sender<ActuatorCommand>>,
+}
+
+impl CommandRouter {
+    // Build a router for `routes` (command_type -> route name), returning it with
+    // the receiving end of every route, including DEFAULT_ROUTE
+    pub fn new(
+        routes: HashMap<String, String>,
+        capacity: usize,
+    ) -> (Self, HashMap<String, Receiver<ActuatorCommand>>) {
+        let mut senders = HashMap::new();
+        let mut receivers = HashMap::new();
+
+        let route_names = routes.values().map(String::as_str);
+        for route in route_names.chain([DEFAULT_ROUTE]) {
+            if !senders.contains_key(route) {
+                let (tx, rx) = bounded(capacity);
+                senders.insert(route.to_string(), tx);
+                receivers.insert(route.to_string(), rx);
+            }
+        }
+
+        (Self { routes, senders }, receivers)
+    }
+
+    // Name of the route `command_type` is dispatched to
+    pub fn route_for(&self, command_type: &str) -> &str {
+        self.routes
+            .get(command_type)
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_ROUTE)
+    }
+
+    // Send `command` to its route, failing if that route's receiver is gone
+    pub fn dispatch(&self, command: ActuatorCommand) -> Result<(), RouteClosed> {
+        let route = self.route_for(&command.control_command.command_type);
+        self.senders[route]
+            .send(command)
+            .map_err(|_| RouteClosed {
+                route: route.to_string(),
+            })
+    }
+
+    // Dispatch everything from `actuator_rx` until it closes or a route does
+    pub fn run(self, actuator_rx: Receiver<ActuatorCommand>) {
+        while let Ok(cmd) = actuator_rx.recv() {
+            if let Err(e) = self.dispatch(cmd) {
+                warn!("{}, stopping command router.", e);
+                return;
+            }
+        }
+        info!("Actuator command channel closed, stopping command router.");
+    }
+}
+
+// Log the commands arriving on `route`, most urgent first under `policy`, and
+// pass those mapped to a setpoint on to the actuator control loop. Blocks until
+// the route closes.
+pub fn run_route(
+    route: &str,
+    route_rx: Receiver<ActuatorCommand>,
+    policy: SchedulingPolicy,
+    setpoint_commands: &HashMap<String, SensorType>,
+    setpoint_tx: &Sender<ActuatorControl>,
+) {
+    let mut pending = PendingCommands::new(policy);
+    while let Ok(cmd) = route_rx.recv() {
+        pending.push(cmd);
+        // Commands that arrive while others wait are ordered along with them
+        loop {
+            pending.extend(route_rx.try_iter());
+            let Some(cmd) = pending.pop() else {
+                break;
+            };
+            debug!(
+                "[{}] Received actuator command for actuator id: {}",
+                route, cmd.actuator_id
+            );
+            debug!("Command details: {:?}", cmd.control_command);
+            debug!("Priority: {}", cmd.priority);
+            debug!(
+                "Deadline: {} ms",
+                cmd.deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_millis()
+            );
+            if setpoint_commands.contains_key(&cmd.control_command.command_type) {
+                let _ = setpoint_tx.send(ActuatorControl::Command(cmd.control_command));
+            }
+        }
+    }
+    info!("Route {} closed, stopping its command logger.", route);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data_types::{SensorData, SensorId};
+
+    fn command(sensor_type: SensorType, seq: u64) -> ActuatorCommand {
+        ActuatorCommand::from_sensor_data(&SensorData {
+            timestamp: 1_000 + seq as u128,
+            sensor_id: SensorId::new("sensor_1"),
+            reading_type: sensor_type,
+            value: seq as f64,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        })
+    }
+
+    #[test]
+    fn commands_land_on_the_route_for_their_type() {
+        let routes = HashMap::from([
+            ("AdjustForce".to_string(), "force".to_string()),
+            ("MovePosition".to_string(), "position".to_string()),
+        ]);
+        let (router, receivers) = CommandRouter::new(routes, 10);
+        let mut names: Vec<_> = receivers.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["default", "force", "position"]);
+
+        router.dispatch(command(SensorType::Force, 1)).unwrap();
+        router.dispatch(command(SensorType::Position, 2)).unwrap();
+        router
+            .dispatch(command(SensorType::Temperature, 3))
+            .unwrap();
+
+        let received = |route: &str| -> Vec<String> {
+            receivers[route]
+                .try_iter()
+                .map(|c| c.control_command.command_type)
+                .collect()
+        };
+        assert_eq!(received("force"), ["AdjustForce"]);
+        assert_eq!(received("position"), ["MovePosition"]);
+        // Unmapped command types go to the default route
+        assert_eq!(received("default"), ["RegulateTemperature"]);
+    }
+
+    #[test]
+    fn dispatch_to_a_closed_route_fails() {
+        let routes = HashMap::from([("AdjustForce".to_string(), "force".to_string())]);
+        let (router, mut receivers) = CommandRouter::new(routes, 10);
+        receivers.remove("force");
+
+        let err = router.dispatch(command(SensorType::Force, 1)).unwrap_err();
+        assert_eq!(err.route, "force");
+        router.dispatch(command(SensorType::Position, 2)).unwrap();
+    }
+}
@@ -1,25 +1,36 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
 pub struct Scheduler {
     interval: Duration,
+    shutdown: Arc<AtomicBool>,
 }
 
 impl Scheduler {
     pub fn new(interval_ms: u64) -> Self {
         Self {
             interval: Duration::from_millis(interval_ms),
+            shutdown: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    // Stop running the task once `shutdown` is set
+    pub fn with_shutdown(mut self, shutdown: Arc<AtomicBool>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
     pub fn start<F>(&self, mut task: F)
     where
         F: FnMut() + Send + 'static,
     {
         let interval = self.interval;
+        let shutdown = Arc::clone(&self.shutdown);
         thread::spawn(move || {
             let mut next_instant = Instant::now();
-            loop {
+            while !shutdown.load(Ordering::Relaxed) {
                 next_instant += interval;
                 task();
 
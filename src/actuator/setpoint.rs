@@ -0,0 +1,127 @@
+use crate::common::data_types::SensorType;
+use std::collections::HashMap;
+
+// Moves the setpoint towards its target at a bounded rate so the PID
+// controller doesn't see a step change (and the derivative kick it causes)
+pub struct SetpointRamp {
+    current: f64,
+    target: f64,
+    rate_per_s: f64, // Maximum change per second; <= 0.0 jumps straight to the target
+}
+
+impl SetpointRamp {
+    pub fn new(initial: f64, rate_per_s: f64) -> Self {
+        Self {
+            current: initial,
+            target: initial,
+            rate_per_s,
+        }
+    }
+
+    pub fn set_target(&mut self, target: f64) {
+        self.target = target;
+    }
+
+    pub fn current(&self) -> f64 {
+        self.current
+    }
+
+    /// Advance the ramp by `dt` seconds and return the interpolated setpoint
+    pub fn step(&mut self, dt: f64) -> f64 {
+        if self.rate_per_s <= 0.0 {
+            self.current = self.target;
+        } else {
+            let max_step = self.rate_per_s * dt;
+            self.current += (self.target - self.current).clamp(-max_step, max_step);
+        }
+        self.current
+    }
+}
+
+// Setpoints for individual sensor types, set by commands mapped to them in
+// ActuatorConfig::setpoint_commands. Each ramps like the default setpoint,
+// which still applies to every type without its own.
+pub struct TypedSetpoints {
+    rate_per_s: f64,
+    ramps: HashMap<SensorType, SetpointRamp>,
+}
+
+impl TypedSetpoints {
+    pub fn new(rate_per_s: f64) -> Self {
+        Self {
+            rate_per_s,
+            ramps: HashMap::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ramps.is_empty()
+    }
+
+    // Retarget `sensor_type`'s setpoint; a new one ramps from `from`
+    pub fn set_target(&mut self, sensor_type: SensorType, target: f64, from: f64) {
+        let rate_per_s = self.rate_per_s;
+        self.ramps
+            .entry(sensor_type)
+            .or_insert_with(|| SetpointRamp::new(from, rate_per_s))
+            .set_target(target);
+    }
+
+    // Advance every setpoint by `dt` seconds, returning `sensor_type`'s if it has one
+    pub fn step(&mut self, dt: f64, sensor_type: SensorType) -> Option<f64> {
+        let mut setpoint = None;
+        for (&ramp_type, ramp) in self.ramps.iter_mut() {
+            let value = ramp.step(dt);
+            if ramp_type == sensor_type {
+                setpoint = Some(value);
+            }
+        }
+        setpoint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actuator::controller::PIDController;
+
+    #[test]
+    fn a_step_is_ramped_through_intermediate_setpoints() {
+        let mut ramp = SetpointRamp::new(0.0, 50.0);
+        ramp.set_target(100.0);
+        let setpoints: Vec<_> = (0..5).map(|_| ramp.step(0.5)).collect();
+        assert_eq!(setpoints, vec![25.0, 50.0, 75.0, 100.0, 100.0]);
+    }
+
+    #[test]
+    fn a_ramp_without_a_rate_jumps_to_the_target() {
+        let mut ramp = SetpointRamp::new(0.0, 0.0);
+        ramp.set_target(100.0);
+        assert_eq!(ramp.step(0.01), 100.0);
+        assert_eq!(ramp.current(), 100.0);
+    }
+
+    #[test]
+    fn a_mapped_command_sets_its_type_setpoint_and_the_pid_tracks_it() {
+        let setpoint_commands =
+            HashMap::from([("move_position".to_string(), SensorType::Position)]);
+        let mut typed = TypedSetpoints::new(0.0);
+        let command_type = "move_position";
+        if let Some(&sensor_type) = setpoint_commands.get(command_type) {
+            typed.set_target(sensor_type, 20.0, 50.0);
+        }
+
+        // Other types keep the global setpoint
+        let dt = 0.01;
+        assert_eq!(typed.step(dt, SensorType::Force), None);
+
+        // A position plant that moves at the commanded rate settles on the new setpoint
+        let mut pid = PIDController::new(2.0, 0.0, 0.0);
+        let mut position = 50.0;
+        for _ in 0..500 {
+            let setpoint = typed.step(dt, SensorType::Position).unwrap_or(50.0);
+            position += pid.compute(setpoint, position, dt).value * dt;
+        }
+        assert!((position - 20.0).abs() < 0.01, "position {}", position);
+    }
+}
@@ -1,26 +1,61 @@
+use crate::actuator::clamp::CommandClamp;
 use crate::actuator::controller::PIDController;
 use crate::actuator::executor::Executor;
 use crate::actuator::scheduler::Scheduler;
-use crate::common::data_types::{ActuatorFeedback, ActuatorStatus, SensorData};
-use crate::common::metrics::MetricsCollector;
-use crate::config::MetricsConfig;
+use crate::actuator::setpoint::{SetpointRamp, TypedSetpoints};
+use crate::common::clock::now_millis;
+use crate::common::control::ActuatorControl;
+use crate::common::data_types::{
+    ActuatorFeedback, ActuatorId, ActuatorStatus, ControlCommand, Heartbeat, PerformanceMetrics,
+    SensorData,
+};
+use crate::common::metrics::{MetricsCollector, MetricsSender};
+use crate::config::{ActuatorConfig, MetricsConfig, SetpointSource};
 use crossbeam_channel::{Receiver, Sender};
+use log::{info, warn};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
 
 use super::receiver::ReceiverTask;
 
-pub async fn run_actuator_system(rx: Receiver<SensorData>, feedback_tx: Sender<ActuatorFeedback>) {
+pub async fn run_actuator_system(
+    config: &ActuatorConfig,
+    rx: Receiver<SensorData>,
+    feedback_tx: Sender<ActuatorFeedback>,
+    metrics_tx: MetricsSender,
+    control_rx: Receiver<ActuatorControl>,
+    shutdown: Arc<AtomicBool>,
+    heartbeat: Option<(Receiver<Heartbeat>, Sender<Heartbeat>)>, // Heartbeats in, acks out
+) {
     let metrics_config = MetricsConfig {
         report_interval_ms: 60_000,
         log_to_file: false,
         log_file: String::new(),
+        records_file: None,
+        metrics_csv: None,
+        histogram_buckets_ms: Vec::new(),
+        deadlines_ms: HashMap::new(),
+        max_samples_per_op: 10_000,
+        channel_capacity: 10_000,
+        throughput_window_ms: 1000,
+        max_log_bytes: None,
+        max_log_files: 0,
+        max_sensor_breakdowns: 0,
     };
 
     let metrics: Arc<MetricsCollector> = Arc::new(MetricsCollector::new(&metrics_config));
 
-    let controller: Arc<Mutex<PIDController>> =
-        Arc::new(Mutex::new(PIDController::new(1.0, 0.1, 0.05)));
+    let controller: Arc<Mutex<PIDController>> = Arc::new(Mutex::new(PIDController::new(
+        config.gains.kp,
+        config.gains.ki,
+        config.gains.kd,
+    )));
     let executor: Arc<Executor> = Arc::new(Executor::new());
 
     let latest_sensor_data: Arc<Mutex<Option<SensorData>>> = Arc::new(Mutex::new(None));
@@ -28,47 +63,272 @@ pub async fn run_actuator_system(rx: Receiver<SensorData>, feedback_tx: Sender<A
     let sensor_data_clone = Arc::clone(&latest_sensor_data);
     let metrics_clone = Arc::clone(&metrics);
 
-    let mut receiver_task = ReceiverTask::new(rx, metrics_clone, sensor_data_clone);
+    let mut receiver_task = ReceiverTask::new(rx, metrics_clone, sensor_data_clone)
+        .with_shutdown(Arc::clone(&shutdown));
+    if let Some((heartbeat_rx, ack_tx)) = heartbeat {
+        receiver_task = receiver_task.with_heartbeat(heartbeat_rx, ack_tx);
+    }
 
-    std::thread::spawn(move || {
-        receiver_task.run();
-    });
+    let receiver_handle = std::thread::spawn(move || receiver_task.run());
 
     // === Scheduler to process control loop ===
-    let scheduler = Scheduler::new(5);
+    let scheduler = Scheduler::new(5).with_shutdown(shutdown);
     let controller_clone = Arc::clone(&controller);
     let executor_clone = Arc::clone(&executor);
     let feedback_tx_clone = feedback_tx.clone();
     let data_for_scheduler = Arc::clone(&latest_sensor_data);
 
+    // Ramp up from zero so the first control cycles don't see a step change
+    let mut setpoint_ramp = SetpointRamp::new(0.0, config.setpoint_ramp_rate);
+    setpoint_ramp.set_target(config.setpoint);
+
+    // A pushed setpoint takes over from any waveform
+    let mut setpoint_source = config.setpoint_source;
+    let mut has_setpoint = !matches!(setpoint_source, SetpointSource::External);
+    let started = Instant::now();
+
+    // Commands mapped to a sensor type give that type its own setpoint
+    let setpoint_commands = config.setpoint_commands.clone();
+    let mut typed_setpoints = TypedSetpoints::new(config.setpoint_ramp_rate);
+
+    let mut command_clamp = CommandClamp::new(config.command_limits.clone());
+    let execution_latency = config.execution_latency;
+    let delayed_executions = DelayedExecutions::new(
+        Handle::current(),
+        config.max_concurrent_executions,
+        metrics_tx,
+    );
+    let mut rng = SmallRng::from_entropy();
+
     scheduler.start(move || {
+        // Apply any pending runtime adjustments
+        while let Ok(control) = control_rx.try_recv() {
+            match control {
+                ActuatorControl::SetSetpoint(setpoint) => {
+                    setpoint_ramp.set_target(setpoint);
+                    setpoint_source = SetpointSource::Static;
+                    has_setpoint = true;
+                }
+                ActuatorControl::SetGains { kp, ki, kd } => {
+                    controller_clone.lock().unwrap().set_gains(kp, ki, kd)
+                }
+                ActuatorControl::Command(command) => {
+                    if let Some(&sensor_type) = setpoint_commands.get(&command.command_type) {
+                        typed_setpoints.set_target(
+                            sensor_type,
+                            command.value,
+                            setpoint_ramp.current(),
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(target) = setpoint_source.waveform_at(started.elapsed().as_secs_f64()) {
+            setpoint_ramp.set_target(target);
+        }
+        if !has_setpoint && typed_setpoints.is_empty() {
+            return;
+        }
+
         let maybe_data = data_for_scheduler.lock().unwrap().clone();
 
         if let Some(data) = maybe_data {
             let sensor_value = data.value;
-            let setpoint = 50.0;
             let dt = 0.005;
+            let default_setpoint = has_setpoint.then(|| setpoint_ramp.step(dt));
+            let Some(setpoint) = typed_setpoints
+                .step(dt, data.reading_type)
+                .or(default_setpoint)
+            else {
+                return;
+            };
 
             let mut ctrl = controller_clone.lock().unwrap();
-            let command = ctrl.compute(setpoint, sensor_value, dt);
-
-            let command_clone = command.clone();
-            executor_clone.execute(command_clone);
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards")
-                .as_millis();
-
-            let feedback = ActuatorFeedback {
-                timestamp,
-                actuator_id: "actuator_1".to_string(),
-                status: ActuatorStatus::Normal,
-                message: Some(format!(
-                    "Executed command {:?} for sensor {:.2}",
-                    command, sensor_value
-                )),
-            };
-            let _ = feedback_tx_clone.send(feedback);
+            let mut command = ctrl.compute(setpoint, sensor_value, dt);
+            command_clamp.apply(&mut command);
+
+            let delay = execution_latency.sample(&mut rng);
+            if delay.is_zero() {
+                execute_command(&executor_clone, command, sensor_value, &feedback_tx_clone);
+                return;
+            }
+
+            // Each command waits out its own delay, so a slow one doesn't hold up the loop
+            let executor = Arc::clone(&executor_clone);
+            let feedback_tx = feedback_tx_clone.clone();
+            delayed_executions.run_after(delay, move || {
+                execute_command(&executor, command, sensor_value, &feedback_tx)
+            });
         }
     });
+
+    // Wait for the receiver to finish its current reading before returning
+    match tokio::task::spawn_blocking(move || receiver_handle.join()).await {
+        Ok(Ok(rx)) if !rx.is_empty() => {
+            info!("{} unprocessed readings left queued for the actuator.", rx.len())
+        }
+        Ok(Ok(_)) => {}
+        _ => warn!("Actuator receiver thread panicked."),
+    }
+}
+
+// Commands waiting out their simulated latency, at most `limit` at a time. Over
+// the limit, run_after blocks the scheduler thread until a slot frees up, so
+// commands hold up the control loop rather than piling up as tasks.
+struct DelayedExecutions {
+    runtime: Handle,
+    permits: Option<Arc<Semaphore>>,
+    metrics_tx: MetricsSender,
+}
+
+impl DelayedExecutions {
+    fn new(runtime: Handle, limit: Option<usize>, metrics_tx: MetricsSender) -> Self {
+        Self {
+            runtime,
+            permits: limit.map(|limit| Arc::new(Semaphore::new(limit.max(1)))),
+            metrics_tx,
+        }
+    }
+
+    // Run `execute` on the runtime once `delay` has passed. Must not be called
+    // from inside the runtime, since it may block.
+    fn run_after(&self, delay: Duration, execute: impl FnOnce() + Send + 'static) {
+        let permit = self.permits.as_ref().map(|permits| {
+            let mut queue_wait = PerformanceMetrics::new("execution_queue_wait");
+            let permit = self
+                .runtime
+                .block_on(Arc::clone(permits).acquire_owned())
+                .expect("execution semaphore is never closed");
+            queue_wait.complete(true);
+            self.metrics_tx.send(queue_wait);
+            permit
+        });
+
+        self.runtime.spawn(async move {
+            let _permit = permit;
+            tokio::time::sleep(delay).await;
+            execute();
+        });
+    }
+}
+
+// Execute `command` and report it on the feedback channel
+fn execute_command(
+    executor: &Executor,
+    command: ControlCommand,
+    sensor_value: f64,
+    feedback_tx: &Sender<ActuatorFeedback>,
+) {
+    executor.execute(command.clone());
+    let timestamp = now_millis();
+
+    let feedback = ActuatorFeedback {
+        timestamp,
+        actuator_id: ActuatorId::new("actuator_1"),
+        status: ActuatorStatus::Normal,
+        message: Some(format!(
+            "Executed command {:?} for sensor {:.2}",
+            command, sensor_value
+        )),
+        command_id: None,
+    };
+    let _ = feedback_tx.send(feedback);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::metrics::metrics_channel;
+    use crate::config::ExecutionLatency;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Run `count` delayed executions that each stay busy for a while. Returns the
+    // most that were ever running at once and how many queue waits were reported.
+    fn peak_in_flight(limit: Option<usize>, count: usize) -> (usize, usize) {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(4)
+            .enable_all()
+            .build()
+            .unwrap();
+        let (metrics_tx, metrics_rx) = metrics_channel(64);
+        let executions = DelayedExecutions::new(runtime.handle().clone(), limit, metrics_tx);
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (done_tx, done_rx) = crossbeam_channel::unbounded();
+        for _ in 0..count {
+            let in_flight = Arc::clone(&in_flight);
+            let peak = Arc::clone(&peak);
+            let done_tx = done_tx.clone();
+            executions.run_after(Duration::from_millis(5), move || {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(30));
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                let _ = done_tx.send(());
+            });
+        }
+        for _ in 0..count {
+            done_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+
+        let mut queue_waits = 0;
+        while let Ok(metrics) = metrics_rx.try_recv() {
+            assert_eq!(metrics.operation, "execution_queue_wait");
+            queue_waits += 1;
+        }
+        (peak.load(Ordering::SeqCst), queue_waits)
+    }
+
+    #[test]
+    fn delayed_executions_stay_within_the_limit() {
+        let (peak, queue_waits) = peak_in_flight(Some(2), 6);
+        assert!(peak <= 2, "{} executions ran at once", peak);
+        assert_eq!(queue_waits, 6);
+    }
+
+    #[test]
+    fn unlimited_executions_overlap() {
+        let (peak, queue_waits) = peak_in_flight(None, 4);
+        assert!(peak > 2, "only {} executions ran at once", peak);
+        assert_eq!(queue_waits, 0);
+    }
+
+    #[test]
+    fn feedback_waits_at_least_the_minimum_latency() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+        let (metrics_tx, _metrics_rx) = metrics_channel(8);
+        let executions = DelayedExecutions::new(runtime.handle().clone(), None, metrics_tx);
+        let latency = ExecutionLatency::Uniform {
+            min_ms: 40,
+            max_ms: 60,
+        };
+        let mut rng = SmallRng::seed_from_u64(1);
+
+        let (feedback_tx, feedback_rx) = crossbeam_channel::unbounded();
+        let executor = Arc::new(Executor::new());
+        let command = ControlCommand {
+            command_type: "adjust_position".to_string(),
+            payload: None,
+            timestamp: 0,
+            value: 1.0,
+        };
+        let sent = Instant::now();
+        let delay = latency.sample(&mut rng);
+        executions.run_after(delay, move || {
+            execute_command(&executor, command, 0.0, &feedback_tx)
+        });
+
+        feedback_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(
+            sent.elapsed() >= Duration::from_millis(40),
+            "{:?}",
+            sent.elapsed()
+        );
+    }
 }
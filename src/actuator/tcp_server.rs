@@ -0,0 +1,465 @@
+use crate::actuator::dedup::{RecentCommands, DEFAULT_DEDUP_WINDOW};
+use crate::actuator::executor::Executor;
+use crate::common::clock::now_millis;
+use crate::common::data_types::{
+    ActuatorCommand, ActuatorFeedback, ActuatorId, ActuatorStatus, SensorData,
+};
+use crate::common::wire_format::SerializationFormat;
+use crate::config::Endpoint;
+use log::{info, warn};
+use serde::de::{Deserializer, SeqAccess, Visitor};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// Largest frame the server will buffer. Anything bigger is treated as a poison
+// message: there is no way to find the next frame boundary, so the connection
+// is rejected instead of stalling on it.
+const MAX_FRAME_LEN: usize = 1024 * 1024;
+
+// Wait after a failed accept before trying again; doubles while accepts keep failing
+const ACCEPT_BACKOFF_INITIAL: Duration = Duration::from_millis(10);
+
+// Longest wait between two accepts
+const ACCEPT_BACKOFF_MAX: Duration = Duration::from_secs(1);
+
+// Accept transmitter connections and execute the commands they send
+pub async fn run_actuator_tcp_server(
+    endpoint: &Endpoint,
+    format: SerializationFormat,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind((endpoint.host(), endpoint.port())).await?;
+    info!("Actuator TCP server listening on {} ({})", endpoint, format.name());
+
+    let executor = Arc::new(Executor::new());
+    // Shared across connections, since redeliveries usually follow a reconnect
+    let recent = Arc::new(Mutex::new(RecentCommands::new(DEFAULT_DEDUP_WINDOW)));
+
+    let mut accept_backoff = ACCEPT_BACKOFF_INITIAL;
+    loop {
+        // Accept errors (running out of file descriptors, a connection reset
+        // before it was accepted, ...) pass, so keep serving after them
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(
+                    "Failed to accept a connection, retrying in {:?}: {}",
+                    accept_backoff, e
+                );
+                tokio::time::sleep(accept_backoff).await;
+                accept_backoff = (accept_backoff * 2).min(ACCEPT_BACKOFF_MAX);
+                continue;
+            }
+        };
+        accept_backoff = ACCEPT_BACKOFF_INITIAL;
+        info!("Accepted transmitter connection from {}", peer);
+
+        let executor = Arc::clone(&executor);
+        let recent = Arc::clone(&recent);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, executor, recent, format).await {
+                warn!("Connection from {} closed with error: {}", peer, e);
+            }
+        });
+    }
+}
+
+// Read framed commands and answer each one with a feedback frame
+async fn handle_connection(
+    stream: TcpStream,
+    executor: Arc<Executor>,
+    recent: Arc<Mutex<RecentCommands>>,
+    format: SerializationFormat,
+) -> std::io::Result<()> {
+    let (mut reader, mut writer) = stream.into_split();
+    let mut buffer = Vec::new();
+    let mut temp_buf = [0u8; 1024];
+
+    loop {
+        while let Some(frame) = format.take_frame(&mut buffer) {
+            if frame.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            // A JSON line holding an array is a batch, answered per command. The
+            // whole array has to fit in one frame, so batches are capped at
+            // MAX_FRAME_LEN bytes like any other frame.
+            let feedbacks = if is_json_array(&frame, format) {
+                handle_batch(&frame, &executor, &recent)
+            } else {
+                vec![handle_frame(&frame, &executor, &recent, format)]
+            };
+            for feedback in &feedbacks {
+                write_feedback(&mut writer, feedback, format).await?;
+            }
+        }
+
+        if buffer.len() > MAX_FRAME_LEN {
+            let message = format!("Frame exceeds {} bytes, closing connection", MAX_FRAME_LEN);
+            warn!("{}", message);
+            let feedback = error_feedback(message);
+            write_feedback(&mut writer, &feedback, format).await?;
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "frame too large"));
+        }
+
+        let n = reader.read(&mut temp_buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&temp_buf[0..n]);
+    }
+}
+
+async fn write_feedback(
+    writer: &mut tokio::net::tcp::OwnedWriteHalf,
+    feedback: &ActuatorFeedback,
+    format: SerializationFormat,
+) -> std::io::Result<()> {
+    let response = format
+        .encode_frame(feedback)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    writer.write_all(&response).await
+}
+
+// Error feedback acts as the NACK: the frame is dropped (not retried) and the
+// connection moves on to the next one
+fn error_feedback(message: String) -> ActuatorFeedback {
+    ActuatorFeedback {
+        timestamp: now_millis(),
+        actuator_id: ActuatorId::new("unknown"),
+        status: ActuatorStatus::Error,
+        message: Some(message),
+        command_id: None,
+    }
+}
+
+fn handle_frame(
+    frame: &[u8],
+    executor: &Executor,
+    recent: &Mutex<RecentCommands>,
+    format: SerializationFormat,
+) -> ActuatorFeedback {
+    // send_data writes raw SensorData, so derive a command from it when needed
+    let command = format.decode::<ActuatorCommand>(frame).or_else(|_| {
+        format
+            .decode::<SensorData>(frame)
+            .map(|data| ActuatorCommand::from_sensor_data(&data))
+    });
+
+    match command {
+        Ok(command) => execute_command(command, executor, recent),
+        Err(e) => {
+            warn!("Dropping unparseable frame ({} bytes): {}", frame.len(), e);
+            error_feedback(format!("Invalid command: {}", e))
+        }
+    }
+}
+
+fn is_json_array(frame: &[u8], format: SerializationFormat) -> bool {
+    format == SerializationFormat::Json
+        && frame.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'[')
+}
+
+// Execute each command of a JSON array as it is parsed out of the frame, rather
+// than decoding the whole batch first. The frame itself is already buffered in
+// full. A malformed element ends the batch: the commands before it have run,
+// the rest get a single error feedback.
+fn handle_batch(
+    frame: &[u8],
+    executor: &Executor,
+    recent: &Mutex<RecentCommands>,
+) -> Vec<ActuatorFeedback> {
+    let mut feedbacks = Vec::new();
+    let visitor = BatchVisitor {
+        executor,
+        recent,
+        feedbacks: &mut feedbacks,
+    };
+    let result = serde_json::Deserializer::from_slice(frame).deserialize_seq(visitor);
+    if let Err(e) = result {
+        warn!(
+            "Dropping rest of command batch after {} command(s): {}",
+            feedbacks.len(),
+            e
+        );
+        feedbacks.push(error_feedback(format!("Invalid command batch: {}", e)));
+    }
+    feedbacks
+}
+
+struct BatchVisitor<'a> {
+    executor: &'a Executor,
+    recent: &'a Mutex<RecentCommands>,
+    feedbacks: &'a mut Vec<ActuatorFeedback>,
+}
+
+impl<'de> Visitor<'de> for BatchVisitor<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of actuator commands")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(command) = seq.next_element::<ActuatorCommand>()? {
+            let feedback = execute_command(command, self.executor, self.recent);
+            self.feedbacks.push(feedback);
+        }
+        Ok(())
+    }
+}
+
+fn execute_command(
+    command: ActuatorCommand,
+    executor: &Executor,
+    recent: &Mutex<RecentCommands>,
+) -> ActuatorFeedback {
+    // Acknowledge duplicates without executing them again. Commands
+    // without an id can't be deduplicated, so they always run.
+    if let Some(command_id) = &command.command_id {
+        if !recent.lock().unwrap().insert(command_id) {
+            info!("Skipping duplicate command {}", command_id);
+            return ActuatorFeedback {
+                timestamp: now_millis(),
+                actuator_id: command.actuator_id,
+                status: ActuatorStatus::Normal,
+                message: Some(format!("Duplicate command {} skipped", command_id)),
+                command_id: Some(command_id.clone()),
+            };
+        }
+    }
+
+    let message = format!(
+        "Executed {} command with value {:.2}",
+        command.control_command.command_type, command.control_command.value
+    );
+    let actuator_id = command.actuator_id.clone();
+    executor.execute(command.control_command);
+
+    ActuatorFeedback {
+        timestamp: now_millis(),
+        actuator_id,
+        status: ActuatorStatus::Normal,
+        message: Some(message),
+        command_id: command.command_id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data_types::{SensorId, SensorType};
+
+    fn reading(seq: u64) -> SensorData {
+        SensorData {
+            timestamp: 1_000 + seq as u128,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Force,
+            value: seq as f64,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        }
+    }
+
+    // Start a JSON server on a free local port and return its endpoint
+    async fn start_server() -> Endpoint {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let endpoint = Endpoint::new("127.0.0.1", port);
+        let server_endpoint = endpoint.clone();
+        tokio::spawn(async move {
+            run_actuator_tcp_server(&server_endpoint, SerializationFormat::Json).await
+        });
+        endpoint
+    }
+
+    async fn connect(endpoint: &Endpoint) -> TcpStream {
+        for _ in 0..100 {
+            if let Ok(stream) = TcpStream::connect((endpoint.host(), endpoint.port())).await {
+                return stream;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("actuator server never came up on {}", endpoint);
+    }
+
+    // Send one raw JSON line and read back `count` feedback lines
+    async fn exchange(stream: &mut TcpStream, line: &[u8], count: usize) -> Vec<ActuatorFeedback> {
+        let format = SerializationFormat::Json;
+        stream.write_all(line).await.unwrap();
+        stream.write_all(b"\n").await.unwrap();
+
+        let mut buffer = Vec::new();
+        let mut feedbacks = Vec::new();
+        while feedbacks.len() < count {
+            if let Some(frame) = format.take_frame(&mut buffer) {
+                feedbacks.push(format.decode(&frame).unwrap());
+                continue;
+            }
+            let mut chunk = [0u8; 1024];
+            let n = stream.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "server closed the connection");
+            buffer.extend_from_slice(&chunk[..n]);
+        }
+        feedbacks
+    }
+
+    async fn send_reading(stream: &mut TcpStream, seq: u64) -> ActuatorFeedback {
+        let line = serde_json::to_vec(&reading(seq)).unwrap();
+        exchange(stream, &line, 1).await.remove(0)
+    }
+
+    #[tokio::test]
+    async fn keeps_serving_after_a_connection_drops() {
+        let endpoint = start_server().await;
+
+        let mut first = connect(&endpoint).await;
+        let feedback = send_reading(&mut first, 1).await;
+        assert!(matches!(feedback.status, ActuatorStatus::Normal));
+        drop(first);
+
+        // The transmitter reconnects and picks up where it left off
+        let mut second = connect(&endpoint).await;
+        let feedback = send_reading(&mut second, 2).await;
+        assert!(matches!(feedback.status, ActuatorStatus::Normal));
+        assert_eq!(
+            feedback.message.as_deref(),
+            Some("Executed AdjustForce command with value 2.00")
+        );
+    }
+
+    #[tokio::test]
+    async fn redelivered_commands_run_once() {
+        let endpoint = start_server().await;
+        let mut stream = connect(&endpoint).await;
+
+        let first = send_reading(&mut stream, 10).await;
+        assert!(first.message.unwrap().starts_with("Executed"));
+
+        // Redelivered after a reconnect, e.g. from the transmitter's retry queue
+        drop(stream);
+        let mut stream = connect(&endpoint).await;
+        let again = send_reading(&mut stream, 10).await;
+        assert!(matches!(again.status, ActuatorStatus::Normal));
+        assert_eq!(
+            again.message.as_deref(),
+            Some("Duplicate command force_1#10 skipped")
+        );
+    }
+
+    #[tokio::test]
+    async fn readings_in_the_same_millisecond_both_run() {
+        let endpoint = start_server().await;
+        let mut stream = connect(&endpoint).await;
+
+        for seq in [20, 21] {
+            let mut data = reading(seq);
+            data.timestamp = 5_000;
+            let line = serde_json::to_vec(&data).unwrap();
+            let feedback = exchange(&mut stream, &line, 1).await.remove(0);
+            assert!(feedback.message.unwrap().starts_with("Executed"));
+        }
+    }
+
+    fn batch(commands: &[serde_json::Value]) -> Vec<u8> {
+        serde_json::to_vec(commands).unwrap()
+    }
+
+    fn command(seq: u64) -> serde_json::Value {
+        serde_json::to_value(ActuatorCommand::from_sensor_data(&reading(seq))).unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_batch_runs_every_command_in_order() {
+        let endpoint = start_server().await;
+        let mut stream = connect(&endpoint).await;
+
+        let line = batch(&[command(30), command(31)]);
+        let feedbacks = exchange(&mut stream, &line, 2).await;
+        let messages: Vec<_> = feedbacks.into_iter().map(|f| f.message.unwrap()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Executed AdjustForce command with value 30.00",
+                "Executed AdjustForce command with value 31.00",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_malformed_element_ends_the_batch() {
+        let endpoint = start_server().await;
+        let mut stream = connect(&endpoint).await;
+
+        let line = batch(&[command(40), serde_json::json!(42), command(41)]);
+        let feedbacks = exchange(&mut stream, &line, 2).await;
+        assert!(matches!(feedbacks[0].status, ActuatorStatus::Normal));
+        assert!(matches!(feedbacks[1].status, ActuatorStatus::Error));
+
+        // The connection is still usable, and the command after the bad one never ran
+        let feedback = send_reading(&mut stream, 41).await;
+        assert!(feedback.message.unwrap().starts_with("Executed"));
+    }
+
+    #[tokio::test]
+    async fn commands_are_answered_line_by_line() {
+        let endpoint = start_server().await;
+        let mut stream = connect(&endpoint).await;
+
+        let line = serde_json::to_vec(&command(50)).unwrap();
+        let feedback = exchange(&mut stream, &line, 1).await.remove(0);
+        assert_eq!(feedback.actuator_id, SensorId::new("force_1").actuator());
+        assert_eq!(
+            feedback.message.as_deref(),
+            Some("Executed AdjustForce command with value 50.00")
+        );
+
+        // A bad line is NACKed and dropped, and the next one still runs
+        let feedback = exchange(&mut stream, b"not json", 1).await.remove(0);
+        assert!(matches!(feedback.status, ActuatorStatus::Error));
+        let feedback = send_reading(&mut stream, 51).await;
+        assert!(matches!(feedback.status, ActuatorStatus::Normal));
+    }
+
+    #[tokio::test]
+    async fn an_oversized_frame_is_rejected_and_the_connection_closed() {
+        let endpoint = start_server().await;
+        let mut stream = connect(&endpoint).await;
+
+        // One byte over the limit and no newline. The server reads all of it,
+        // so it closes cleanly rather than resetting the connection.
+        stream
+            .write_all(&vec![b'x'; MAX_FRAME_LEN + 1])
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let format = SerializationFormat::Json;
+        let frame = format.take_frame(&mut response).unwrap();
+        assert!(response.is_empty());
+        let feedback: ActuatorFeedback = format.decode(&frame).unwrap();
+        assert!(matches!(feedback.status, ActuatorStatus::Error));
+        assert_eq!(
+            feedback.message,
+            Some(format!(
+                "Frame exceeds {} bytes, closing connection",
+                MAX_FRAME_LEN
+            ))
+        );
+
+        // Other connections are unaffected
+        let mut stream = connect(&endpoint).await;
+        let feedback = send_reading(&mut stream, 60).await;
+        assert!(matches!(feedback.status, ActuatorStatus::Normal));
+    }
+}
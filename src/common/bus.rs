@@ -0,0 +1,157 @@
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Subscribers and live publishers of one message type
+struct Topic<T> {
+    subscribers: Mutex<Vec<Sender<T>>>,
+    publishers: AtomicUsize,
+}
+
+// Typed in-process publish/subscribe over crossbeam channels. Every subscriber
+// of a type gets its own bounded channel and a copy of each message published
+// for that type. Subscribers see their channel close once every publisher of
+// the type has been dropped, so pipelines wind down as they do with plain channels.
+pub struct Bus {
+    capacity: usize,
+    topics: Mutex<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+}
+
+impl Bus {
+    // Each subscriber buffers up to `capacity` unread messages
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Receive every `T` published from now on
+    pub fn subscribe<T: Clone + Send + 'static>(&self) -> Receiver<T> {
+        let (tx, rx) = bounded(self.capacity);
+        self.topic::<T>().subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    // Handle for publishing `T`s to the current and future subscribers
+    pub fn publisher<T: Clone + Send + 'static>(&self) -> Publisher<T> {
+        let topic = self.topic::<T>();
+        topic.publishers.fetch_add(1, Ordering::Relaxed);
+        Publisher { topic }
+    }
+
+    fn topic<T: Clone + Send + 'static>(&self) -> Arc<Topic<T>> {
+        let mut topics = self.topics.lock().unwrap();
+        let topic = topics.entry(TypeId::of::<T>()).or_insert_with(|| {
+            Box::new(Arc::new(Topic::<T> {
+                subscribers: Mutex::new(Vec::new()),
+                publishers: AtomicUsize::new(0),
+            }))
+        });
+        Arc::clone(
+            topic
+                .downcast_ref::<Arc<Topic<T>>>()
+                .expect("topics are keyed by their message type"),
+        )
+    }
+}
+
+// Publishes `T`s on a Bus. Cloning adds a publisher; the type's subscribers are
+// disconnected when the last one is dropped.
+pub struct Publisher<T> {
+    topic: Arc<Topic<T>>,
+}
+
+impl<T: Clone> Publisher<T> {
+    // Send `message` to every subscriber, waiting on any that are full. Dropped
+    // subscribers are forgotten; the message comes back if none are left.
+    pub fn publish(&self, message: T) -> Result<(), T> {
+        // Send outside the lock, so a full subscriber doesn't hold up subscribe()
+        // or the type's other publishers
+        let subscribers = self.topic.subscribers.lock().unwrap().clone();
+        let closed: Vec<_> = subscribers
+            .iter()
+            .filter(|tx| tx.send(message.clone()).is_err())
+            .collect();
+        if !closed.is_empty() {
+            self.topic
+                .subscribers
+                .lock()
+                .unwrap()
+                .retain(|tx| !closed.iter().any(|c| c.same_channel(tx)));
+        }
+        if closed.len() == subscribers.len() {
+            return Err(message);
+        }
+        Ok(())
+    }
+}
+
+impl<T> Clone for Publisher<T> {
+    fn clone(&self) -> Self {
+        self.topic.publishers.fetch_add(1, Ordering::Relaxed);
+        Self {
+            topic: Arc::clone(&self.topic),
+        }
+    }
+}
+
+impl<T> Drop for Publisher<T> {
+    fn drop(&mut self) {
+        if self.topic.publishers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.topic.subscribers.lock().unwrap().clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn one_publish_reaches_every_subscriber() {
+        let bus = Bus::new(4);
+        let first = bus.subscribe::<u32>();
+        let second = bus.subscribe::<u32>();
+        let publisher = bus.publisher::<u32>();
+
+        publisher.publish(7).unwrap();
+        assert_eq!(first.try_recv(), Ok(7));
+        assert_eq!(second.try_recv(), Ok(7));
+
+        // Once one subscriber is gone the other still gets everything
+        drop(first);
+        publisher.publish(8).unwrap();
+        assert_eq!(second.try_recv(), Ok(8));
+
+        drop(publisher);
+        assert!(second.recv().is_err());
+    }
+
+    #[test]
+    fn full_subscriber_does_not_block_subscribing() {
+        let bus = Arc::new(Bus::new(1));
+        let full = bus.subscribe::<u32>();
+        let publisher = bus.publisher::<u32>();
+        publisher.publish(1).unwrap();
+
+        // This publish waits for `full` to make room
+        let blocked = std::thread::spawn(move || publisher.publish(2));
+        std::thread::sleep(Duration::from_millis(50));
+
+        let (done_tx, done_rx) = crossbeam_channel::bounded(1);
+        let subscriber = Arc::clone(&bus);
+        std::thread::spawn(move || done_tx.send(subscriber.subscribe::<u32>()).unwrap());
+        let late = done_rx
+            .recv_timeout(Duration::from_secs(5))
+            .expect("subscribe blocked behind a full subscriber");
+
+        assert_eq!(full.recv(), Ok(1));
+        assert_eq!(full.recv(), Ok(2));
+        blocked.join().unwrap().unwrap();
+        assert!(late.try_recv().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Source of time for anything that timestamps readings or measures durations
+pub trait Clock: Send + Sync {
+    // Monotonic time, for durations and deadlines
+    fn now(&self) -> Instant;
+
+    // Wall-clock time in milliseconds since the Unix epoch, for timestamps
+    fn epoch_ms(&self) -> u128;
+}
+
+// The system clocks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn epoch_ms(&self) -> u128 {
+        now_millis()
+    }
+}
+
+// Current wall-clock time in milliseconds since the Unix epoch, the unit of
+// every timestamp in the system
+pub fn now_millis() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis()
+}
+
+// Clock that only moves when told to. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    start_epoch_ms: u128,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl MockClock {
+    // Starts at `start_epoch_ms` on the wall clock
+    pub fn new(start_epoch_ms: u128) -> Self {
+        Self {
+            start: Instant::now(),
+            start_epoch_ms,
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.elapsed.lock().unwrap() += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+
+    fn epoch_ms(&self) -> u128 {
+        self.start_epoch_ms + self.elapsed.lock().unwrap().as_millis()
+    }
+}
+
+// Shared handle to the system clock
+pub fn real_clock() -> Arc<dyn Clock> {
+    Arc::new(RealClock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_clock_only_moves_when_advanced() {
+        let clock = MockClock::new(1_000);
+        let start = clock.now();
+        assert_eq!(clock.epoch_ms(), 1_000);
+
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(clock.now() - start, Duration::from_millis(250));
+        assert_eq!(clock.epoch_ms(), 1_250);
+    }
+
+    #[test]
+    fn mock_clock_clones_share_time() {
+        let clock = MockClock::new(0);
+        let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(shared.epoch_ms(), 2_000);
+        assert_eq!(shared.now(), clock.now());
+    }
+
+    #[test]
+    fn now_millis_agrees_with_chrono() {
+        let chrono_ms = chrono::Utc::now().timestamp_millis() as u128;
+        let ours = now_millis();
+        assert!(
+            ours.abs_diff(chrono_ms) < 1_000,
+            "{} vs {}",
+            ours,
+            chrono_ms
+        );
+        assert!(RealClock.epoch_ms().abs_diff(ours) < 1_000);
+    }
+}
@@ -0,0 +1,228 @@
+use super::data_types::{ControlCommand, SensorId, SensorType};
+use super::metrics::SharedReport;
+use crate::config::{Config, PidGains};
+use crate::sensor::anomaly_log::AnomalyLog;
+use crate::sensor::processor::{default_thresholds, ProcessorSnapshot, SharedSensorStats};
+use crate::sensor::transmitter::SharedQueueDepth;
+use crossbeam_channel::Sender;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+// Runtime adjustments pushed into a running processor
+#[derive(Debug)]
+pub enum ProcessorControl {
+    SetThreshold {
+        sensor_type: SensorType,
+        threshold: f64,
+    },
+    SetWindowSize(usize),
+    // Clear filter state for one sensor_id, or for every sensor if None
+    Reset(Option<SensorId>),
+    // Reply with the current per-sensor state
+    Snapshot(oneshot::Sender<ProcessorSnapshot>),
+}
+
+impl ProcessorControl {
+    // Copy of an adjustment, for broadcasting it to several processors. None
+    // for snapshot requests, whose reply channel can only be used once.
+    pub fn try_clone(&self) -> Option<Self> {
+        match self {
+            ProcessorControl::SetThreshold {
+                sensor_type,
+                threshold,
+            } => Some(ProcessorControl::SetThreshold {
+                sensor_type: *sensor_type,
+                threshold: *threshold,
+            }),
+            ProcessorControl::SetWindowSize(window_size) => {
+                Some(ProcessorControl::SetWindowSize(*window_size))
+            }
+            ProcessorControl::Reset(sensor_id) => Some(ProcessorControl::Reset(sensor_id.clone())),
+            ProcessorControl::Snapshot(_) => None,
+        }
+    }
+}
+
+// Runtime adjustments pushed into a running actuator control loop
+#[derive(Debug, Clone)]
+pub enum ActuatorControl {
+    SetSetpoint(f64),
+    SetGains { kp: f64, ki: f64, kd: f64 },
+    // A command received by the actuator, which may move a setpoint
+    Command(ControlCommand),
+}
+
+// What reloading the config means for a running pipeline
+#[derive(Debug, Default)]
+pub struct ConfigReload {
+    pub processor: Vec<ProcessorControl>,
+    pub actuator: Vec<ActuatorControl>,
+    pub ignored: Vec<String>, // Changed fields that only take effect on restart
+}
+
+impl ConfigReload {
+    // Compare the running config with a reloaded one. Window size, thresholds, the
+    // setpoint and gains become adjustments; every other change is left for a restart.
+    pub fn plan(running: &Config, reloaded: &Config) -> Self {
+        let mut reload = ConfigReload::default();
+        for field in running.changed_fields(reloaded) {
+            if let Some(name) = field.strip_prefix("processor.thresholds.") {
+                if let Ok(sensor_type) = name.parse::<SensorType>() {
+                    // A removed entry falls back to the type's default
+                    let threshold = reloaded
+                        .processor
+                        .thresholds
+                        .get(&sensor_type)
+                        .or(default_thresholds().get(&sensor_type))
+                        .copied();
+                    if let Some(threshold) = threshold {
+                        reload.processor.push(ProcessorControl::SetThreshold {
+                            sensor_type,
+                            threshold,
+                        });
+                        continue;
+                    }
+                }
+            }
+            match field.as_str() {
+                "processor.window_size" => reload
+                    .processor
+                    .push(ProcessorControl::SetWindowSize(reloaded.processor.window_size)),
+                "actuator.setpoint" => reload
+                    .actuator
+                    .push(ActuatorControl::SetSetpoint(reloaded.actuator.setpoint)),
+                // One adjustment sets all three gains
+                "actuator.gains.kp" | "actuator.gains.ki" | "actuator.gains.kd" => {
+                    let gains = reloaded.actuator.gains;
+                    let control = ActuatorControl::SetGains {
+                        kp: gains.kp,
+                        ki: gains.ki,
+                        kd: gains.kd,
+                    };
+                    if !reload
+                        .actuator
+                        .iter()
+                        .any(|c| matches!(c, ActuatorControl::SetGains { .. }))
+                    {
+                        reload.actuator.push(control);
+                    }
+                }
+                _ => reload.ignored.push(field),
+            }
+        }
+        reload
+    }
+
+    // Record the adjustments in `config`, so it describes the running pipeline again
+    pub fn apply_to(&self, config: &mut Config) {
+        for control in &self.processor {
+            match control {
+                ProcessorControl::SetThreshold {
+                    sensor_type,
+                    threshold,
+                } => {
+                    config.processor.thresholds.insert(*sensor_type, *threshold);
+                }
+                ProcessorControl::SetWindowSize(window_size) => {
+                    config.processor.window_size = *window_size
+                }
+                ProcessorControl::Reset(_) | ProcessorControl::Snapshot(_) => {}
+            }
+        }
+        for control in &self.actuator {
+            match control {
+                ActuatorControl::SetSetpoint(setpoint) => config.actuator.setpoint = *setpoint,
+                ActuatorControl::SetGains { kp, ki, kd } => {
+                    config.actuator.gains = PidGains {
+                        kp: *kp,
+                        ki: *ki,
+                        kd: *kd,
+                    }
+                }
+                ActuatorControl::Command(_) => {}
+            }
+        }
+    }
+}
+
+// Everything needed to inspect and retune a running pipeline
+#[derive(Clone)]
+pub struct ControlHandles {
+    pub config: Arc<Mutex<Config>>,
+    pub latest_report: SharedReport,
+    pub anomaly_log: AnomalyLog,
+    pub sensor_stats: SharedSensorStats,
+    pub retry_queue_depth: SharedQueueDepth,
+    pub processor_tx: Sender<ProcessorControl>,
+    pub actuator_tx: Sender<ActuatorControl>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Endpoint;
+
+    #[test]
+    fn reload_plan_adjusts_live_fields_and_lists_the_rest() {
+        let running = Config::default();
+        let mut reloaded = running.clone();
+        reloaded.processor.window_size = 30;
+        reloaded.processor.thresholds.insert(SensorType::Force, 4.0);
+        reloaded.actuator.setpoint = 60.0;
+        reloaded.actuator.gains.kp = 2.0;
+        reloaded.actuator.gains.kd = 0.5;
+        reloaded.sensor.num_sensors = 5;
+        reloaded.transmitter.endpoint = Endpoint::new("10.0.0.2", 9000);
+
+        let reload = ConfigReload::plan(&running, &reloaded);
+        assert_eq!(reload.processor.len(), 2);
+        assert!(reload
+            .processor
+            .iter()
+            .any(|control| matches!(control, ProcessorControl::SetWindowSize(30))));
+        assert!(reload.processor.iter().any(|control| matches!(
+            control,
+            ProcessorControl::SetThreshold {
+                sensor_type: SensorType::Force,
+                threshold,
+            } if *threshold == 4.0
+        )));
+        // Both gain changes become one adjustment
+        assert_eq!(reload.actuator.len(), 2);
+        assert!(reload.actuator.iter().any(|control| matches!(
+            control,
+            ActuatorControl::SetSetpoint(setpoint) if *setpoint == 60.0
+        )));
+        assert!(reload.actuator.iter().any(|control| matches!(
+            control,
+            ActuatorControl::SetGains { kp, ki, kd } if (*kp, *ki, *kd) == (2.0, 0.1, 0.5)
+        )));
+        let mut ignored = reload.ignored.clone();
+        ignored.sort();
+        assert_eq!(ignored, ["sensor.num_sensors", "transmitter.endpoint"]);
+
+        // Applied, the running config differs only in what needs a restart
+        let mut applied = running.clone();
+        reload.apply_to(&mut applied);
+        assert_eq!(applied.changed_fields(&reloaded), ignored);
+    }
+
+    #[test]
+    fn removed_threshold_reloads_as_the_default() {
+        let mut running = Config::default();
+        running.processor.thresholds.insert(SensorType::Force, 4.0);
+        let mut reloaded = running.clone();
+        reloaded.processor.thresholds.remove(&SensorType::Force);
+
+        let reload = ConfigReload::plan(&running, &reloaded);
+        let default = default_thresholds()[&SensorType::Force];
+        assert!(matches!(
+            reload.processor.as_slice(),
+            [ProcessorControl::SetThreshold {
+                sensor_type: SensorType::Force,
+                threshold,
+            }] if *threshold == default
+        ));
+        assert!(reload.ignored.is_empty());
+    }
+}
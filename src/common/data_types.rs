@@ -1,34 +1,167 @@
+use crate::common::clock::{now_millis, Clock, RealClock};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
 use std::time::Instant;
 
+// Actuator ids derived from a sensor id are this prefix followed by the sensor id
+const ACTUATOR_FOR_PREFIX: &str = "actuator_for_";
+
+// Identifies a sensor, e.g. "sensor_0". Serialized as a plain string.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(transparent)]
+pub struct SensorId(String);
+
+// Identifies an actuator, e.g. "actuator_for_sensor_0". Serialized as a plain string.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(transparent)]
+pub struct ActuatorId(String);
+
+impl SensorId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    // Actuator that acts on this sensor's readings
+    pub fn actuator(&self) -> ActuatorId {
+        ActuatorId(format!("{}{}", ACTUATOR_FOR_PREFIX, self.0))
+    }
+}
+
+impl ActuatorId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    // Sensor this actuator acts for, if its id came from SensorId::actuator.
+    // None for actuators named otherwise (e.g. "actuator_1").
+    pub fn sensor(&self) -> Option<SensorId> {
+        self.0.strip_prefix(ACTUATOR_FOR_PREFIX).map(SensorId::new)
+    }
+}
+
+// Both ids behave as strings wherever one is read, and parse from any string
+macro_rules! string_id {
+    ($id:ident) => {
+        impl fmt::Display for $id {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $id {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self(s.to_string()))
+            }
+        }
+
+        impl Deref for $id {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        // Lets maps keyed by id be looked up with a &str
+        impl Borrow<str> for $id {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<&str> for $id {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<String> for $id {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+    };
+}
+
+string_id!(SensorId);
+string_id!(ActuatorId);
+
 // Main data structure for sensor readings
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SensorData {
     pub timestamp: u128,          // Timestamp in milliseconds
-    pub sensor_id: String,        // Unique identifier for the sensor
+    pub sensor_id: SensorId,      // Unique identifier for the sensor
     pub reading_type: SensorType, // Type of sensor
     pub value: f64,               // Actual sensor reading
     pub is_anomaly: bool,         // Flag for anomalies
     pub confidence: f64,          // Confidence level (0.0-1.0)
+    #[serde(default)]
+    pub confidence_lower: Option<f64>, // Lower edge of the processor's band (mean - k·σ)
+    #[serde(default)]
+    pub confidence_upper: Option<f64>, // Upper edge of the processor's band (mean + k·σ)
+    #[serde(default)]
+    pub seq: u64, // Per-sensor sequence number from 1, 0 if the source doesn't number readings
+    #[serde(default)]
+    pub rejected: bool, // Value impossible for its reading_type, left out of the statistics
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ControlCommand {
     pub command_type: String,
-    pub payload: Option<String>,
+    pub payload: Option<CommandPayload>,
     pub timestamp: u128,
     pub value: f64,
 }
 
-#[derive(Debug, Clone)]
+// Structured command parameters. A typed struct rather than a free-form JSON
+// value so the binary wire formats can encode it too.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CommandPayload {
+    #[serde(default)]
+    pub value: Option<f64>, // Reading the command was derived from
+    #[serde(default)]
+    pub target: Option<String>, // What the actuator should adjust toward
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ActuatorCommand {
-    pub actuator_id: String,
+    pub actuator_id: ActuatorId,
     pub control_command: ControlCommand,
     pub priority: u8,
-    pub deadline: Instant,
+    #[serde(with = "deadline_ms")]
+    #[schemars(with = "u64")]
+    pub deadline: Instant, // Milliseconds remaining on the wire
+    #[serde(default)]
+    pub command_id: Option<String>, // Stable across redeliveries, used to skip duplicates
+}
+
+// Instant has no fixed epoch, so deadlines travel as milliseconds remaining
+mod deadline_ms {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::{Duration, Instant};
+
+    pub fn serialize<S: Serializer>(deadline: &Instant, serializer: S) -> Result<S::Ok, S::Error> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        serializer.serialize_u64(remaining.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Instant, D::Error> {
+        let remaining = u64::deserialize(deserializer)?;
+        Ok(Instant::now() + Duration::from_millis(remaining))
+    }
 }
 
 // Types of sensors we might simulate
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
 pub enum SensorType {
     Force,       // Force sensor (Newtons)
     Position,    // Position sensor (mm)
@@ -36,16 +169,71 @@ pub enum SensorType {
     Temperature, // Temperature sensor (Celsius)
 }
 
+impl SensorType {
+    pub const ALL: [SensorType; 4] = [
+        SensorType::Force,
+        SensorType::Position,
+        SensorType::Velocity,
+        SensorType::Temperature,
+    ];
+
+    // Typical base value, noise level, drift factor and unit for simulating this type
+    pub fn default_params(&self) -> (f64, f64, f64, &'static str) {
+        match self {
+            SensorType::Force => (10.0, 0.2, 0.01, "N"),
+            SensorType::Position => (100.0, 0.5, 0.005, "mm"),
+            SensorType::Velocity => (50.0, 0.3, 0.005, "mm/s"),
+            SensorType::Temperature => (25.0, 0.1, 0.002, "°C"),
+        }
+    }
+
+    // Whether `value` could physically come from this type of sensor. Anything
+    // else is corruption (e.g. from the transport), not a measurement.
+    pub fn is_plausible(&self, value: f64) -> bool {
+        value.is_finite()
+            && match self {
+                SensorType::Temperature => value >= ABSOLUTE_ZERO_C,
+                SensorType::Force | SensorType::Position | SensorType::Velocity => true,
+            }
+    }
+
+    // Lowercase name accepted on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            SensorType::Force => "force",
+            SensorType::Position => "position",
+            SensorType::Velocity => "velocity",
+            SensorType::Temperature => "temperature",
+        }
+    }
+}
+
+impl std::str::FromStr for SensorType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SensorType::ALL
+            .into_iter()
+            .find(|sensor_type| sensor_type.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let names: Vec<_> = SensorType::ALL.iter().map(|t| t.name()).collect();
+                format!("unknown sensor type '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
+
 // Feedback from the actuator system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ActuatorFeedback {
     pub timestamp: u128,
-    pub actuator_id: String,
+    pub actuator_id: ActuatorId,
     pub status: ActuatorStatus,
     pub message: Option<String>,
+    #[serde(default)]
+    pub command_id: Option<String>, // Id of the command this answers, if it had one
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
 pub enum ActuatorStatus {
     Normal,
     Adjusting,
@@ -53,6 +241,31 @@ pub enum ActuatorStatus {
     Error,
 }
 
+// JSON Schema documents for the types exchanged between the sensor and
+// actuator systems, keyed by type name
+pub fn wire_schemas() -> serde_json::Map<String, serde_json::Value> {
+    let schemas = [
+        ("SensorData", schemars::schema_for!(SensorData)),
+        ("ActuatorCommand", schemars::schema_for!(ActuatorCommand)),
+        ("ActuatorFeedback", schemars::schema_for!(ActuatorFeedback)),
+    ];
+    schemas
+        .into_iter()
+        .map(|(name, schema)| {
+            let schema = serde_json::to_value(schema).expect("schemas serialize to JSON");
+            (name.to_string(), schema)
+        })
+        .collect()
+}
+
+// Link liveness probe sent by the sensor side and echoed back unchanged by the
+// actuator. Only travels in-process, so it carries an Instant.
+#[derive(Debug, Clone, Copy)]
+pub struct Heartbeat {
+    pub sequence: u64,
+    pub sent_at: Instant,
+}
+
 // Metrics for performance benchmarking
 #[derive(Debug, Clone)]
 pub struct PerformanceMetrics {
@@ -61,57 +274,147 @@ pub struct PerformanceMetrics {
     pub end_time: Option<Instant>,
     pub duration_ms: Option<f64>,
     pub success: bool,
+    pub attempts: usize, // How many tries the operation took
+    pub error_kind: Option<String>, // Why the operation failed, when a reason was given
+    pub label: Option<String>, // What the operation was for (e.g. a sensor_id), to break stats down by
+    pub count: usize, // Occurrences this sample stands for, e.g. readings lost in one gap
 }
 
 impl PerformanceMetrics {
     pub fn new(operation: &str) -> Self {
+        Self::new_with_clock(operation, &RealClock)
+    }
+
+    // Start timing against `clock` rather than the system clock
+    pub fn new_with_clock(operation: &str, clock: &dyn Clock) -> Self {
         Self {
             operation: operation.to_string(),
-            start_time: Instant::now(),
+            start_time: clock.now(),
             end_time: None,
             duration_ms: None,
             success: false,
+            attempts: 1,
+            error_kind: None,
+            label: None,
+            count: 1,
         }
     }
 
+    // Tag the operation with what it was for, e.g. the sensor it handled
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    // Stand for `count` occurrences of the operation rather than one
+    pub fn with_count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
     pub fn complete(&mut self, success: bool) {
-        let end = Instant::now();
+        self.complete_with_clock(success, &RealClock);
+    }
+
+    // Stop timing against `clock`; use the same clock the metrics were started with
+    pub fn complete_with_clock(&mut self, success: bool, clock: &dyn Clock) {
+        let end = clock.now();
         self.end_time = Some(end);
         self.duration_ms = Some((end - self.start_time).as_secs_f64() * 1000.0);
         self.success = success;
     }
+
+    // Stop timing as a failure, recording why it failed (e.g. "connect_timeout")
+    pub fn complete_with_error(&mut self, error_kind: &str) {
+        self.complete(false);
+        self.error_kind = Some(error_kind.to_string());
+    }
+}
+
+// Serializable form of PerformanceMetrics for shipping off-box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsRecord {
+    pub operation: String,
+    pub start_time_ms: u128,       // Epoch milliseconds
+    pub end_time_ms: Option<u128>, // Epoch milliseconds
+    pub duration_ms: Option<f64>,
+    pub success: bool,
+    pub attempts: usize,
+    #[serde(default)]
+    pub error_kind: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default = "default_count")]
+    pub count: usize,
+}
+
+fn default_count() -> usize {
+    1
+}
+
+impl From<&PerformanceMetrics> for MetricsRecord {
+    fn from(metrics: &PerformanceMetrics) -> Self {
+        // Instant has no epoch, so anchor it against the current wall-clock time
+        let now = Instant::now();
+        let now_ms = now_millis();
+        let to_epoch_ms = |instant: Instant| {
+            now_ms.saturating_sub(now.saturating_duration_since(instant).as_millis())
+        };
+
+        Self {
+            operation: metrics.operation.clone(),
+            start_time_ms: to_epoch_ms(metrics.start_time),
+            end_time_ms: metrics.end_time.map(to_epoch_ms),
+            duration_ms: metrics.duration_ms,
+            success: metrics.success,
+            attempts: metrics.attempts,
+            error_kind: metrics.error_kind.clone(),
+            label: metrics.label.clone(),
+            count: metrics.count,
+        }
+    }
 }
 
+// Standard deviations below this fraction of the mean's magnitude (or of 1.0 for
+// means near zero) are treated as zero. A constant signal leaves a rounding
+// residue in the running variance, and scoring against it flags every reading.
+pub const MIN_RELATIVE_STD_DEV: f64 = 1e-9;
+
+// Lowest possible temperature reading, in Celsius
+pub const ABSOLUTE_ZERO_C: f64 = -273.15;
+
 impl SensorData {
+    // Unique id for this reading, and for commands derived from it, built from
+    // the per-sensor sequence number. Timestamps can repeat within a millisecond,
+    // so None when the source doesn't number its readings (seq 0).
+    pub fn reading_id(&self) -> Option<String> {
+        (self.seq > 0).then(|| format!("{}#{}", self.sensor_id, self.seq))
+    }
+
     /// Detects if the value is anomalous based on z-score and thresholds.
-    /// Requires mean and std_dev to calculate z-score.
-    pub fn detect_anomaly(&mut self, mean: f64, std_dev: f64, threshold: f64) {
-        if std_dev > 0.0 {
+    /// Requires mean and std_dev to calculate z-score, which is returned
+    /// (0.0 when std_dev is effectively zero, see MIN_RELATIVE_STD_DEV).
+    pub fn detect_anomaly(&mut self, mean: f64, std_dev: f64, threshold: f64) -> f64 {
+        if std_dev > MIN_RELATIVE_STD_DEV * mean.abs().max(1.0) {
             let z_score = (self.value - mean).abs() / std_dev;
             self.is_anomaly = z_score > threshold;
 
             let mut confidence = 1.0 - (z_score / (threshold * 2.0)).min(0.9);
             confidence = confidence.max(0.1);
 
-            if self.is_anomaly {
-                println!(
-                    "[ANOMALY] Sensor: {}, Value: {:.2}, Mean: {:.2}, StdDev: {:.2}, Z-score: {:.2}, Confidence: {:.2}",
-                    self.sensor_id, self.value, mean, std_dev, z_score, confidence
-                );
-            }
-
             self.confidence = confidence;
+            z_score
         } else {
             self.is_anomaly = false;
             self.confidence = 0.0;
+            0.0
         }
     }
 }
 
 impl ActuatorCommand {
     pub fn from_sensor_data(data: &SensorData) -> Self {
-        // Determine actuator_id from sensor_id (example logic)
-        let actuator_id = format!("actuator_for_{}", data.sensor_id);
+        let actuator_id = data.sensor_id.actuator();
 
         // Example: command_type depends on sensor reading type
         let command_type = match data.reading_type {
@@ -122,9 +425,10 @@ impl ActuatorCommand {
         }
         .to_string();
 
-        // Payload could be some JSON or string representing the command parameters,
-        // here we just serialize the value as string for simplicity
-        let payload = Some(format!("{{\"value\": {:.2}}}", data.value));
+        let payload = Some(CommandPayload {
+            value: Some(data.value),
+            target: None,
+        });
 
         // Set priority higher if anomaly detected, else default 5
         let priority = if data.is_anomaly { 10 } else { 5 };
@@ -140,6 +444,7 @@ impl ActuatorCommand {
         };
 
         ActuatorCommand {
+            command_id: data.reading_id(),
             actuator_id,
             control_command,
             priority,
@@ -147,3 +452,147 @@ impl ActuatorCommand {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(seq: u64, timestamp: u128) -> SensorData {
+        SensorData {
+            timestamp,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Force,
+            value: 1.0,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        }
+    }
+
+    #[test]
+    fn sensor_to_actuator_mapping_round_trips() {
+        let sensor = SensorId::new("sensor_0");
+        let actuator = sensor.actuator();
+        assert_eq!(actuator.to_string(), "actuator_for_sensor_0");
+        assert_eq!(actuator, sensor.actuator());
+        assert_eq!(actuator.sensor(), Some(sensor));
+
+        // Through serde and FromStr, as ids arrive off the wire and the CLI
+        let json = serde_json::to_string(&actuator).unwrap();
+        assert_eq!(json, "\"actuator_for_sensor_0\"");
+        let parsed: ActuatorId = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.sensor(), Some(SensorId::new("sensor_0")));
+        assert_eq!("sensor_0".parse::<SensorId>().unwrap().actuator(), actuator);
+    }
+
+    #[test]
+    fn actuators_named_otherwise_have_no_sensor() {
+        assert_eq!(ActuatorId::new("actuator_1").sensor(), None);
+    }
+
+    #[test]
+    fn commands_go_to_the_sensors_actuator() {
+        let command = ActuatorCommand::from_sensor_data(&reading(1, 5_000));
+        assert_eq!(command.actuator_id, SensorId::new("force_1").actuator());
+    }
+
+    #[test]
+    fn command_ids_differ_within_one_millisecond() {
+        let first = ActuatorCommand::from_sensor_data(&reading(1, 5_000));
+        let second = ActuatorCommand::from_sensor_data(&reading(2, 5_000));
+        assert_eq!(first.command_id.as_deref(), Some("force_1#1"));
+        assert_eq!(second.command_id.as_deref(), Some("force_1#2"));
+    }
+
+    #[test]
+    fn unnumbered_readings_get_no_command_id() {
+        assert_eq!(reading(0, 5_000).reading_id(), None);
+        assert_eq!(
+            ActuatorCommand::from_sensor_data(&reading(0, 5_000)).command_id,
+            None
+        );
+    }
+
+    #[test]
+    fn a_completed_metric_round_trips_as_a_record() {
+        let mut metrics = PerformanceMetrics::new("data_transmission").with_label("force_1");
+        metrics.complete_with_error("connect_timeout");
+        metrics.attempts = 3;
+        // Pin the span to 25ms ending 15ms ago
+        let now = Instant::now();
+        metrics.start_time = now - std::time::Duration::from_millis(40);
+        metrics.end_time = Some(now - std::time::Duration::from_millis(15));
+        metrics.duration_ms = Some(25.0);
+
+        let before_ms = now_millis();
+        let record = MetricsRecord::from(&metrics);
+        let end_ms = record.end_time_ms.unwrap();
+        assert!((24..=26).contains(&(end_ms - record.start_time_ms)));
+        assert!(end_ms <= before_ms);
+        assert_eq!(record.operation, "data_transmission");
+        assert_eq!(record.duration_ms, Some(25.0));
+        assert!(!record.success);
+        assert_eq!(record.attempts, 3);
+        assert_eq!(record.error_kind.as_deref(), Some("connect_timeout"));
+        assert_eq!(record.label.as_deref(), Some("force_1"));
+        assert_eq!(record.count, 1);
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            serde_json::from_str::<MetricsRecord>(&json).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    fn command_payload_serializes_as_an_object() {
+        let mut data = reading(3, 1_000);
+        data.value = 12.5;
+        let command = ActuatorCommand::from_sensor_data(&data);
+
+        let json = serde_json::to_value(&command).unwrap();
+        let payload = &json["control_command"]["payload"];
+        assert!(payload.is_object(), "{}", payload);
+        assert_eq!(payload["value"], serde_json::json!(12.5));
+
+        // Missing fields default to None
+        let payload: CommandPayload = serde_json::from_str(r#"{"target": "home"}"#).unwrap();
+        assert_eq!(
+            payload,
+            CommandPayload {
+                value: None,
+                target: Some("home".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn sensor_data_schema_lists_value_and_sensor_id() {
+        let schemas = wire_schemas();
+        let properties = &schemas["SensorData"]["properties"];
+        assert!(properties.get("value").is_some());
+        assert!(properties.get("sensor_id").is_some());
+    }
+
+    #[test]
+    fn rounding_residue_std_dev_is_treated_as_zero() {
+        // A constant 0.1 can leave a std dev of ~1e-17 in a running variance
+        let mut data = SensorData {
+            value: 0.1 + 1e-15,
+            ..reading(1, 1_000)
+        };
+        assert_eq!(data.detect_anomaly(0.1, 1e-17, 3.0), 0.0);
+        assert!(!data.is_anomaly);
+
+        // Near zero the floor is absolute, so small real spreads still score
+        let mut data = SensorData {
+            value: 0.5,
+            ..reading(2, 1_010)
+        };
+        assert!(data.detect_anomaly(0.0, 0.01, 3.0) > 3.0);
+        assert!(data.is_anomaly);
+    }
+}
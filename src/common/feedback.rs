@@ -0,0 +1,95 @@
+use super::data_types::{ActuatorFeedback, ActuatorId, ActuatorStatus};
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Feedback counts by status for one actuator
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ActuatorSummary {
+    pub normal: usize,
+    pub adjusting: usize,
+    pub warning: usize,
+    pub error: usize,
+    pub last_seen: u128, // Timestamp of the latest feedback in milliseconds
+}
+
+// Aggregates the feedback stream per actuator
+#[derive(Debug, Default)]
+pub struct FeedbackAggregator {
+    actuators: HashMap<ActuatorId, ActuatorSummary>,
+}
+
+impl FeedbackAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, feedback: &ActuatorFeedback) {
+        let summary = self
+            .actuators
+            .entry(feedback.actuator_id.clone())
+            .or_default();
+
+        match feedback.status {
+            ActuatorStatus::Normal => summary.normal += 1,
+            ActuatorStatus::Adjusting => summary.adjusting += 1,
+            ActuatorStatus::Warning => summary.warning += 1,
+            ActuatorStatus::Error => summary.error += 1,
+        }
+        summary.last_seen = summary.last_seen.max(feedback.timestamp);
+    }
+
+    pub fn summary(&self) -> &HashMap<ActuatorId, ActuatorSummary> {
+        &self.actuators
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feedback(actuator_id: &str, status: ActuatorStatus, timestamp: u128) -> ActuatorFeedback {
+        ActuatorFeedback {
+            timestamp,
+            actuator_id: ActuatorId::new(actuator_id),
+            status,
+            message: None,
+            command_id: None,
+        }
+    }
+
+    #[test]
+    fn feedback_is_counted_by_status_per_actuator() {
+        let mut aggregator = FeedbackAggregator::new();
+        for (actuator_id, status, timestamp) in [
+            ("actuator_1", ActuatorStatus::Normal, 100),
+            ("actuator_1", ActuatorStatus::Adjusting, 300),
+            ("actuator_1", ActuatorStatus::Normal, 200),
+            ("actuator_2", ActuatorStatus::Warning, 150),
+            ("actuator_2", ActuatorStatus::Error, 250),
+            ("actuator_2", ActuatorStatus::Error, 400),
+        ] {
+            aggregator.record(&feedback(actuator_id, status, timestamp));
+        }
+
+        let summary = aggregator.summary();
+        assert_eq!(summary.len(), 2);
+        let first = &summary["actuator_1"];
+        assert_eq!(
+            (first.normal, first.adjusting, first.warning, first.error),
+            (2, 1, 0, 0)
+        );
+        // Latest by timestamp, not arrival order
+        assert_eq!(first.last_seen, 300);
+        let second = &summary["actuator_2"];
+        assert_eq!(
+            (
+                second.normal,
+                second.adjusting,
+                second.warning,
+                second.error
+            ),
+            (0, 0, 1, 2)
+        );
+        assert_eq!(second.last_seen, 400);
+    }
+}
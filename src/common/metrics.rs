@@ -1,42 +1,196 @@
-use crate::common::data_types::PerformanceMetrics;
+use crate::common::data_types::{MetricsRecord, PerformanceMetrics};
 use chrono::Local;
-use std::collections::HashMap;
-use std::fs::OpenOptions;
-use std::io::Write;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::time;
 
 use super::data_types::SensorData;
 
+// Failure reasons kept per operation in each report, most frequent first
+pub const TOP_FAILURE_REASONS: usize = 5;
+
+// Producer side of the bounded metrics channel. Sending never blocks: if the
+// collector falls behind, metrics are dropped and counted instead.
+#[derive(Clone)]
+pub struct MetricsSender {
+    tx: crossbeam_channel::Sender<PerformanceMetrics>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl MetricsSender {
+    pub fn send(&self, metrics: PerformanceMetrics) {
+        if let Err(crossbeam_channel::TrySendError::Full(_)) = self.tx.try_send(metrics) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+// Collector side of the metrics channel
+pub struct MetricsReceiver {
+    rx: crossbeam_channel::Receiver<PerformanceMetrics>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl MetricsReceiver {
+    // Next unread metrics, without waiting for one
+    pub fn try_recv(&self) -> Result<PerformanceMetrics, crossbeam_channel::TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    // Metrics dropped since the last call
+    pub fn take_dropped(&self) -> usize {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+// Bounded metrics channel holding at most `capacity` unread metrics
+pub fn metrics_channel(capacity: usize) -> (MetricsSender, MetricsReceiver) {
+    let (tx, rx) = crossbeam_channel::bounded(capacity);
+    let dropped = Arc::new(AtomicUsize::new(0));
+    (
+        MetricsSender {
+            tx,
+            dropped: Arc::clone(&dropped),
+        },
+        MetricsReceiver { rx, dropped },
+    )
+}
+
+// Rolling readings-per-second rate per sensor, from when each reading arrived
+pub struct ThroughputEstimator {
+    window: Duration,
+    arrivals: HashMap<String, VecDeque<Instant>>, // Within `window` of the latest arrival
+}
+
+impl ThroughputEstimator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            arrivals: HashMap::new(),
+        }
+    }
+
+    // Note a reading from `sensor_id` arriving at `now`
+    pub fn record(&mut self, sensor_id: &str, now: Instant) {
+        let arrivals = match self.arrivals.get_mut(sensor_id) {
+            Some(arrivals) => arrivals,
+            None => self.arrivals.entry(sensor_id.to_string()).or_default(),
+        };
+        arrivals.push_back(now);
+        while let Some(&oldest) = arrivals.front() {
+            if now.saturating_duration_since(oldest) <= self.window {
+                break;
+            }
+            arrivals.pop_front();
+        }
+    }
+
+    // Readings per second over the arrivals in the window, for each sensor with
+    // at least two of them. Sensors silent for a whole window report nothing.
+    pub fn rates(&self, now: Instant) -> BTreeMap<String, f64> {
+        let mut rates = BTreeMap::new();
+        for (sensor_id, arrivals) in &self.arrivals {
+            let recent: Vec<Instant> = arrivals
+                .iter()
+                .copied()
+                .filter(|&arrival| now.saturating_duration_since(arrival) <= self.window)
+                .collect();
+            let (Some(first), Some(last)) = (recent.first(), recent.last()) else {
+                continue;
+            };
+            let span = last.saturating_duration_since(*first).as_secs_f64();
+            if span > 0.0 {
+                rates.insert(sensor_id.clone(), (recent.len() - 1) as f64 / span);
+            }
+        }
+        rates
+    }
+}
+
+// Per-sensor throughput, shared between whatever sees readings arrive and the collector
+pub type SharedThroughput = Arc<Mutex<ThroughputEstimator>>;
+
+// Samples recorded for one operation since the last report
+#[derive(Default)]
+struct OperationSamples {
+    samples: VecDeque<PerformanceMetrics>,
+    evicted: usize, // Dropped to stay within max_samples_per_op
+}
+
 // Metrics collector for benchmarking performance
 pub struct MetricsCollector {
-    metrics: Arc<Mutex<HashMap<String, Vec<PerformanceMetrics>>>>,
+    metrics: Arc<Mutex<HashMap<String, OperationSamples>>>,
+    max_samples_per_op: usize,
     last_report_time: Instant,
     report_interval: Duration,
     log_to_file: bool,
     log_file: String,
+    max_log_bytes: Option<u64>,
+    max_log_files: usize,
+    csv_file: Option<String>,
+    histogram_buckets: Vec<f64>,
+    deadlines_ms: HashMap<String, f64>,
+    max_sensor_breakdowns: usize,
+    dropped: usize, // Metrics lost to a full channel since the last report
+    throughput: SharedThroughput,
 }
 
 impl MetricsCollector {
     pub fn new(config: &crate::config::MetricsConfig) -> Self {
+        let mut histogram_buckets = config.histogram_buckets_ms.clone();
+        histogram_buckets.sort_by(|a, b| a.total_cmp(b));
+
         Self {
             metrics: Arc::new(Mutex::new(HashMap::new())),
+            max_samples_per_op: config.max_samples_per_op.max(1),
             last_report_time: Instant::now(),
             report_interval: Duration::from_millis(config.report_interval_ms),
             log_to_file: config.log_to_file,
             log_file: config.log_file.clone(),
+            max_log_bytes: config.max_log_bytes,
+            max_log_files: config.max_log_files,
+            csv_file: config.metrics_csv.clone(),
+            histogram_buckets,
+            deadlines_ms: config.deadlines_ms.clone(),
+            max_sensor_breakdowns: config.max_sensor_breakdowns,
+            dropped: 0,
+            throughput: Arc::new(Mutex::new(ThroughputEstimator::new(Duration::from_millis(
+                config.throughput_window_ms,
+            )))),
         }
     }
+
+    // Report throughput from `throughput`, which something else records arrivals into
+    pub fn with_throughput(mut self, throughput: SharedThroughput) -> Self {
+        self.throughput = throughput;
+        self
+    }
+
+    // Readings per second per sensor over the throughput window
+    pub fn sensor_throughput(&self) -> BTreeMap<String, f64> {
+        self.throughput.lock().unwrap().rates(Instant::now())
+    }
     
     // Add a new metrics record
     pub fn add_metrics(&self, metrics: PerformanceMetrics) {
         let mut metrics_lock = self.metrics.lock().unwrap();
         let entry = metrics_lock.entry(metrics.operation.clone()).or_default();
-        entry.push(metrics);
+        if entry.samples.len() >= self.max_samples_per_op {
+            entry.samples.pop_front();
+            entry.evicted += 1;
+        }
+        entry.samples.push_back(metrics);
     }
-    pub fn record_sensor_data(&self, _data: &SensorData) {
+    pub fn record_sensor_data(&self, data: &SensorData) {
     let now = Instant::now();
+    self.throughput.lock().unwrap().record(&data.sensor_id, now);
     
     let metrics = PerformanceMetrics {
         operation: "sensor_data_received".to_string(),
@@ -44,6 +198,10 @@ impl MetricsCollector {
         end_time: Some(now), // or `None` if the operation is still in progress
         duration_ms: Some(0.0), // You can calculate actual duration if needed
         success: true,
+        attempts: 1,
+        error_kind: None,
+        label: None,
+        count: 1,
     };
 
     self.add_metrics(metrics);
@@ -54,108 +212,216 @@ impl MetricsCollector {
         let metrics_lock = self.metrics.lock().unwrap();
         let mut report = HashMap::new();
         
-        for (operation, metrics) in metrics_lock.iter() {
-            if metrics.is_empty() {
+        for (operation, entry) in metrics_lock.iter() {
+            if entry.samples.is_empty() {
                 continue;
             }
-            
-            // Calculate statistics
-            let total = metrics.len();
-            let success_count = metrics.iter().filter(|m| m.success).count();
-            let success_rate = success_count as f64 / total as f64 * 100.0;
-            
-            // Calculate average duration
-            let durations: Vec<f64> = metrics
-                .iter()
-                .filter_map(|m| m.duration_ms)
-                .collect();
-            
-            let avg_duration = if !durations.is_empty() {
-                durations.iter().sum::<f64>() / durations.len() as f64
-            } else {
-                0.0
-            };
-            
-            // Calculate min and max durations
-            let min_duration = durations.iter().fold(f64::INFINITY, |a, &b| a.min(b));
-            let max_duration = durations.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
-            
-            // Calculate jitter (standard deviation of durations)
-            let jitter = if durations.len() > 1 {
-                let mean = avg_duration;
-                let variance = durations.iter()
-                    .map(|&d| (d - mean).powi(2))
-                    .sum::<f64>() / durations.len() as f64;
-                variance.sqrt()
-            } else {
-                0.0
-            };
-            
-            // Calculate missed deadlines
-            let mut missed_deadlines = 0;
-            for m in metrics {
-                if let Some(duration) = m.duration_ms {
-                    match m.operation.as_str() {
-                        "data_processing" => {
-                            if duration > 2.0 {
-                                missed_deadlines += 1;
-                            }
-                        },
-                        "data_transmission" => {
-                            if duration > 1.0 {
-                                missed_deadlines += 1;
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-            }
-            
-            let stats = OperationStats {
-                operation: operation.clone(),
-                total_operations: total,
-                success_rate,
-                avg_duration,
-                min_duration: if min_duration.is_finite() { min_duration } else { 0.0 },
-                max_duration: if max_duration.is_finite() { max_duration } else { 0.0 },
-                jitter,
-                missed_deadlines,
-            };
-            
+            let samples: Vec<&PerformanceMetrics> = entry.samples.iter().collect();
+
+            let mut stats = self.operation_stats(operation, &samples, entry.evicted);
+            stats.by_sensor = self.sensor_breakdown(operation, &samples);
             report.insert(operation.clone(), stats);
         }
         
         report
     }
+
+    // Stats for the labeled samples of `operation`, one entry per label, for at
+    // most max_sensor_breakdowns labels. Labels are taken in sorted order so the
+    // same sensors are broken down from one report to the next.
+    fn sensor_breakdown(
+        &self,
+        operation: &str,
+        samples: &[&PerformanceMetrics],
+    ) -> BTreeMap<String, OperationStats> {
+        let mut by_label: BTreeMap<&str, Vec<&PerformanceMetrics>> = BTreeMap::new();
+        for &sample in samples {
+            if let Some(label) = sample.label.as_deref() {
+                by_label.entry(label).or_default().push(sample);
+            }
+        }
+        by_label
+            .into_iter()
+            .take(self.max_sensor_breakdowns)
+            .map(|(label, samples)| {
+                let stats = self.operation_stats(operation, &samples, 0);
+                (label.to_string(), stats)
+            })
+            .collect()
+    }
+
+    // Stats over `metrics`, all samples of `operation`
+    fn operation_stats(
+        &self,
+        operation: &str,
+        metrics: &[&PerformanceMetrics],
+        evicted: usize,
+    ) -> OperationStats {
+        // Calculate statistics
+        let total: usize = metrics.iter().map(|m| m.count).sum();
+        let success_count: usize = metrics.iter().filter(|m| m.success).map(|m| m.count).sum();
+        let success_rate = success_count as f64 / total as f64 * 100.0;
+        
+        // Calculate average duration
+        let durations: Vec<f64> = metrics
+            .iter()
+            .filter_map(|m| m.duration_ms)
+            .collect();
+        
+        // None when no operation completed, so "no timing data" isn't shown as 0ms
+        let avg_duration = (!durations.is_empty())
+            .then(|| durations.iter().sum::<f64>() / durations.len() as f64);
+        
+        // Calculate min and max durations
+        let min_duration = durations.iter().copied().reduce(f64::min);
+        let max_duration = durations.iter().copied().reduce(f64::max);
+        
+        // Calculate jitter (standard deviation of durations)
+        let jitter = avg_duration.map(|mean| {
+            let variance = durations.iter()
+                .map(|&d| (d - mean).powi(2))
+                .sum::<f64>() / durations.len() as f64;
+            variance.sqrt()
+        });
+        
+        // Calculate missed deadlines (operations without a deadline never miss)
+        let missed_deadlines = match self.deadlines_ms.get(operation) {
+            Some(&deadline) => durations.iter().filter(|&&d| d > deadline).count(),
+            None => 0,
+        };
+        
+        // Bucket durations into the latency histogram (last bucket is +Inf)
+        let mut histogram = vec![0; self.histogram_buckets.len() + 1];
+        for &duration in &durations {
+            let bucket = self
+                .histogram_buckets
+                .iter()
+                .position(|&bound| duration <= bound)
+                .unwrap_or(self.histogram_buckets.len());
+            histogram[bucket] += 1;
+        }
+
+        // Count failures per reason; failures without one are left out
+        let mut reason_counts: HashMap<&str, usize> = HashMap::new();
+        for m in metrics {
+            if let Some(error_kind) = m.error_kind.as_deref() {
+                *reason_counts.entry(error_kind).or_default() += m.count;
+            }
+        }
+        let mut failure_reasons: Vec<(String, usize)> = reason_counts
+            .into_iter()
+            .map(|(reason, count)| (reason.to_string(), count))
+            .collect();
+        failure_reasons.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        failure_reasons.truncate(TOP_FAILURE_REASONS);
+
+        OperationStats {
+            operation: operation.to_string(),
+            total_operations: total,
+            timed_operations: durations.len(),
+            success_rate,
+            avg_duration,
+            min_duration,
+            max_duration,
+            jitter,
+            missed_deadlines,
+            histogram,
+            evicted,
+            failure_reasons,
+            by_sensor: BTreeMap::new(),
+        }
+    }
     
     // Log report to console and file
     pub fn log_report(&self, report: &HashMap<String, OperationStats>) {
         // Print to console
         println!("--- Performance Report ---");
         println!("Time: {}", Local::now().format("%Y-%m-%d %H:%M:%S"));
-        println!("{:<20} | {:<10} | {:<10} | {:<15} | {:<15} | {:<15} | {:<10} | {:<15}", 
-                 "Operation", "Total", "Success%", "Avg Duration(ms)", "Min Duration(ms)", 
+        println!("{:<20} | {:<10} | {:<10} | {:<10} | {:<15} | {:<15} | {:<15} | {:<10} | {:<15}", 
+                 "Operation", "Total", "Timed", "Success%", "Avg Duration(ms)", "Min Duration(ms)", 
                  "Max Duration(ms)", "Jitter(ms)", "Missed Deadlines");
-        println!("{:-<130}", "");
+        println!("{:-<143}", "");
         
         for stats in report.values() {
-            println!("{:<20} | {:<10} | {:<10.2} | {:<15.3} | {:<15.3} | {:<15.3} | {:<10.3} | {:<15}", 
-                     stats.operation, stats.total_operations, stats.success_rate, 
-                     stats.avg_duration, stats.min_duration, stats.max_duration, 
-                     stats.jitter, stats.missed_deadlines);
+            println!("{:<20} | {:<10} | {:<10} | {:<10.2} | {:<15} | {:<15} | {:<15} | {:<10} | {:<15}", 
+                     stats.operation, stats.total_operations, stats.timed_operations,
+                     stats.success_rate, format_ms(stats.avg_duration),
+                     format_ms(stats.min_duration), format_ms(stats.max_duration),
+                     format_ms(stats.jitter), stats.missed_deadlines);
+        }
+        println!("{:-<143}", "");
+
+        // Per-sensor rows, for operations broken down by sensor
+        for stats in report.values().filter(|stats| !stats.by_sensor.is_empty()) {
+            for (sensor_id, sensor_stats) in &stats.by_sensor {
+                println!(
+                    "{:<20} | {:<10} | {:<10} | {:<10.2} | {:<15} | {:<15} | {:<15} | {:<10} | {:<15}",
+                    format!("{}[{}]", stats.operation, sensor_id),
+                    sensor_stats.total_operations,
+                    sensor_stats.timed_operations,
+                    sensor_stats.success_rate,
+                    format_ms(sensor_stats.avg_duration),
+                    format_ms(sensor_stats.min_duration),
+                    format_ms(sensor_stats.max_duration),
+                    format_ms(sensor_stats.jitter),
+                    sensor_stats.missed_deadlines
+                );
+            }
+            println!("{:-<143}", "");
+        }
+
+        // Latency histogram per operation
+        for stats in report.values() {
+            println!("{:<20} | {}", stats.operation, self.format_histogram(&stats.histogram));
+        }
+        println!("{:-<143}", "");
+
+        if self.dropped > 0 {
+            println!("{} metrics dropped because the metrics channel was full", self.dropped);
+        }
+
+        // Samples dropped to respect max_samples_per_op
+        for stats in report.values().filter(|stats| stats.evicted > 0) {
+            println!(
+                "{:<20} | {} oldest samples evicted (max_samples_per_op reached)",
+                stats.operation, stats.evicted
+            );
+        }
+
+        // Why operations failed
+        for stats in report.values().filter(|stats| !stats.failure_reasons.is_empty()) {
+            println!(
+                "{:<20} | failures: {}",
+                stats.operation,
+                format_failure_reasons(&stats.failure_reasons)
+            );
+        }
+
+        // Readings per second per sensor
+        for (sensor_id, rate) in self.sensor_throughput() {
+            println!("{:<20} | {:.1} readings/s", sensor_id, rate);
         }
-        println!("{:-<130}", "");
         
         // Log to file if enabled
         if self.log_to_file {
             let log = format!(
-                "Time: {}\n{:<20} | {:<10} | {:<10} | {:<15} | {:<15} | {:<15} | {:<10} | {:<15}\n{:-<130}\n", 
+                "Time: {}\n{:<20} | {:<10} | {:<10} | {:<10} | {:<15} | {:<15} | {:<15} | {:<10} | {:<15}\n{:-<143}\n", 
                 Local::now().format("%Y-%m-%d %H:%M:%S"),
-                "Operation", "Total", "Success%", "Avg Duration(ms)", "Min Duration(ms)", 
+                "Operation", "Total", "Timed", "Success%", "Avg Duration(ms)", "Min Duration(ms)", 
                 "Max Duration(ms)", "Jitter(ms)", "Missed Deadlines",
                 ""
             );
             
+            // Start a fresh file once the current one is over its size limit
+            if let Some(max_bytes) = self.max_log_bytes {
+                let size = fs::metadata(&self.log_file).map(|m| m.len()).unwrap_or(0);
+                if size > max_bytes {
+                    if let Err(e) = rotate_log(&self.log_file, self.max_log_files) {
+                        println!("Failed to rotate log file: {}", e);
+                    }
+                }
+            }
+
             // Open the file in append mode
             let mut file = match OpenOptions::new()
                 .create(true)
@@ -177,10 +443,11 @@ impl MetricsCollector {
             // Write data
             for stats in report.values() {
                 let line = format!(
-                    "{:<20} | {:<10} | {:<10.2} | {:<15.3} | {:<15.3} | {:<15.3} | {:<10.3} | {:<15}\n", 
-                    stats.operation, stats.total_operations, stats.success_rate, 
-                    stats.avg_duration, stats.min_duration, stats.max_duration, 
-                    stats.jitter, stats.missed_deadlines
+                    "{:<20} | {:<10} | {:<10} | {:<10.2} | {:<15} | {:<15} | {:<15} | {:<10} | {:<15}\n", 
+                    stats.operation, stats.total_operations, stats.timed_operations,
+                    stats.success_rate, format_ms(stats.avg_duration),
+                    format_ms(stats.min_duration), format_ms(stats.max_duration),
+                    format_ms(stats.jitter), stats.missed_deadlines
                 );
                 
                 if let Err(e) = file.write_all(line.as_bytes()) {
@@ -189,18 +456,70 @@ impl MetricsCollector {
                 }
             }
             
+            // Write histogram
+            let mut histogram_log = format!("{:-<143}\n", "");
+            for stats in report.values() {
+                histogram_log.push_str(&format!(
+                    "{:<20} | {}\n",
+                    stats.operation,
+                    self.format_histogram(&stats.histogram)
+                ));
+            }
+            if let Err(e) = file.write_all(histogram_log.as_bytes()) {
+                println!("Failed to write to log file: {}", e);
+                return;
+            }
+
             // Write footer
-            if let Err(e) = file.write_all(format!("{:-<130}\n\n", "").as_bytes()) {
+            if let Err(e) = file.write_all(format!("{:-<143}\n\n", "").as_bytes()) {
                 println!("Failed to write to log file: {}", e);
             }
         }
     }
     
+    // Append one CSV row per operation to the metrics_csv file, if configured,
+    // writing the header first when the file is new or empty
+    pub fn write_csv(&self, report: &HashMap<String, OperationStats>) {
+        let Some(path) = &self.csv_file else {
+            return;
+        };
+        if let Err(e) = append_csv(path, report) {
+            warn!("Failed to write metrics CSV: {}", e);
+        }
+    }
+
+    // Render histogram counts as "<=1ms: 42 (97.7%) | ... | +Inf: 0 (0.0%)"
+    fn format_histogram(&self, histogram: &[usize]) -> String {
+        let total: usize = histogram.iter().sum();
+        histogram
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let label = match self.histogram_buckets.get(i) {
+                    Some(bound) => format!("<={}ms", bound),
+                    None => "+Inf".to_string(),
+                };
+                let percent = if total > 0 {
+                    count as f64 / total as f64 * 100.0
+                } else {
+                    0.0
+                };
+                format!("{}: {} ({:.1}%)", label, count, percent)
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
     // Check if it's time to report metrics
     pub fn should_report(&self) -> bool {
         self.last_report_time.elapsed() >= self.report_interval
     }
     
+    // Record how many metrics were lost before reaching the collector
+    pub fn set_dropped(&mut self, dropped: usize) {
+        self.dropped = dropped;
+    }
+
     // Reset the report timer
     pub fn reset_report_timer(&mut self) {
         self.last_report_time = Instant::now();
@@ -209,32 +528,55 @@ impl MetricsCollector {
     // Clear metrics after reporting
     pub fn clear_metrics(&self) {
         let mut metrics_lock = self.metrics.lock().unwrap();
-        for (_, metrics) in metrics_lock.iter_mut() {
-            metrics.clear();
+        for (_, entry) in metrics_lock.iter_mut() {
+            entry.samples.clear();
+            entry.evicted = 0;
         }
     }
 }
 
+// Latest report, shared with anything that wants to read it at runtime
+pub type SharedReport = Arc<Mutex<HashMap<String, OperationStats>>>;
+
 // Statistics for an operation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct OperationStats {
     pub operation: String,
     pub total_operations: usize,
+    pub timed_operations: usize, // Operations that completed with a duration
     pub success_rate: f64,
-    pub avg_duration: f64,
-    pub min_duration: f64,
-    pub max_duration: f64,
-    pub jitter: f64,
+    // Duration stats cover timed operations only, and are None if there were none
+    pub avg_duration: Option<f64>,
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    pub jitter: Option<f64>,
     pub missed_deadlines: usize,
+    pub histogram: Vec<usize>, // Counts per MetricsConfig bucket, plus a final +Inf bucket
+    pub evicted: usize,        // Samples dropped because max_samples_per_op was reached
+    pub failure_reasons: Vec<(String, usize)>, // Most frequent error kinds with their counts
+    pub by_sensor: BTreeMap<String, OperationStats>, // Same stats per labeled sensor, if configured
 }
 
 // Function to run the metrics collector in real-time
 pub async fn run_metrics_collector(
     config: &crate::config::MetricsConfig,
-    rx: crossbeam_channel::Receiver<PerformanceMetrics>,
+    rx: MetricsReceiver,
+    latest_report: SharedReport,
+    throughput: SharedThroughput,
 ) {
-    let mut collector = MetricsCollector::new(config);
+    let mut collector = MetricsCollector::new(config).with_throughput(throughput);
     let mut interval = time::interval(Duration::from_millis(100)); // Check every 100ms
+
+    // Optional JSON-lines sink for raw records
+    let mut records_writer = config.records_file.as_ref().and_then(|path| {
+        match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(BufWriter::new(file)),
+            Err(e) => {
+                warn!("Failed to open metrics records file: {}", e);
+                None
+            }
+        }
+    });
     
     loop {
         // Wait for the next check
@@ -242,8 +584,13 @@ pub async fn run_metrics_collector(
         
         // Try to receive metrics (non-blocking)
         loop {
-            match rx.try_recv() {
+            match rx.rx.try_recv() {
                 Ok(metrics) => {
+                    if let Some(writer) = records_writer.as_mut() {
+                        if let Err(e) = write_record(writer, &metrics) {
+                            warn!("Failed to write metrics record: {}", e);
+                        }
+                    }
                     collector.add_metrics(metrics);
                 },
                 Err(crossbeam_channel::TryRecvError::Empty) => {
@@ -251,18 +598,461 @@ pub async fn run_metrics_collector(
                     break;
                 },
                 Err(crossbeam_channel::TryRecvError::Disconnected) => {
-                    println!("Metrics channel closed, stopping collector.");
+                    info!("Metrics channel closed, stopping collector.");
+                    if let Some(writer) = records_writer.as_mut() {
+                        let _ = writer.flush();
+                    }
                     return;
                 }
             }
         }
+
+        if let Some(writer) = records_writer.as_mut() {
+            if let Err(e) = writer.flush() {
+                warn!("Failed to flush metrics records: {}", e);
+            }
+        }
         
         // Report metrics if it's time
         if collector.should_report() {
             let report = collector.generate_report();
+            collector.set_dropped(rx.take_dropped());
             collector.log_report(&report);
+            collector.write_csv(&report);
+            *latest_report.lock().unwrap() = report;
             collector.reset_report_timer();
             collector.clear_metrics();
         }
     }
-}
\ No newline at end of file
+}
+
+// Move `path` to `path`.1, shifting older rotations up by one and dropping
+// any past `max_files`. With max_files = 0 the old log is just deleted.
+fn rotate_log(path: &str, max_files: usize) -> std::io::Result<()> {
+    if max_files == 0 {
+        return fs::remove_file(path);
+    }
+    for n in (1..max_files).rev() {
+        let older = format!("{}.{}", path, n);
+        if Path::new(&older).exists() {
+            fs::rename(&older, format!("{}.{}", path, n + 1))?;
+        }
+    }
+    fs::rename(path, format!("{}.1", path))
+}
+
+// Column order of the metrics CSV; rows are written in the same order. Duration
+// columns are left empty when an operation has no timing data.
+const CSV_HEADER: &str = "time,operation,total_operations,timed_operations,success_rate,\
+avg_duration_ms,min_duration_ms,max_duration_ms,jitter_ms,missed_deadlines,evicted";
+
+fn append_csv(path: &str, report: &HashMap<String, OperationStats>) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let is_empty = file.metadata()?.len() == 0;
+    let mut writer = BufWriter::new(file);
+    if is_empty {
+        writeln!(writer, "{}", CSV_HEADER)?;
+    }
+
+    // Sorted so every report lists operations in the same order
+    let mut operations: Vec<_> = report.values().collect();
+    operations.sort_by(|a, b| a.operation.cmp(&b.operation));
+
+    let time = Local::now().format("%Y-%m-%dT%H:%M:%S");
+    for stats in operations {
+        writeln!(
+            writer,
+            "{},{},{},{},{:.2},{},{},{},{},{},{}",
+            time,
+            stats.operation,
+            stats.total_operations,
+            stats.timed_operations,
+            stats.success_rate,
+            csv_ms(stats.avg_duration),
+            csv_ms(stats.min_duration),
+            csv_ms(stats.max_duration),
+            csv_ms(stats.jitter),
+            stats.missed_deadlines,
+            stats.evicted
+        )?;
+    }
+    writer.flush()
+}
+
+// Empty when there is no timing data
+fn csv_ms(value: Option<f64>) -> String {
+    value.map(|ms| format!("{:.3}", ms)).unwrap_or_default()
+}
+
+// Render failure reasons as "connect_timeout: 3, io: 1"
+pub fn format_failure_reasons(reasons: &[(String, usize)]) -> String {
+    reasons
+        .iter()
+        .map(|(reason, count)| format!("{}: {}", reason, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Duration for display, or "n/a" when there is no timing data
+pub fn format_ms(value: Option<f64>) -> String {
+    value.map_or_else(|| "n/a".to_string(), |ms| format!("{:.3}", ms))
+}
+
+// Append one metrics record as a JSON line
+fn write_record(
+    writer: &mut BufWriter<File>,
+    metrics: &PerformanceMetrics,
+) -> std::io::Result<()> {
+    let line = serde_json::to_string(&MetricsRecord::from(metrics))?;
+    writeln!(writer, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn completed(operation: &str, error_kind: Option<&str>) -> PerformanceMetrics {
+        let mut metrics = PerformanceMetrics::new(operation);
+        match error_kind {
+            Some(error_kind) => metrics.complete_with_error(error_kind),
+            None => metrics.complete(true),
+        }
+        metrics
+    }
+
+    #[test]
+    fn counted_samples_weigh_in_totals() {
+        let collector = MetricsCollector::new(&Config::default().metrics);
+        collector.add_metrics(completed("missing_reading", Some("sequence_gap")).with_count(3));
+        collector.add_metrics(completed("missing_reading", Some("sequence_gap")));
+
+        let report = collector.generate_report();
+        let stats = &report["missing_reading"];
+        assert_eq!(stats.total_operations, 4);
+        assert_eq!(stats.success_rate, 0.0);
+        assert_eq!(stats.failure_reasons, [("sequence_gap".to_string(), 4)]);
+    }
+
+    fn timed(operation: &str, label: &str, duration_ms: f64, success: bool) -> PerformanceMetrics {
+        let mut metrics = completed(operation, None).with_label(label);
+        metrics.duration_ms = Some(duration_ms);
+        metrics.success = success;
+        metrics
+    }
+
+    #[test]
+    fn labeled_metrics_are_broken_down_per_sensor() {
+        let mut config = Config::default().metrics;
+        config.max_sensor_breakdowns = 2;
+        let collector = MetricsCollector::new(&config);
+        collector.add_metrics(timed("data_processing", "sensor_0", 1.0, true));
+        collector.add_metrics(timed("data_processing", "sensor_0", 3.0, true));
+        collector.add_metrics(timed("data_processing", "sensor_1", 10.0, false));
+        collector.add_metrics(completed("data_processing", None));
+
+        let report = collector.generate_report();
+        let stats = &report["data_processing"];
+        assert_eq!(stats.total_operations, 4);
+        assert_eq!(
+            stats.by_sensor.keys().collect::<Vec<_>>(),
+            ["sensor_0", "sensor_1"]
+        );
+
+        let sensor_0 = &stats.by_sensor["sensor_0"];
+        assert_eq!(sensor_0.total_operations, 2);
+        assert_eq!(sensor_0.avg_duration, Some(2.0));
+        assert_eq!(sensor_0.success_rate, 100.0);
+
+        let sensor_1 = &stats.by_sensor["sensor_1"];
+        assert_eq!(sensor_1.total_operations, 1);
+        assert_eq!(sensor_1.max_duration, Some(10.0));
+        assert_eq!(sensor_1.success_rate, 0.0);
+    }
+
+    #[test]
+    fn breakdowns_are_capped_and_off_by_default() {
+        let mut config = Config::default().metrics;
+        let labeled = ["sensor_2", "sensor_0", "sensor_1"];
+
+        let collector = MetricsCollector::new(&config);
+        for label in labeled {
+            collector.add_metrics(timed("data_processing", label, 1.0, true));
+        }
+        let report = collector.generate_report();
+        assert!(report["data_processing"].by_sensor.is_empty());
+
+        // The first sensors in sorted order, whatever order they arrived in
+        config.max_sensor_breakdowns = 2;
+        let collector = MetricsCollector::new(&config);
+        for label in labeled {
+            collector.add_metrics(timed("data_processing", label, 1.0, true));
+        }
+        let report = collector.generate_report();
+        let by_sensor = &report["data_processing"].by_sensor;
+        assert_eq!(by_sensor.keys().collect::<Vec<_>>(), ["sensor_0", "sensor_1"]);
+    }
+
+    // Metrics config logging to `name` in a fresh directory under the system temp dir
+    fn temp_config(name: &str) -> crate::config::MetricsConfig {
+        let dir = std::env::temp_dir()
+            .join(format!("rust_assignment_metrics_{}", std::process::id()))
+            .join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut config = Config::default().metrics;
+        config.log_to_file = true;
+        config.log_file = dir.join("metrics.log").to_str().unwrap().to_string();
+        config
+    }
+
+    fn reports_in(path: &str) -> usize {
+        fs::read_to_string(path).unwrap().matches("Time: ").count()
+    }
+
+    #[test]
+    fn log_is_rotated_once_past_max_log_bytes() {
+        let mut config = temp_config("rotate");
+        config.max_log_bytes = Some(1);
+        config.max_log_files = 2;
+        let log = config.log_file.clone();
+        let collector = MetricsCollector::new(&config);
+        collector.add_metrics(completed("data_processing", None));
+        let report = collector.generate_report();
+
+        collector.log_report(&report);
+        assert!(!Path::new(&format!("{}.1", log)).exists());
+
+        // Each later report finds the log over its limit and starts a new one
+        collector.log_report(&report);
+        assert_eq!(reports_in(&format!("{}.1", log)), 1);
+        assert_eq!(reports_in(&log), 1);
+
+        collector.log_report(&report);
+        collector.log_report(&report);
+        assert!(Path::new(&format!("{}.2", log)).exists());
+        assert!(!Path::new(&format!("{}.3", log)).exists());
+        assert_eq!(reports_in(&log), 1);
+    }
+
+    #[test]
+    fn log_over_the_limit_is_deleted_without_rotated_files() {
+        let mut config = temp_config("delete");
+        config.max_log_bytes = Some(1);
+        config.max_log_files = 0;
+        let log = config.log_file.clone();
+        let collector = MetricsCollector::new(&config);
+        collector.add_metrics(completed("data_processing", None));
+        let report = collector.generate_report();
+
+        collector.log_report(&report);
+        collector.log_report(&report);
+        assert_eq!(reports_in(&log), 1);
+        assert!(!Path::new(&format!("{}.1", log)).exists());
+    }
+
+    #[test]
+    fn samples_past_max_samples_per_op_evict_the_oldest() {
+        let mut config = Config::default().metrics;
+        config.max_samples_per_op = 3;
+        let collector = MetricsCollector::new(&config);
+        for duration_ms in 1..=5 {
+            collector.add_metrics(timed(
+                "data_processing",
+                "sensor_0",
+                duration_ms as f64,
+                true,
+            ));
+        }
+
+        let report = collector.generate_report();
+        let stats = &report["data_processing"];
+        assert_eq!(stats.total_operations, 3);
+        assert_eq!(stats.evicted, 2);
+        assert_eq!(stats.min_duration, Some(3.0));
+
+        // The count starts over with the next report
+        collector.clear_metrics();
+        collector.add_metrics(timed("data_processing", "sensor_0", 1.0, true));
+        assert_eq!(collector.generate_report()["data_processing"].evicted, 0);
+    }
+
+    #[test]
+    fn full_metrics_channel_drops_instead_of_blocking() {
+        let (metrics_tx, metrics_rx) = metrics_channel(2);
+
+        // Nothing reads while the producer floods the channel
+        let producer = std::thread::spawn(move || {
+            for _ in 0..100 {
+                metrics_tx.send(completed("data_processing", None));
+            }
+        });
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !producer.is_finished() {
+            assert!(
+                Instant::now() < deadline,
+                "producer blocked on a full channel"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(metrics_rx.take_dropped(), 98);
+        assert_eq!(metrics_rx.take_dropped(), 0);
+        let received = std::iter::from_fn(|| metrics_rx.try_recv().ok()).count();
+        assert_eq!(received, 2);
+    }
+
+    #[test]
+    fn csv_rows_parse_back_with_the_header_written_once() {
+        let mut config = temp_config("csv");
+        config.log_to_file = false;
+        let csv = config.log_file.replace("metrics.log", "metrics.csv");
+        config.metrics_csv = Some(csv.clone());
+        let collector = MetricsCollector::new(&config);
+        collector.add_metrics(timed("data_processing", "sensor_0", 2.5, true));
+        // Never completed, so it has no duration
+        collector.add_metrics(PerformanceMetrics::new("data_transmission"));
+        let report = collector.generate_report();
+
+        collector.write_csv(&report);
+        collector.write_csv(&report);
+
+        let contents = fs::read_to_string(&csv).unwrap();
+        let mut lines = contents.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows
+            .iter()
+            .all(|row| row.len() == CSV_HEADER.split(',').count()));
+        assert!(rows.iter().all(|row| row[0] != "time"));
+
+        // Operations in sorted order, numeric columns parse back
+        let processing = &rows[0];
+        assert_eq!(processing[1], "data_processing");
+        assert_eq!(processing[2].parse::<usize>().unwrap(), 1);
+        assert_eq!(processing[4].parse::<f64>().unwrap(), 100.0);
+        assert_eq!(processing[5].parse::<f64>().unwrap(), 2.5);
+
+        // No timing data leaves the duration columns empty
+        let transmission = &rows[1];
+        assert_eq!(transmission[1], "data_transmission");
+        assert_eq!(transmission[4].parse::<f64>().unwrap(), 0.0);
+        assert_eq!(transmission[5], "");
+        assert_eq!(transmission[10].parse::<usize>().unwrap(), 0);
+    }
+
+    #[test]
+    fn throughput_matches_a_known_arrival_rate() {
+        let mut estimator = ThroughputEstimator::new(Duration::from_secs(1));
+        let start = Instant::now();
+        // sensor_0 every 10ms for 2s, sensor_1 every 50ms
+        for i in 0..=200 {
+            estimator.record("sensor_0", start + Duration::from_millis(i * 10));
+        }
+        for i in 0..=40 {
+            estimator.record("sensor_1", start + Duration::from_millis(i * 50));
+        }
+
+        let now = start + Duration::from_secs(2);
+        let rates = estimator.rates(now);
+        assert!((rates["sensor_0"] - 100.0).abs() < 1.0, "{:?}", rates);
+        assert!((rates["sensor_1"] - 20.0).abs() < 0.5, "{:?}", rates);
+
+        // Sensors silent for a whole window drop out
+        assert!(estimator.rates(now + Duration::from_secs(2)).is_empty());
+    }
+
+    #[test]
+    fn durations_fall_in_the_first_bucket_they_fit_or_inf() {
+        let mut config = Config::default().metrics;
+        config.histogram_buckets_ms = vec![5.0, 1.0];
+        let collector = MetricsCollector::new(&config);
+        // A duration on a bound counts towards that bucket
+        for duration_ms in [0.5, 1.0, 3.0, 5.0, 7.0] {
+            collector.add_metrics(timed("data_processing", "sensor_0", duration_ms, true));
+        }
+
+        let report = collector.generate_report();
+        let histogram = &report["data_processing"].histogram;
+        assert_eq!(histogram, &[2, 2, 1]);
+        assert_eq!(
+            collector.format_histogram(histogram),
+            "<=1ms: 2 (40.0%) | <=5ms: 2 (40.0%) | +Inf: 1 (20.0%)"
+        );
+    }
+
+    #[test]
+    fn missed_deadlines_follow_the_configured_deadlines() {
+        let missed = |deadlines_ms: HashMap<String, f64>| {
+            let mut config = Config::default().metrics;
+            config.deadlines_ms = deadlines_ms;
+            let collector = MetricsCollector::new(&config);
+            for duration_ms in [0.5, 1.5, 2.5, 5.0] {
+                collector.add_metrics(timed("data_processing", "sensor_0", duration_ms, true));
+                collector.add_metrics(timed(
+                    "sensor_reading_generation",
+                    "sensor_0",
+                    duration_ms,
+                    true,
+                ));
+            }
+            let report = collector.generate_report();
+            (
+                report["data_processing"].missed_deadlines,
+                report["sensor_reading_generation"].missed_deadlines,
+            )
+        };
+
+        // Defaults: 2.0ms for data_processing, none for sensor_reading_generation
+        assert_eq!(missed(Config::default().metrics.deadlines_ms), (2, 0));
+        assert_eq!(
+            missed(HashMap::from([
+                ("data_processing".to_string(), 1.0),
+                ("sensor_reading_generation".to_string(), 3.0),
+            ])),
+            (3, 1)
+        );
+    }
+
+    #[test]
+    fn uncompleted_operations_report_no_timing_data() {
+        let collector = MetricsCollector::new(&Config::default().metrics);
+        collector.add_metrics(PerformanceMetrics::new("stalled"));
+        collector.add_metrics(PerformanceMetrics::new("stalled"));
+
+        let report = collector.generate_report();
+        let stats = &report["stalled"];
+        assert_eq!(stats.total_operations, 2);
+        assert_eq!(stats.timed_operations, 0);
+        assert_eq!(stats.success_rate, 0.0);
+        assert_eq!(stats.avg_duration, None);
+        assert_eq!(stats.min_duration, None);
+        assert_eq!(stats.max_duration, None);
+        assert_eq!(stats.jitter, None);
+        assert_eq!(stats.missed_deadlines, 0);
+        assert!(stats.histogram.iter().all(|&count| count == 0));
+    }
+
+    #[test]
+    fn distinct_failure_reasons_are_both_reported() {
+        let collector = MetricsCollector::new(&Config::default().metrics);
+        collector.add_metrics(completed("data_transmission", Some("timeout")));
+        collector.add_metrics(completed("data_transmission", Some("connection_refused")));
+        collector.add_metrics(completed("data_transmission", Some("timeout")));
+        collector.add_metrics(completed("data_transmission", None));
+
+        let report = collector.generate_report();
+        let stats = &report["data_transmission"];
+        assert_eq!(stats.total_operations, 4);
+        assert_eq!(stats.success_rate, 25.0);
+        // Most frequent first
+        assert_eq!(
+            stats.failure_reasons,
+            [
+                ("timeout".to_string(), 2),
+                ("connection_refused".to_string(), 1)
+            ]
+        );
+    }
+}
@@ -1,2 +1,10 @@
+pub mod bus;
+pub mod clock;
+pub mod control;
 pub mod data_types;
+pub mod feedback;
 pub mod metrics;
+pub mod sla;
+#[cfg(test)]
+pub mod test_log;
+pub mod wire_format;
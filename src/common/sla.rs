@@ -0,0 +1,95 @@
+use crate::common::data_types::MetricsRecord;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// Limits every operation in a metrics recording is checked against
+#[derive(Debug, Clone, Copy)]
+pub struct SlaPolicy {
+    pub max_latency_ms: f64,
+    pub min_success_rate: f64, // 0.0-1.0, also the share of timed ops that must be within max_latency_ms
+}
+
+// How one operation fared against an SlaPolicy
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlaResult {
+    pub operation: String,
+    pub total: usize,
+    pub successes: usize,
+    pub timed: usize,          // Records with a duration
+    pub within_latency: usize, // Timed records no slower than max_latency_ms
+}
+
+impl SlaResult {
+    pub fn success_rate(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.successes as f64 / self.total as f64
+    }
+
+    // Share of timed records within the latency bound, None if nothing was timed
+    pub fn latency_compliance(&self) -> Option<f64> {
+        if self.timed == 0 {
+            return None;
+        }
+        Some(self.within_latency as f64 / self.timed as f64)
+    }
+
+    // Operations without timing data are judged on success rate alone
+    pub fn met(&self, policy: &SlaPolicy) -> bool {
+        self.success_rate() >= policy.min_success_rate
+            && !matches!(
+                self.latency_compliance(),
+                Some(compliance) if compliance < policy.min_success_rate
+            )
+    }
+}
+
+// Read a JSON-lines metrics recording, as written via metrics.records_file
+pub fn read_records(path: &Path) -> std::io::Result<Vec<MetricsRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("line {}: {}", index + 1, e),
+            )
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+// Per-operation SLA results, sorted by operation name
+pub fn evaluate(records: &[MetricsRecord], policy: &SlaPolicy) -> Vec<SlaResult> {
+    let mut results: BTreeMap<&str, SlaResult> = BTreeMap::new();
+    for record in records {
+        let result = results
+            .entry(record.operation.as_str())
+            .or_insert_with(|| SlaResult {
+                operation: record.operation.clone(),
+                total: 0,
+                successes: 0,
+                timed: 0,
+                within_latency: 0,
+            });
+        result.total += record.count;
+        if record.success {
+            result.successes += record.count;
+        }
+        if let Some(duration_ms) = record.duration_ms {
+            result.timed += 1;
+            if duration_ms <= policy.max_latency_ms {
+                result.within_latency += 1;
+            }
+        }
+    }
+    results.into_values().collect()
+}
@@ -0,0 +1,43 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::{Mutex, Once};
+
+// Keeps every record for tests to check what was logged. The logger is global to
+// the test binary, so it is installed once and shared by every test.
+struct Capture(Mutex<Vec<(Level, String)>>);
+
+impl Log for Capture {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let line = record.args().to_string();
+        self.0.lock().unwrap().push((record.level(), line));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+static INSTALL: Once = Once::new();
+
+// Start capturing warnings and errors, if not already
+pub fn capture() {
+    INSTALL.call_once(|| {
+        log::set_logger(&CAPTURE).expect("only one logger in the test binary");
+        log::set_max_level(LevelFilter::Warn);
+    });
+}
+
+// Records captured so far whose message starts with `prefix`. Tests run in
+// parallel, so the prefix should pick out the calling test's own records.
+pub fn records_starting_with(prefix: &str) -> Vec<(Level, String)> {
+    CAPTURE
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, line)| line.starts_with(prefix))
+        .cloned()
+        .collect()
+}
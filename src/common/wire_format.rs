@@ -0,0 +1,191 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
+
+// Encoding used on the wire between transmitter and actuator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SerializationFormat {
+    #[default]
+    Json,
+    Bincode,
+    MessagePack,
+}
+
+#[derive(Debug, Error)]
+pub enum WireError {
+    #[error("JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("bincode: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("MessagePack: {0}")]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    #[error("MessagePack: {0}")]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+}
+
+impl SerializationFormat {
+    pub const ALL: [SerializationFormat; 3] = [
+        SerializationFormat::Json,
+        SerializationFormat::Bincode,
+        SerializationFormat::MessagePack,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SerializationFormat::Json => "json",
+            SerializationFormat::Bincode => "bincode",
+            SerializationFormat::MessagePack => "messagepack",
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, WireError> {
+        Ok(match self {
+            SerializationFormat::Json => serde_json::to_vec(value)?,
+            SerializationFormat::Bincode => bincode::serialize(value)?,
+            SerializationFormat::MessagePack => rmp_serde::to_vec(value)?,
+        })
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, WireError> {
+        Ok(match self {
+            SerializationFormat::Json => serde_json::from_slice(bytes)?,
+            SerializationFormat::Bincode => bincode::deserialize(bytes)?,
+            SerializationFormat::MessagePack => rmp_serde::from_slice(bytes)?,
+        })
+    }
+
+    // Encode and frame a message. JSON stays newline-delimited; binary formats
+    // can contain newlines, so they get a 4-byte big-endian length prefix.
+    pub fn encode_frame<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, WireError> {
+        let payload = self.encode(value)?;
+        Ok(match self {
+            SerializationFormat::Json => {
+                let mut frame = payload;
+                frame.push(b'\n');
+                frame
+            }
+            SerializationFormat::Bincode | SerializationFormat::MessagePack => {
+                let mut frame = Vec::with_capacity(4 + payload.len());
+                frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+                frame.extend_from_slice(&payload);
+                frame
+            }
+        })
+    }
+
+    // Remove the first complete frame from `buffer` and return its payload,
+    // leaving any following bytes in place. Returns None if no frame is complete yet.
+    pub fn take_frame(&self, buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+        match self {
+            SerializationFormat::Json => {
+                let pos = buffer.iter().position(|&b| b == b'\n')?;
+                let mut line: Vec<u8> = buffer.drain(..=pos).collect();
+                line.pop(); // Drop the newline delimiter
+                Some(line)
+            }
+            SerializationFormat::Bincode | SerializationFormat::MessagePack => {
+                if buffer.len() < 4 {
+                    return None;
+                }
+                let len =
+                    u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
+                if buffer.len() < 4 + len {
+                    return None;
+                }
+                let frame: Vec<u8> = buffer.drain(..4 + len).skip(4).collect();
+                Some(frame)
+            }
+        }
+    }
+}
+
+impl FromStr for SerializationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SerializationFormat::ALL
+            .into_iter()
+            .find(|format| format.name() == s)
+            .ok_or_else(|| format!("unknown serialization format '{}'", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data_types::{
+        ActuatorCommand, ActuatorId, CommandPayload, ControlCommand, SensorData, SensorId,
+        SensorType,
+    };
+    use std::time::{Duration, Instant};
+
+    // Decode the one frame in `frame`, which must hold nothing else
+    fn unframe<T: DeserializeOwned>(format: SerializationFormat, mut frame: Vec<u8>) -> T {
+        let payload = format.take_frame(&mut frame).unwrap();
+        assert!(frame.is_empty());
+        format.decode(&payload).unwrap()
+    }
+
+    #[test]
+    fn sensor_data_round_trips_in_every_format() {
+        let data = SensorData {
+            timestamp: 1_700_000_000_123,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Velocity,
+            value: -12.5,
+            is_anomaly: true,
+            confidence: 0.25,
+            confidence_lower: Some(-14.0),
+            confidence_upper: None,
+            seq: 42,
+            rejected: false,
+        };
+        for format in SerializationFormat::ALL {
+            let decoded: SensorData = unframe(format, format.encode_frame(&data).unwrap());
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&data).unwrap(),
+                "{}",
+                format.name()
+            );
+        }
+    }
+
+    #[test]
+    fn actuator_commands_round_trip_in_every_format() {
+        let command = ActuatorCommand {
+            actuator_id: ActuatorId::new("actuator_1"),
+            control_command: ControlCommand {
+                command_type: "adjust_position".to_string(),
+                payload: Some(CommandPayload {
+                    value: Some(100.0),
+                    target: Some("new_target_position".to_string()),
+                }),
+                timestamp: 1_700_000_000_123,
+                value: 100.0,
+            },
+            priority: 1,
+            deadline: Instant::now() + Duration::from_secs(60),
+            command_id: Some("force_1#7/anomaly".to_string()),
+        };
+        for format in SerializationFormat::ALL {
+            let decoded: ActuatorCommand = unframe(format, format.encode_frame(&command).unwrap());
+            assert_eq!(decoded.actuator_id, command.actuator_id);
+            assert_eq!(decoded.control_command.command_type, "adjust_position");
+            assert_eq!(
+                decoded.control_command.payload,
+                command.control_command.payload
+            );
+            assert_eq!(decoded.control_command.timestamp, 1_700_000_000_123);
+            assert_eq!(decoded.control_command.value, 100.0);
+            assert_eq!(decoded.priority, 1);
+            assert_eq!(decoded.command_id, command.command_id);
+            // The deadline travels as milliseconds remaining
+            let skew = command.deadline.saturating_duration_since(decoded.deadline)
+                + decoded.deadline.saturating_duration_since(command.deadline);
+            assert!(skew < Duration::from_secs(1), "{}", format.name());
+        }
+    }
+}
@@ -1,6 +1,20 @@
+use crate::common::data_types::{SensorId, SensorType};
+use crate::common::wire_format::SerializationFormat;
+use crate::sensor::fault::FaultScenario;
+use crate::sensor::generator::SampleMode;
+use crate::sensor::processor::{default_thresholds, AnomalyMethod, FilterKind, TimestampGuard};
+use crate::sensor::stats::StatsBackendKind;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::Read;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Prefix for environment variable overrides, e.g. SENSORSYS_SENSOR_SAMPLE_RATE_MS=10
+pub const ENV_PREFIX: &str = "SENSORSYS";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -8,6 +22,8 @@ pub struct Config {
     pub processor: ProcessorConfig,
     pub transmitter: TransmitterConfig,
     pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub actuator: ActuatorConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,21 +32,244 @@ pub struct SensorConfig {
     pub num_sensors: usize,     // Number of sensors to simulate
     pub enable_anomalies: bool, // Whether to intentionally generate anomalies
     pub anomaly_rate: f64,      // Rate of anomaly generation (0.0-1.0)
+    #[serde(default)]
+    pub sample_mode: Option<SampleMode>, // Overrides sample_rate_ms pacing (e.g. bursts)
+    #[serde(default = "default_watchdog_timeout_ms")]
+    pub watchdog_timeout_ms: u64, // Warn when a sensor is silent this long (0 disables)
+    #[serde(default)]
+    pub value_ranges: HashMap<String, ValueRange>, // Saturation limits keyed by sensor_id
+    #[serde(default = "default_channel_capacity")]
+    pub channel_capacity: usize, // Buffer for raw readings (per fan-out channel)
+    #[serde(default)]
+    pub drift_reversion_rate: Option<f64>, // Mean-reverting drift toward the base value
+    #[serde(default = "default_sensor_types")]
+    pub sensor_types: Vec<SensorType>, // Types assigned round-robin to the sensors
+    #[serde(default)]
+    pub base_values: HashMap<String, f64>, // Base value overrides keyed by sensor_id (may be negative)
+    #[serde(default)]
+    pub jitter_ms: u64, // Random ±jitter on each sample interval (0 disables)
+    #[serde(default)]
+    pub fault_scenarios: HashMap<String, FaultScenario>, // Faults to play back keyed by sensor_id
+}
+
+fn default_sensor_types() -> Vec<SensorType> {
+    SensorType::ALL.to_vec()
+}
+
+fn default_channel_capacity() -> usize {
+    100
+}
+
+// Physical range a sensor saturates at (either bound may be omitted)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ValueRange {
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+fn default_watchdog_timeout_ms() -> u64 {
+    1000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessorConfig {
-    pub window_size: usize,     // Size of moving average window
-    pub anomaly_threshold: f64, // Base threshold for anomaly detection
+    pub window_size: usize, // Size of moving average window
+    #[serde(default)]
+    pub filter: FilterKind, // "window" or "ewma"
+    #[serde(default)]
+    pub anomaly_method: AnomalyMethod, // "zscore", "mad" or "rate_of_change"
+    #[serde(default)]
+    pub stats_backend: StatsBackendKind, // "window", "cumulative" or "ewma"
+    #[serde(default)]
+    pub update_on_anomaly: bool, // Feed z-score anomalies into the moving average
+    #[serde(default)]
+    pub max_rate_of_change: Option<f64>, // Max |Δvalue/Δt| per second (None disables)
+    #[serde(default)]
+    pub calibrations: HashMap<SensorId, Calibration>, // Per-sensor calibration keyed by sensor_id
+    #[serde(default)]
+    pub anomaly_cooldown_ms: HashMap<SensorType, u64>, // Don't re-flag a sensor within this window
+    #[serde(default = "default_anomaly_log_size")]
+    pub anomaly_log_size: usize, // How many recent anomalies to keep for inspection
+    #[serde(default)]
+    pub derive_velocity: bool, // Emit "<id>_velocity" readings from position sensors
+    #[serde(default = "default_confidence_band_k")]
+    pub confidence_band_k: f64, // Confidence band half-width in standard deviations
+    #[serde(default)]
+    pub timestamp_guard: TimestampGuard, // "flag" or "clamp" out-of-order timestamps
+    #[serde(default)]
+    pub min_confidence: Option<f64>, // Withhold non-anomalous readings below this confidence
+    #[serde(default = "default_channel_capacity")]
+    pub channel_capacity: usize, // Buffer for processed readings and actuator commands
+    #[serde(default)]
+    pub passthrough: bool, // Skip processing and forward raw readings to the transmitter
+    #[serde(default = "default_processor_workers")]
+    pub workers: usize, // Processor tasks, with sensors sharded across them by sensor_id
+    #[serde(default = "default_warmup_samples")]
+    pub warmup_samples: usize, // Readings per sensor before anomalies can be flagged
+    #[serde(default)]
+    pub hard_limits: HashMap<SensorType, HardLimits>, // Absolute limits checked on every reading
+    #[serde(default)]
+    pub thresholds: HashMap<SensorType, f64>, // Anomaly threshold per type; missing types keep the default
+    #[serde(default)]
+    pub output_deadline_us: Option<u64>, // Per-reading processing budget; readings are shed past it (None = never)
+    #[serde(default = "default_max_decimation")]
+    pub max_decimation: usize, // Most a sensor's readings are thinned by when shedding (1 in N)
+}
+
+fn default_max_decimation() -> usize {
+    16
+}
+
+// Absolute upper limits for a sensor type, applied to calibrated readings
+// regardless of the statistics (either may be omitted)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct HardLimits {
+    #[serde(default)]
+    pub warn: Option<f64>, // Log readings above this
+    #[serde(default)]
+    pub alarm: Option<f64>, // Flag readings above this as anomalies
+}
+
+fn default_warmup_samples() -> usize {
+    crate::sensor::processor::DEFAULT_WARMUP_SAMPLES
+}
+
+fn default_processor_workers() -> usize {
+    1
+}
+
+fn default_anomaly_log_size() -> usize {
+    crate::sensor::processor::DEFAULT_ANOMALY_LOG_SIZE
+}
+
+fn default_confidence_band_k() -> f64 {
+    crate::sensor::processor::DEFAULT_CONFIDENCE_BAND_K
+}
+
+// Linear calibration applied to raw readings: raw * scale + offset
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Calibration {
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Calibration {
+    pub fn apply(&self, raw: f64) -> f64 {
+        raw * self.scale + self.offset
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransmitterConfig {
     pub connection_type: String, // "tcp", "shared_memory", or "channel"
-    pub endpoint: String,        // For TCP: address:port
+    pub endpoint: Endpoint,      // For TCP: address:port
     pub shared_mem_name: String, // For shared memory: name
     pub buffer_size: usize,      // Buffer size for communication
     pub retry_attempts: usize,   // How many times to retry failed transmissions
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64, // How long to wait for a TCP connection
+    #[serde(default)]
+    pub serialization_format: SerializationFormat, // "json", "bincode" or "messagepack"
+    #[serde(default = "default_feedback_timeout_ms")]
+    pub feedback_timeout_ms: u64, // How long to wait for actuator feedback per reading
+    #[serde(default = "default_heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u64, // How often to check the actuator link (0 disables)
+    #[serde(default)]
+    pub stdout_jsonl: bool, // Also print each reading to stdout as a JSON line
+    #[serde(default)]
+    pub retry_queue_capacity: usize, // Failed readings held for resending (0 drops them)
+}
+
+// A host:port address, validated when the config is loaded rather than on the
+// first connection attempt. Stored in the config file as a plain string.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Endpoint {
+    host: String, // Without the brackets around IPv6 addresses
+    port: u16,
+}
+
+impl Endpoint {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+        }
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |problem: &str| {
+            format!(
+                "invalid endpoint '{}': {}, expected host:port (e.g. 127.0.0.1:8080)",
+                s, problem
+            )
+        };
+
+        let (host, port) = s.rsplit_once(':').ok_or_else(|| invalid("missing port"))?;
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        let port = match port.parse::<u16>() {
+            Ok(port) if port > 0 => port,
+            _ => return Err(invalid("port must be a number from 1 to 65535")),
+        };
+
+        Ok(Self::new(host, port))
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl TryFrom<String> for Endpoint {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Endpoint> for String {
+    fn from(endpoint: Endpoint) -> Self {
+        endpoint.to_string()
+    }
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    crate::sensor::transmitter::DEFAULT_CONNECT_TIMEOUT_MS
+}
+
+fn default_feedback_timeout_ms() -> u64 {
+    crate::sensor::transmitter::DEFAULT_FEEDBACK_TIMEOUT_MS
+}
+
+fn default_heartbeat_interval_ms() -> u64 {
+    crate::sensor::heartbeat::DEFAULT_HEARTBEAT_INTERVAL_MS
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,44 +277,353 @@ pub struct MetricsConfig {
     pub log_to_file: bool,       // Whether to log metrics to file
     pub log_file: String,        // Path to log file
     pub report_interval_ms: u64, // How often to report metrics
+    #[serde(default)]
+    pub records_file: Option<String>, // Optional JSON-lines file for raw metrics records
+    #[serde(default)]
+    pub metrics_csv: Option<String>, // Optional CSV file, one row per operation per report
+    #[serde(default = "default_histogram_buckets")]
+    pub histogram_buckets_ms: Vec<f64>, // Latency histogram upper bounds (+Inf is implicit)
+    #[serde(default = "default_deadlines")]
+    pub deadlines_ms: HashMap<String, f64>, // Per-operation deadline used for missed-deadline counts
+    #[serde(default = "default_max_samples_per_op")]
+    pub max_samples_per_op: usize, // Oldest samples are evicted past this between reports
+    #[serde(default = "default_metrics_channel_capacity")]
+    pub channel_capacity: usize, // Unread metrics buffered before new ones are dropped
+    #[serde(default = "default_throughput_window_ms")]
+    pub throughput_window_ms: u64, // Rolling window for per-sensor readings/sec
+    #[serde(default)]
+    pub max_log_bytes: Option<u64>, // Rotate log_file once it grows past this (None = never)
+    #[serde(default = "default_max_log_files")]
+    pub max_log_files: usize, // Rotated files kept as log_file.1 (newest) to log_file.N
+    #[serde(default)]
+    pub max_sensor_breakdowns: usize, // Sensors per operation reported with their own stats (0 = none)
 }
 
-impl Config {
-    // Load configuration from file
-    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut file = File::open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        let config: Config = serde_json::from_str(&contents)?;
-        Ok(config)
+fn default_max_log_files() -> usize {
+    5
+}
+
+fn default_throughput_window_ms() -> u64 {
+    1000
+}
+
+fn default_metrics_channel_capacity() -> usize {
+    10_000
+}
+
+fn default_max_samples_per_op() -> usize {
+    10_000
+}
+
+fn default_histogram_buckets() -> Vec<f64> {
+    vec![0.5, 1.0, 2.0, 5.0, 10.0]
+}
+
+fn default_deadlines() -> HashMap<String, f64> {
+    HashMap::from([
+        ("data_processing".to_string(), 2.0),
+        ("data_transmission".to_string(), 1.0),
+    ])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActuatorConfig {
+    pub setpoint: f64,           // Target value for the control loop
+    pub setpoint_ramp_rate: f64, // Max setpoint change per second (0 = jump immediately)
+    #[serde(default)]
+    pub command_routes: HashMap<String, String>, // command_type -> route; others go to "default"
+    #[serde(default)]
+    pub scheduling_policy: SchedulingPolicy, // Order commands queued on a route are handled in
+    #[serde(default)]
+    pub setpoint_source: SetpointSource, // Where the control loop's setpoint comes from
+    #[serde(default)]
+    pub execution_latency: ExecutionLatency, // Simulated delay before a command takes effect
+    #[serde(default)]
+    pub max_concurrent_executions: Option<usize>, // Delayed commands in flight at once; the control loop waits for a slot (None = unbounded)
+    #[serde(default)]
+    pub command_limits: HashMap<String, CommandLimits>, // Value limits keyed by command_type
+    #[serde(default)]
+    pub setpoint_commands: HashMap<String, SensorType>, // command_type -> setpoint its value sets
+    #[serde(default)]
+    pub gains: PidGains, // Control loop's PID gains
+}
+
+// Gains of the actuator's PID controller
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PidGains {
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+}
+
+impl Default for PidGains {
+    fn default() -> Self {
+        Self {
+            kp: 1.0,
+            ki: 0.1,
+            kd: 0.05,
+        }
     }
+}
 
+// Order commands waiting on a route are executed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedulingPolicy {
+    // In the order they arrived
+    #[default]
+    Fifo,
+    // Highest `priority` first
+    Priority,
+    // Earliest `deadline` first
+    Edf,
+}
+
+// Limits a command's value is clamped to before execution (any may be omitted)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CommandLimits {
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub max_rate_of_change: Option<f64>, // Max |Δvalue| per second between executed commands
+}
+
+// How long the simulated actuator takes to carry out a command
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionLatency {
+    // Execute as soon as the command is computed
+    #[default]
+    None,
+    // Always `ms` milliseconds
+    Fixed {
+        ms: u64,
+    },
+    // Uniformly random between `min_ms` and `max_ms`, per command
+    Uniform {
+        min_ms: u64,
+        max_ms: u64,
+    },
+}
+
+impl ExecutionLatency {
+    // Delay for one command
+    pub fn sample(&self, rng: &mut impl rand::Rng) -> Duration {
+        let ms = match *self {
+            ExecutionLatency::None => 0,
+            ExecutionLatency::Fixed { ms } => ms,
+            ExecutionLatency::Uniform { min_ms, max_ms } => {
+                rng.gen_range(min_ms.min(max_ms)..=max_ms.max(min_ms))
+            }
+        };
+        Duration::from_millis(ms)
+    }
+}
+
+// Where the actuator control loop gets its setpoint from
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SetpointSource {
+    // `setpoint`, retargeted by pushes on the control channel
+    #[default]
+    Static,
+    // `high` for the first half of each period, `low` for the second
+    Square {
+        low: f64,
+        high: f64,
+        period_s: f64,
+    },
+    // center + amplitude * sin(2πt / period_s)
+    Sine {
+        center: f64,
+        amplitude: f64,
+        period_s: f64,
+    },
+    // No setpoint (the loop idles) until one is pushed on the control channel
+    External,
+}
+
+impl SetpointSource {
+    // Waveform value `elapsed_s` seconds after start, None for non-waveform sources
+    pub fn waveform_at(&self, elapsed_s: f64) -> Option<f64> {
+        match *self {
+            SetpointSource::Square {
+                low,
+                high,
+                period_s,
+            } => {
+                if period_s <= 0.0 {
+                    return Some(high);
+                }
+                let phase = (elapsed_s / period_s).fract();
+                Some(if phase < 0.5 { high } else { low })
+            }
+            SetpointSource::Sine {
+                center,
+                amplitude,
+                period_s,
+            } => {
+                if period_s <= 0.0 {
+                    return Some(center);
+                }
+                let angle = std::f64::consts::TAU * elapsed_s / period_s;
+                Some(center + amplitude * angle.sin())
+            }
+            SetpointSource::Static | SetpointSource::External => None,
+        }
+    }
+}
+
+impl Default for ActuatorConfig {
+    fn default() -> Self {
+        Self {
+            setpoint: 50.0,           // Control loop target
+            setpoint_ramp_rate: 25.0, // Reach the target over 2 seconds from zero
+            command_routes: HashMap::new(), // Every command on the default route
+            scheduling_policy: SchedulingPolicy::Fifo, // Commands handled as they arrive
+            setpoint_source: SetpointSource::Static, // Fixed setpoint
+            execution_latency: ExecutionLatency::None, // Commands take effect immediately
+            max_concurrent_executions: None,           // No cap on commands in flight
+            command_limits: HashMap::new(),            // Commands execute unclamped
+            setpoint_commands: HashMap::new(),         // One setpoint for every sensor type
+            gains: PidGains::default(),
+        }
+    }
+}
+
+impl Default for Config {
     // Get default configuration
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             sensor: SensorConfig {
                 sample_rate_ms: 5,      // 5ms sample rate
                 num_sensors: 3,         // 3 sensors
                 enable_anomalies: true, // Enable anomaly generation
                 anomaly_rate: 0.01,     // 1% anomaly rate
+                sample_mode: None,      // Continuous sampling at sample_rate_ms
+                watchdog_timeout_ms: default_watchdog_timeout_ms(),
+                value_ranges: HashMap::new(), // No saturation limits
+                channel_capacity: default_channel_capacity(),
+                drift_reversion_rate: None, // Random-walk drift
+                sensor_types: default_sensor_types(),
+                base_values: HashMap::new(), // Each type's default base value
+                jitter_ms: 0,                // Evenly spaced samples
+                fault_scenarios: HashMap::new(), // Healthy sensors
             },
             processor: ProcessorConfig {
-                window_size: 20,        // 20 samples window
-                anomaly_threshold: 3.0, // 3 standard deviations
+                window_size: 20,              // 20 samples window
+                filter: FilterKind::Window,   // Moving window mean
+                anomaly_method: AnomalyMethod::ZScore,
+                stats_backend: StatsBackendKind::Window, // Statistics over the moving window
+                update_on_anomaly: false,     // Reject outliers from the window
+                max_rate_of_change: None,     // Rate-of-change detector disabled
+                calibrations: HashMap::new(), // No calibration (raw values)
+                anomaly_cooldown_ms: HashMap::new(), // Flag every anomalous reading
+                anomaly_log_size: default_anomaly_log_size(),
+                derive_velocity: false, // No derived sensors
+                confidence_band_k: default_confidence_band_k(),
+                timestamp_guard: TimestampGuard::Flag, // Log out-of-order timestamps
+                min_confidence: None,                  // Forward every reading
+                channel_capacity: default_channel_capacity(),
+                passthrough: false, // Smooth and score every reading
+                workers: default_processor_workers(),
+                warmup_samples: default_warmup_samples(),
+                hard_limits: HashMap::new(), // Statistical detection only
+                thresholds: default_thresholds(),
+                output_deadline_us: None, // Process every reading however slow
+                max_decimation: default_max_decimation(),
             },
             transmitter: TransmitterConfig {
                 connection_type: "channel".to_string(), // Default to in-process channel
-                endpoint: "127.0.0.1:8080".to_string(), // Default TCP endpoint
+                endpoint: Endpoint::new("127.0.0.1", 8080), // Default TCP endpoint
                 shared_mem_name: "sensor_data".to_string(), // Default shared memory name
                 buffer_size: 1024,                      // 1KB buffer
                 retry_attempts: 3,                      // 3 retry attempts
+                connect_timeout_ms: default_connect_timeout_ms(),
+                serialization_format: SerializationFormat::Json,
+                feedback_timeout_ms: default_feedback_timeout_ms(),
+                heartbeat_interval_ms: default_heartbeat_interval_ms(),
+                stdout_jsonl: false,
+                retry_queue_capacity: 0, // Drop readings that exhaust their retries
             },
             metrics: MetricsConfig {
                 log_to_file: true,                   // Log metrics to file
                 log_file: "metrics.log".to_string(), // Default log file
                 report_interval_ms: 1000,            // Report every second
+                records_file: None,                  // Don't export raw records
+                metrics_csv: None,                   // Don't export CSV rows
+                histogram_buckets_ms: default_histogram_buckets(),
+                deadlines_ms: default_deadlines(),
+                max_samples_per_op: default_max_samples_per_op(),
+                channel_capacity: default_metrics_channel_capacity(),
+                throughput_window_ms: default_throughput_window_ms(),
+                max_log_bytes: None, // Let the log grow
+                max_log_files: default_max_log_files(),
+                max_sensor_breakdowns: 0, // Aggregate across sensors only
             },
+            actuator: ActuatorConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    // Load configuration from file
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut file = File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let config: Config = serde_json::from_str(&contents)?;
+        Ok(config)
+    }
+
+    // Load and merge several configuration files, later files overriding earlier
+    // ones (e.g. a base config plus an environment overlay). See merge_values for
+    // how sections combine; the merged result must be a complete configuration.
+    pub fn from_files(paths: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut merged = Value::Object(serde_json::Map::new());
+        for path in paths {
+            let mut contents = String::new();
+            File::open(path)?.read_to_string(&mut contents)?;
+            let overlay: Value = serde_json::from_str(&contents)
+                .map_err(|e| format!("Invalid config file {}: {}", path, e))?;
+            merge_values(&mut merged, overlay);
         }
+        Ok(serde_json::from_value(merged)?)
+    }
+
+    // Override fields from environment variables named PREFIX_SECTION_FIELD.
+    // Precedence is CLI > env > file > default, so call this before applying CLI args.
+    pub fn apply_env_overrides(&mut self, prefix: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut value = serde_json::to_value(&*self)?;
+
+        if let Some(sections) = value.as_object_mut() {
+            for (section, fields) in sections.iter_mut() {
+                let Some(fields) = fields.as_object_mut() else {
+                    continue;
+                };
+                for (field, current) in fields.iter_mut() {
+                    let key = format!("{}_{}_{}", prefix, section, field).to_uppercase();
+                    if let Ok(raw) = std::env::var(&key) {
+                        *current = parse_env_value(&key, &raw, current)?;
+                    }
+                }
+            }
+        }
+
+        *self = serde_json::from_value(value)?;
+        Ok(())
+    }
+
+    // Dotted paths (e.g. "processor.window_size") of every field that differs
+    // in `other`. Keyed maps are compared key by key, arrays as a whole.
+    pub fn changed_fields(&self, other: &Config) -> Vec<String> {
+        let old = serde_json::to_value(self).expect("Config always serializes");
+        let new = serde_json::to_value(other).expect("Config always serializes");
+        let mut changed = Vec::new();
+        diff_values("", &old, &new, &mut changed);
+        changed
     }
 
     // Save configuration to file
@@ -85,3 +633,250 @@ impl Config {
         Ok(())
     }
 }
+
+// Deep-merge `overlay` into `base`. Objects (the sections, and keyed maps such as
+// calibrations or command_routes) merge key by key, so an overlay only needs the
+// fields it changes. Scalars and arrays (e.g. sensor_types) are replaced whole,
+// as are tagged enums whose "type" changes, so no fields of the old variant remain.
+fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            let retagged = matches!(
+                (base.get("type"), overlay.get("type")),
+                (Some(old), Some(new)) if old != new
+            );
+            if retagged {
+                *base = overlay;
+                return;
+            }
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changed: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let keys: std::collections::BTreeSet<_> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_values(&path, old, new, changed),
+                    _ => changed.push(path),
+                }
+            }
+        }
+        (old, new) if old != new => changed.push(path.to_string()),
+        _ => {}
+    }
+}
+
+// Strings are taken verbatim, anything else (numbers, booleans) is parsed as JSON
+fn parse_env_value(
+    key: &str,
+    raw: &str,
+    current: &Value,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    match current {
+        Value::String(_) => Ok(Value::String(raw.to_string())),
+        // Unset optional fields accept either JSON or a plain string
+        Value::Null => {
+            Ok(serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())))
+        }
+        _ => serde_json::from_str(raw)
+            .map_err(|e| format!("Invalid value for {}: {}", key, e).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::processor::DataProcessor;
+    use crate::sensor::stats::MovingWindow;
+    use std::path::PathBuf;
+
+    // Write `contents` to a file of its own under the system temp dir
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rust_assignment_config_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn processor_uses_a_threshold_loaded_from_file() {
+        let mut value = serde_json::to_value(Config::default()).unwrap();
+        value["processor"]["thresholds"]["Force"] = 4.25.into();
+        // Files written before per-type thresholds still load
+        value["processor"]["anomaly_threshold"] = 3.0.into();
+        let path = temp_file("force_threshold.json", &value.to_string());
+
+        let config = Config::from_file(path.to_str().unwrap()).unwrap();
+        let processor = DataProcessor::<MovingWindow>::from_config(&config.processor);
+        assert_eq!(processor.threshold(SensorType::Force), 4.25);
+        assert_eq!(
+            processor.threshold(SensorType::Position),
+            default_thresholds()[&SensorType::Position]
+        );
+    }
+
+    #[test]
+    fn endpoints_round_trip_through_strings() {
+        for text in ["127.0.0.1:8080", "actuator.local:9000", "[::1]:8080"] {
+            let endpoint: Endpoint = text.parse().unwrap();
+            assert_eq!(endpoint.to_string(), text);
+        }
+        let ipv6: Endpoint = "[::1]:8080".parse().unwrap();
+        assert_eq!((ipv6.host(), ipv6.port()), ("::1", 8080));
+
+        // Stored in config files as the same string
+        let json = serde_json::to_string(&Endpoint::new("127.0.0.1", 8080)).unwrap();
+        assert_eq!(json, "\"127.0.0.1:8080\"");
+        assert!(serde_json::from_str::<Endpoint>("\"127.0.0.1\"").is_err());
+    }
+
+    #[test]
+    fn malformed_endpoints_are_rejected() {
+        for (text, problem) in [
+            ("127.0.0.1", "missing port"),
+            (":8080", "missing host"),
+            ("[]:8080", "missing host"),
+            ("127.0.0.1:0", "port must be"),
+            ("127.0.0.1:65536", "port must be"),
+            ("127.0.0.1:http", "port must be"),
+        ] {
+            let error = text.parse::<Endpoint>().unwrap_err();
+            assert!(error.contains(problem), "{}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn later_config_files_override_nested_keys_and_keep_the_rest() {
+        let mut base = serde_json::to_value(Config::default()).unwrap();
+        base["processor"]["calibrations"] = serde_json::json!({
+            "force_1": {"scale": 2.0, "offset": 0.0},
+            "force_2": {"scale": 3.0, "offset": 1.0},
+        });
+        let base = temp_file("base.json", &base.to_string());
+        let overlay = serde_json::json!({
+            "processor": {
+                "window_size": 50,
+                "calibrations": {"force_2": {"scale": 4.0, "offset": 0.5}},
+            },
+            "sensor": {"sensor_types": ["Temperature"]},
+        });
+        let overlay = temp_file("overlay.json", &overlay.to_string());
+
+        let config =
+            Config::from_files(&[base.to_str().unwrap(), overlay.to_str().unwrap()]).unwrap();
+        let defaults = Config::default();
+        assert_eq!(config.processor.window_size, 50);
+        assert_eq!(config.processor.filter, defaults.processor.filter);
+        assert_eq!(config.sensor.num_sensors, defaults.sensor.num_sensors);
+
+        // Keyed maps merge entry by entry, arrays are replaced whole
+        assert_eq!(config.processor.calibrations["force_1"].scale, 2.0);
+        assert_eq!(config.processor.calibrations["force_2"].scale, 4.0);
+        assert_eq!(config.sensor.sensor_types, [SensorType::Temperature]);
+    }
+
+    #[test]
+    fn changing_a_tagged_enum_variant_replaces_it_whole() {
+        let mut base = serde_json::to_value(Config::default()).unwrap();
+        base["actuator"]["setpoint_source"] =
+            serde_json::json!({"type": "square", "low": 10.0, "high": 20.0, "period_s": 1.0});
+        let base = temp_file("square.json", &base.to_string());
+        let overlay = serde_json::json!({
+            "actuator": {
+                "setpoint_source": {"type": "sine", "center": 5.0, "amplitude": 1.0, "period_s": 2.0},
+            },
+        });
+        let overlay = temp_file("sine.json", &overlay.to_string());
+
+        let config =
+            Config::from_files(&[base.to_str().unwrap(), overlay.to_str().unwrap()]).unwrap();
+        let source = serde_json::to_value(config.actuator.setpoint_source).unwrap();
+        assert_eq!(
+            source,
+            serde_json::json!({"type": "sine", "center": 5.0, "amplitude": 1.0, "period_s": 2.0})
+        );
+    }
+
+    #[test]
+    fn env_vars_override_file_values() {
+        let mut config = Config::default();
+        config.processor.window_size = 40;
+        // A prefix of its own, so no other test sees these
+        std::env::set_var("RA_ENV_TEST_PROCESSOR_WINDOW_SIZE", "64");
+        std::env::set_var("RA_ENV_TEST_TRANSMITTER_ENDPOINT", "10.0.0.2:9000");
+
+        config.apply_env_overrides("RA_ENV_TEST").unwrap();
+        assert_eq!(config.processor.window_size, 64);
+        assert_eq!(config.transmitter.endpoint, Endpoint::new("10.0.0.2", 9000));
+        assert_eq!(
+            config.sensor.num_sensors,
+            Config::default().sensor.num_sensors
+        );
+    }
+
+    #[test]
+    fn malformed_env_values_are_rejected() {
+        std::env::set_var("RA_BAD_ENV_TEST_PROCESSOR_WINDOW_SIZE", "lots");
+        let mut config = Config::default();
+        let error = config.apply_env_overrides("RA_BAD_ENV_TEST").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("RA_BAD_ENV_TEST_PROCESSOR_WINDOW_SIZE"),
+            "{}",
+            error
+        );
+
+        // Well-formed JSON of the wrong type fails too
+        std::env::set_var("RA_BAD_ENV_TEST_PROCESSOR_WINDOW_SIZE", "-3");
+        assert!(config.apply_env_overrides("RA_BAD_ENV_TEST").is_err());
+    }
+
+    #[test]
+    fn pid_output_follows_each_square_wave_transition() {
+        let source = SetpointSource::Square {
+            low: 0.0,
+            high: 100.0,
+            period_s: 2.0,
+        };
+        let gains = PidGains::default();
+        let mut pid = crate::actuator::controller::PIDController::new(gains.kp, gains.ki, gains.kd);
+
+        // Hold the measurement at 50 for 4s, so the output's sign tracks the setpoint
+        let dt = 0.1;
+        let mut previous = None;
+        let mut transitions = 0;
+        for step in 0..40 {
+            let setpoint = source.waveform_at(step as f64 * dt).unwrap();
+            let output = pid.compute(setpoint, 50.0, dt).value;
+            assert_eq!(output > 0.0, setpoint == 100.0, "at step {}", step);
+            if previous.is_some_and(|previous| previous != setpoint) {
+                transitions += 1;
+            }
+            previous = Some(setpoint);
+        }
+        assert_eq!(transitions, 3);
+
+        assert_eq!(SetpointSource::Static.waveform_at(1.0), None);
+        assert_eq!(SetpointSource::External.waveform_at(1.0), None);
+    }
+}
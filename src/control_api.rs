@@ -0,0 +1,304 @@
+use crate::common::control::{ActuatorControl, ControlHandles, ProcessorControl};
+use crate::common::data_types::{SensorId, SensorType};
+use crate::common::metrics::OperationStats;
+use crate::config::{Config, PidGains};
+use crate::sensor::anomaly_log::AnomalyEvent;
+use crate::sensor::processor::{ProcessorSnapshot, SensorStats};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use crossbeam_channel::Sender;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+#[derive(Deserialize)]
+struct RecentQuery {
+    n: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct RetryQueueResponse {
+    depth: usize, // Failed readings waiting to be resent
+}
+
+#[derive(Deserialize)]
+struct ThresholdRequest {
+    sensor_type: SensorType,
+    threshold: f64,
+}
+
+#[derive(Deserialize)]
+struct WindowSizeRequest {
+    window_size: usize,
+}
+
+#[derive(Deserialize)]
+struct ResetRequest {
+    sensor_id: Option<SensorId>, // Omit to reset every sensor
+}
+
+#[derive(Deserialize)]
+struct SetpointRequest {
+    setpoint: f64,
+}
+
+#[derive(Deserialize)]
+struct GainsRequest {
+    kp: f64,
+    ki: f64,
+    kd: f64,
+}
+
+pub fn router(handles: ControlHandles) -> Router {
+    Router::new()
+        .route("/config", get(get_config))
+        .route("/metrics", get(get_metrics))
+        .route("/anomalies", get(get_anomalies))
+        .route("/sensors", get(get_sensor_stats))
+        .route("/snapshot", get(get_snapshot))
+        .route("/retry_queue", get(get_retry_queue))
+        .route("/threshold", post(set_threshold))
+        .route("/window_size", post(set_window_size))
+        .route("/reset", post(reset))
+        .route("/setpoint", post(set_setpoint))
+        .route("/gains", post(set_gains))
+        .with_state(handles)
+}
+
+// Serve the control API until the listener fails
+pub async fn run_control_api(addr: &str, handles: ControlHandles) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Control API listening on {}", addr);
+    axum::serve(listener, router(handles)).await
+}
+
+async fn get_config(State(handles): State<ControlHandles>) -> Json<Config> {
+    Json(handles.config.lock().unwrap().clone())
+}
+
+async fn get_metrics(
+    State(handles): State<ControlHandles>,
+) -> Json<HashMap<String, OperationStats>> {
+    Json(handles.latest_report.lock().unwrap().clone())
+}
+
+async fn get_anomalies(
+    State(handles): State<ControlHandles>,
+    Query(query): Query<RecentQuery>,
+) -> Json<Vec<AnomalyEvent>> {
+    Json(handles.anomaly_log.recent(query.n.unwrap_or(20)))
+}
+
+async fn get_sensor_stats(
+    State(handles): State<ControlHandles>,
+) -> Json<HashMap<SensorId, SensorStats>> {
+    Json(handles.sensor_stats.lock().unwrap().clone())
+}
+
+async fn get_retry_queue(State(handles): State<ControlHandles>) -> Json<RetryQueueResponse> {
+    Json(RetryQueueResponse {
+        depth: handles.retry_queue_depth.load(Ordering::Relaxed),
+    })
+}
+
+// Asks the processor directly, so this reflects readings since the last
+// published stats. Answered once the processor handles its next reading.
+async fn get_snapshot(
+    State(handles): State<ControlHandles>,
+) -> Result<Json<ProcessorSnapshot>, StatusCode> {
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+    if handles
+        .processor_tx
+        .send(ProcessorControl::Snapshot(reply_tx))
+        .is_err()
+    {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    reply_rx
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::SERVICE_UNAVAILABLE)
+}
+
+async fn set_threshold(
+    State(handles): State<ControlHandles>,
+    Json(request): Json<ThresholdRequest>,
+) -> StatusCode {
+    let control = ProcessorControl::SetThreshold {
+        sensor_type: request.sensor_type,
+        threshold: request.threshold,
+    };
+    let status = send_control(&handles.processor_tx, control);
+    if status.is_success() {
+        handles
+            .config
+            .lock()
+            .unwrap()
+            .processor
+            .thresholds
+            .insert(request.sensor_type, request.threshold);
+    }
+    status
+}
+
+async fn set_window_size(
+    State(handles): State<ControlHandles>,
+    Json(request): Json<WindowSizeRequest>,
+) -> StatusCode {
+    let control = ProcessorControl::SetWindowSize(request.window_size);
+    let status = send_control(&handles.processor_tx, control);
+    if status.is_success() {
+        handles.config.lock().unwrap().processor.window_size = request.window_size;
+    }
+    status
+}
+
+async fn reset(
+    State(handles): State<ControlHandles>,
+    Json(request): Json<ResetRequest>,
+) -> StatusCode {
+    send_control(&handles.processor_tx, ProcessorControl::Reset(request.sensor_id))
+}
+
+async fn set_setpoint(
+    State(handles): State<ControlHandles>,
+    Json(request): Json<SetpointRequest>,
+) -> StatusCode {
+    let control = ActuatorControl::SetSetpoint(request.setpoint);
+    let status = send_control(&handles.actuator_tx, control);
+    if status.is_success() {
+        handles.config.lock().unwrap().actuator.setpoint = request.setpoint;
+    }
+    status
+}
+
+async fn set_gains(
+    State(handles): State<ControlHandles>,
+    Json(request): Json<GainsRequest>,
+) -> StatusCode {
+    let control = ActuatorControl::SetGains {
+        kp: request.kp,
+        ki: request.ki,
+        kd: request.kd,
+    };
+    let status = send_control(&handles.actuator_tx, control);
+    if status.is_success() {
+        handles.config.lock().unwrap().actuator.gains = PidGains {
+            kp: request.kp,
+            ki: request.ki,
+            kd: request.kd,
+        };
+    }
+    status
+}
+
+// The receiving task has stopped if the send fails
+fn send_control<T>(tx: &Sender<T>, control: T) -> StatusCode {
+    match tx.send(control) {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::anomaly_log::AnomalyLog;
+    use crate::sensor::processor::DataProcessor;
+    use crate::sensor::stats::MovingWindow;
+    use crate::sensor::transmitter::SharedQueueDepth;
+    use crossbeam_channel::Receiver;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Serve the API on a free port; returns its address and the control receivers
+    async fn serve(
+        config: Config,
+    ) -> (
+        String,
+        ControlHandles,
+        Receiver<ProcessorControl>,
+        Receiver<ActuatorControl>,
+    ) {
+        let (processor_tx, processor_rx) = crossbeam_channel::unbounded();
+        let (actuator_tx, actuator_rx) = crossbeam_channel::unbounded();
+        let handles = ControlHandles {
+            config: Arc::new(Mutex::new(config)),
+            latest_report: Default::default(),
+            anomaly_log: AnomalyLog::new(8),
+            sensor_stats: Default::default(),
+            retry_queue_depth: SharedQueueDepth::default(),
+            processor_tx,
+            actuator_tx,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let app = router(handles.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (addr, handles, processor_rx, actuator_rx)
+    }
+
+    // POST `body` as JSON to `path`, returning the response's status line
+    async fn post(addr: &str, path: &str, body: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            path,
+            addr,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn posted_threshold_reaches_the_processor_and_the_config() {
+        let config = Config::default();
+        let mut processor = DataProcessor::<MovingWindow>::from_config(&config.processor);
+        let (addr, handles, processor_rx, _actuator_rx) = serve(config).await;
+
+        let status = post(
+            &addr,
+            "/threshold",
+            r#"{"sensor_type":"Force","threshold":7.5}"#,
+        )
+        .await;
+        assert_eq!(status, "HTTP/1.1 204 No Content");
+
+        processor.apply_control(processor_rx.try_recv().unwrap());
+        assert_eq!(processor.threshold(SensorType::Force), 7.5);
+        let config = handles.config.lock().unwrap();
+        assert_eq!(
+            config.processor.thresholds.get(&SensorType::Force),
+            Some(&7.5)
+        );
+    }
+
+    #[tokio::test]
+    async fn posted_gains_are_recorded_in_the_config() {
+        let (addr, handles, _processor_rx, actuator_rx) = serve(Config::default()).await;
+
+        let status = post(&addr, "/gains", r#"{"kp":2.0,"ki":0.5,"kd":0.25}"#).await;
+        assert_eq!(status, "HTTP/1.1 204 No Content");
+
+        assert!(matches!(
+            actuator_rx.try_recv(),
+            Ok(ActuatorControl::SetGains { kp, .. }) if kp == 2.0
+        ));
+        let gains = handles.config.lock().unwrap().actuator.gains;
+        assert_eq!(
+            gains,
+            PidGains {
+                kp: 2.0,
+                ki: 0.5,
+                kd: 0.25
+            }
+        );
+    }
+}
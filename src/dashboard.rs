@@ -0,0 +1,262 @@
+use crate::common::data_types::{PerformanceMetrics, SensorData, SensorId, SensorType};
+use crate::common::metrics::MetricsReceiver;
+use crossbeam_channel::{Receiver, TryRecvError};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::ExecutableCommand;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders, Row, Sparkline, Table};
+use ratatui::{Frame, Terminal};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::time::Duration;
+
+// Processing latencies kept for the sparkline
+const LATENCY_HISTORY: usize = 200;
+
+// Latest state of one sensor as shown on the dashboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensorView {
+    pub reading_type: SensorType,
+    pub value: f64,
+    pub is_anomaly: bool, // Whether the latest reading was flagged
+    pub readings: usize,
+    pub anomalies: usize,
+}
+
+// Everything the dashboard draws, built up from processed readings and metrics
+#[derive(Debug, Default)]
+pub struct DashboardState {
+    sensors: BTreeMap<SensorId, SensorView>, // Sorted so rows don't jump around
+    latencies_us: VecDeque<u64>,             // Recent data_processing durations
+}
+
+impl DashboardState {
+    pub fn record_reading(&mut self, data: &SensorData) {
+        let view = self
+            .sensors
+            .entry(data.sensor_id.clone())
+            .or_insert(SensorView {
+                reading_type: data.reading_type,
+                value: data.value,
+                is_anomaly: false,
+                readings: 0,
+                anomalies: 0,
+            });
+        view.value = data.value;
+        view.is_anomaly = data.is_anomaly;
+        view.readings += 1;
+        if data.is_anomaly {
+            view.anomalies += 1;
+        }
+    }
+
+    // Only processing latencies feed the sparkline
+    pub fn record_metrics(&mut self, metrics: &PerformanceMetrics) {
+        if metrics.operation != "data_processing" {
+            return;
+        }
+        let Some(duration_ms) = metrics.duration_ms else {
+            return;
+        };
+        if self.latencies_us.len() == LATENCY_HISTORY {
+            self.latencies_us.pop_front();
+        }
+        self.latencies_us
+            .push_back((duration_ms * 1000.0).round() as u64);
+    }
+
+    // Mean of the recent processing latencies, None before the first one
+    pub fn avg_latency_us(&self) -> Option<f64> {
+        (!self.latencies_us.is_empty())
+            .then(|| self.latencies_us.iter().sum::<u64>() as f64 / self.latencies_us.len() as f64)
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let [sensors_area, latency_area] =
+            Layout::vertical([Constraint::Min(4), Constraint::Length(7)]).areas(frame.size());
+
+        let rows = self.sensors.iter().map(|(sensor_id, view)| {
+            let (status, style) = if view.is_anomaly {
+                ("ANOMALY", Style::default().fg(Color::Red))
+            } else {
+                ("ok", Style::default())
+            };
+            Row::new(vec![
+                sensor_id.to_string(),
+                view.reading_type.name().to_string(),
+                format!("{:.3}", view.value),
+                status.to_string(),
+                view.readings.to_string(),
+                view.anomalies.to_string(),
+            ])
+            .style(style)
+        });
+        let widths = [
+            Constraint::Percentage(25),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+            Constraint::Percentage(15),
+        ];
+        let table = Table::new(rows, widths)
+            .header(Row::new(vec![
+                "Sensor",
+                "Type",
+                "Value",
+                "Status",
+                "Readings",
+                "Anomalies",
+            ]))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Sensors (q to quit)"),
+            );
+        frame.render_widget(table, sensors_area);
+
+        let latencies: Vec<u64> = self.latencies_us.iter().copied().collect();
+        let title = match self.avg_latency_us() {
+            Some(avg) => format!("Processing latency (avg {:.1}µs)", avg),
+            None => "Processing latency (no data yet)".to_string(),
+        };
+        let sparkline = Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .data(&latencies)
+            .style(Style::default().fg(Color::Cyan));
+        frame.render_widget(sparkline, latency_area);
+    }
+}
+
+// Draw the dashboard every `refresh` until the user presses q or Esc, or the
+// processed reading channel closes. Blocks, so run it off the async runtime.
+pub fn run_dashboard(
+    processed_rx: Receiver<SensorData>,
+    metrics_rx: MetricsReceiver,
+    refresh: Duration,
+) -> io::Result<()> {
+    enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    let result = draw_until_quit(&processed_rx, &metrics_rx, refresh);
+    // Restore the terminal even if drawing failed
+    disable_raw_mode()?;
+    io::stdout().execute(LeaveAlternateScreen)?;
+    result
+}
+
+fn draw_until_quit(
+    processed_rx: &Receiver<SensorData>,
+    metrics_rx: &MetricsReceiver,
+    refresh: Duration,
+) -> io::Result<()> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let mut state = DashboardState::default();
+
+    loop {
+        loop {
+            match processed_rx.try_recv() {
+                Ok(data) => state.record_reading(&data),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+        while let Ok(metrics) = metrics_rx.try_recv() {
+            state.record_metrics(&metrics);
+        }
+
+        terminal.draw(|frame| state.render(frame))?;
+
+        if event::poll(refresh)? {
+            if let Event::Key(key) = event::read()? {
+                let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc);
+                if key.kind == KeyEventKind::Press && quit {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(sensor_id: &str, value: f64, is_anomaly: bool) -> SensorData {
+        SensorData {
+            timestamp: 1_000,
+            sensor_id: SensorId::new(sensor_id),
+            reading_type: SensorType::Force,
+            value,
+            is_anomaly,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq: 0,
+            rejected: false,
+        }
+    }
+
+    fn processing(duration_ms: f64) -> PerformanceMetrics {
+        let mut metrics = PerformanceMetrics::new("data_processing");
+        metrics.complete(true);
+        metrics.duration_ms = Some(duration_ms);
+        metrics
+    }
+
+    #[test]
+    fn state_summarizes_readings_and_latencies() {
+        let mut state = DashboardState::default();
+        assert_eq!(state.avg_latency_us(), None);
+
+        state.record_reading(&reading("force_2", 1.0, false));
+        state.record_reading(&reading("force_1", 5.0, false));
+        state.record_reading(&reading("force_1", 40.0, true));
+        state.record_reading(&reading("force_1", 6.0, false));
+        state.record_metrics(&processing(0.010));
+        state.record_metrics(&processing(0.030));
+        // Other operations don't feed the sparkline
+        let mut transmission = processing(5.0);
+        transmission.operation = "data_transmission".to_string();
+        state.record_metrics(&transmission);
+
+        let ids: Vec<_> = state.sensors.keys().map(|id| id.to_string()).collect();
+        assert_eq!(ids, ["force_1", "force_2"]);
+        assert_eq!(
+            state.sensors[&SensorId::new("force_1")],
+            SensorView {
+                reading_type: SensorType::Force,
+                value: 6.0,
+                is_anomaly: false,
+                readings: 3,
+                anomalies: 1,
+            }
+        );
+        assert_eq!(state.sensors[&SensorId::new("force_2")].readings, 1);
+        assert_eq!(state.avg_latency_us(), Some(20.0));
+    }
+
+    #[test]
+    fn latest_anomaly_and_average_latency_are_drawn() {
+        let mut state = DashboardState::default();
+        state.record_reading(&reading("force_1", 40.0, true));
+        state.record_metrics(&processing(0.025));
+
+        let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(80, 16)).unwrap();
+        terminal.draw(|frame| state.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("force_1"));
+        assert!(screen.contains("ANOMALY"));
+        assert!(screen.contains("avg 25.0µs"));
+    }
+}
@@ -1,5 +1,6 @@
 // src/lib.rs
 
+pub mod actuator;
 pub mod common;
 pub mod config;
 pub mod sensor;
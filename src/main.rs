@@ -1,12 +1,18 @@
-mod actuator;
-mod common;
-mod config;
-mod sensor;
+#[cfg(feature = "control-api")]
+mod control_api;
+#[cfg(feature = "tui")]
+mod dashboard;
+
+use rust_assignment::{actuator, common, config, sensor};
 
 use actuator::system::run_actuator_system;
 use clap::{Parser, Subcommand};
-use crossbeam_channel::{bounded, unbounded};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use log::{debug, info, warn, LevelFilter};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 #[derive(Parser)]
 #[command(name = "sensor_system")]
@@ -14,27 +20,120 @@ use std::path::PathBuf;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Log level (off, error, warn, info, debug, trace)
+    #[arg(long, global = true, default_value = "info", value_parser = parse_log_level)]
+    log_level: LevelFilter,
+}
+
+fn parse_log_level(level: &str) -> Result<LevelFilter, String> {
+    level.parse().map_err(|_| {
+        format!(
+            "invalid log level '{}', expected one of: off, error, warn, info, debug, trace",
+            level
+        )
+    })
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Run the sensor system
     Run {
-        /// Path to configuration file
+        /// Path to configuration file; repeat to layer overlays over a base file
         #[arg(short, long, value_name = "FILE")]
-        config: Option<PathBuf>,
+        config: Vec<PathBuf>,
 
         /// Connection mode (tcp, shared_memory, channel)
-        #[arg(short, long, default_value = "channel")]
-        mode: String,
+        #[arg(short, long)]
+        mode: Option<String>,
 
         /// Endpoint for connection (IP:PORT for TCP)
         #[arg(short, long)]
-        endpoint: Option<String>,
+        endpoint: Option<config::Endpoint>,
 
         /// Sample rate in milliseconds
         #[arg(short, long)]
         sample_rate: Option<u64>,
+
+        /// Replay readings from a CSV file instead of generating them
+        #[arg(long, value_name = "FILE")]
+        replay: Option<PathBuf>,
+
+        /// Replay speed multiplier (2.0 = twice as fast, 0 = as fast as possible)
+        #[arg(long, default_value = "1.0")]
+        replay_speed: f64,
+
+        /// Sensor types to run, comma separated (e.g. force,temperature)
+        #[arg(long, value_delimiter = ',')]
+        sensors: Option<Vec<common::data_types::SensorType>>,
+
+        /// Processor filter (window, ewma)
+        #[arg(long)]
+        filter: Option<sensor::processor::FilterKind>,
+
+        /// Anomaly detection method (zscore, mad, rate_of_change)
+        #[arg(long)]
+        anomaly_method: Option<sensor::processor::AnomalyMethod>,
+
+        /// Send raw readings straight to the transmitter, skipping the processor
+        #[arg(long)]
+        passthrough: bool,
+
+        /// Print each processed reading to stdout as a JSON line (logs stay on stderr)
+        #[arg(long)]
+        stdout_jsonl: bool,
+
+        /// Address for the HTTP control API (requires the control-api feature)
+        #[arg(long, value_name = "IP:PORT")]
+        control_addr: Option<String>,
+    },
+
+    /// Run the sensors and processor with a live terminal dashboard
+    #[cfg(feature = "tui")]
+    Dashboard {
+        /// Path to configuration file; repeat to layer overlays over a base file
+        #[arg(short, long, value_name = "FILE")]
+        config: Vec<PathBuf>,
+
+        /// Redraw interval in milliseconds
+        #[arg(long, default_value = "200")]
+        refresh_ms: u64,
+    },
+
+    /// Run the actuator as a TCP server
+    Serve {
+        /// Address to listen on (IP:PORT)
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        endpoint: config::Endpoint,
+
+        /// Wire format (json, bincode, messagepack); must match the transmitter
+        #[arg(short, long, default_value = "json")]
+        format: common::wire_format::SerializationFormat,
+    },
+
+    /// List supported sensor types, connection types and default thresholds
+    Info,
+
+    /// Print JSON Schemas for SensorData, ActuatorCommand and ActuatorFeedback
+    Schema {
+        /// Write the schemas to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Check a recorded metrics file (metrics.records_file) against an SLA
+    Sla {
+        /// JSON-lines metrics records to check
+        #[arg(short, long, value_name = "FILE")]
+        input: PathBuf,
+
+        /// Latency bound in milliseconds
+        #[arg(long)]
+        max_latency_ms: f64,
+
+        /// Share (0.0-1.0) of each operation that must succeed and finish within the bound
+        #[arg(long)]
+        min_success_rate: f64,
     },
 
     /// Generate default configuration file
@@ -50,6 +149,10 @@ enum Commands {
         #[arg(short, long, default_value = "1000")]
         iterations: usize,
 
+        /// Seed for the benchmark dataset; the same seed replays the same readings
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
         /// Path to output benchmark results
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
@@ -60,149 +163,489 @@ enum Commands {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
+    // Logs go to stderr so stdout can carry data (see --stdout-jsonl)
+    env_logger::Builder::new()
+        .filter_level(cli.log_level)
+        .target(env_logger::Target::Stderr)
+        .init();
+
     match cli.command {
         Commands::Run {
-            config,
+            config: config_paths,
             mode,
             endpoint,
             sample_rate,
+            replay,
+            replay_speed,
+            sensors,
+            filter,
+            anomaly_method,
+            passthrough,
+            stdout_jsonl,
+            control_addr,
         } => {
             // Load configuration
-            let mut config = match config {
-                Some(path) => config::Config::from_file(path.to_str().unwrap())?,
-                None => config::Config::default(),
-            };
+            let mut config = load_config(&config_paths)?;
+
+            // Environment overrides sit between the file and the CLI args
+            config.apply_env_overrides(config::ENV_PREFIX)?;
+            // Reloads are compared against the files, so CLI overrides don't show as changes
+            #[cfg(unix)]
+            let file_config = config.clone();
 
             // Override config with CLI args
-            config.transmitter.connection_type = mode;
+            if let Some(mode) = mode {
+                config.transmitter.connection_type = mode;
+            }
             if let Some(ep) = endpoint {
                 config.transmitter.endpoint = ep;
             }
             if let Some(rate) = sample_rate {
                 config.sensor.sample_rate_ms = rate;
             }
+            if let Some(sensors) = sensors {
+                config.sensor.sensor_types = sensors;
+            }
+            if let Some(filter) = filter {
+                config.processor.filter = filter;
+            }
+            if let Some(anomaly_method) = anomaly_method {
+                config.processor.anomaly_method = anomaly_method;
+            }
+            if passthrough {
+                config.processor.passthrough = true;
+            }
+            if stdout_jsonl {
+                config.transmitter.stdout_jsonl = true;
+            }
 
-            // Display current config
-            println!("Starting sensor system with configuration:");
-            println!("  Sample rate: {}ms", config.sensor.sample_rate_ms);
-            println!("  Connection type: {}", config.transmitter.connection_type);
-            if config.transmitter.connection_type == "tcp" {
-                println!("  Endpoint: {}", config.transmitter.endpoint);
-            } else if config.transmitter.connection_type == "shared_memory" {
-                println!(
-                    "  Shared memory name: {}",
-                    config.transmitter.shared_mem_name
-                );
+            // Display current config, unless stdout is reserved for readings
+            if config.transmitter.stdout_jsonl {
+                info!("Writing processed readings to stdout as JSON lines.");
+            } else {
+                print_run_config(&config);
             }
 
             // Create main sensor channel
-            let (sensor_tx, sensor_rx_main) = bounded::<common::data_types::SensorData>(100);
+            let sensor_capacity = config.sensor.channel_capacity;
+            let processor_capacity = config.processor.channel_capacity;
+            let PipelineChannels {
+                sensor: (sensor_tx, sensor_rx_main),
+                processed: (processed_tx, processed_rx),
+                actuator: (actuator_tx, actuator_rx),
+            } = PipelineChannels::new(&config);
 
-            // Create fan-out channels for actuator system and processor
-            let (sensor_tx_actuator, sensor_rx_actuator) =
-                bounded::<common::data_types::SensorData>(100);
-            let (sensor_tx_processor, sensor_rx_processor) =
-                bounded::<common::data_types::SensorData>(100);
+            // Readings fan out to the actuator system, processor and sensor
+            // monitor over the bus
+            let sensor_bus = common::bus::Bus::new(sensor_capacity);
+            let sensor_rx_actuator = sensor_bus.subscribe::<common::data_types::SensorData>();
+            let sensor_rx_processor = sensor_bus.subscribe::<common::data_types::SensorData>();
+            let sensor_rx_monitor = sensor_bus.subscribe::<common::data_types::SensorData>();
+            let sensor_publisher = sensor_bus.publisher::<common::data_types::SensorData>();
 
             // Other channels
-            let (processed_tx, processed_rx) = bounded::<common::data_types::SensorData>(100);
-            let (metrics_tx, metrics_rx) = unbounded::<common::data_types::PerformanceMetrics>();
-            let (actuator_tx, actuator_rx) = bounded::<common::data_types::ActuatorCommand>(100);
+            let (metrics_tx, metrics_rx) =
+                common::metrics::metrics_channel(config.metrics.channel_capacity);
             let (feedback_tx, feedback_rx) = unbounded::<common::data_types::ActuatorFeedback>();
             let feedback_tx_clone = feedback_tx.clone();
-            tokio::spawn(async move {
-                while let Ok(cmd) = actuator_rx.recv() {
-                    println!(
-                        "Received actuator command for actuator id: {}",
-                        cmd.actuator_id
+
+            // Control channels used to retune the processor and actuator at runtime
+            let (processor_control_tx, processor_control_rx) =
+                unbounded::<common::control::ProcessorControl>();
+            let (actuator_control_tx, actuator_control_rx) =
+                unbounded::<common::control::ActuatorControl>();
+            let latest_report = common::metrics::SharedReport::default();
+            let anomaly_log =
+                sensor::anomaly_log::AnomalyLog::new(config.processor.anomaly_log_size);
+            let sensor_stats = sensor::processor::SharedSensorStats::default();
+            let throughput = Arc::new(Mutex::new(common::metrics::ThroughputEstimator::new(
+                std::time::Duration::from_millis(config.metrics.throughput_window_ms),
+            )));
+
+            // Channel consumers block on recv(), so they run on the blocking pool.
+            // Each one exits once all of its senders are dropped and logs that once.
+            // Commands are routed by command_type, with a logger on each route
+            let (router, route_receivers) = actuator::router::CommandRouter::new(
+                config.actuator.command_routes.clone(),
+                processor_capacity,
+            );
+            // Commands mapped to a setpoint are passed on to the actuator control loop
+            for (route, route_rx) in route_receivers {
+                let policy = config.actuator.scheduling_policy;
+                let setpoint_commands = config.actuator.setpoint_commands.clone();
+                let setpoint_tx = actuator_control_tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    actuator::router::run_route(
+                        &route,
+                        route_rx,
+                        policy,
+                        &setpoint_commands,
+                        &setpoint_tx,
                     );
-                    println!("Command details: {:?}", cmd.control_command);
-                    println!("Priority: {}", cmd.priority);
-                    println!("Deadline: {:?}", cmd.deadline);
+                });
+            }
+            let command_router = tokio::task::spawn_blocking(move || router.run(actuator_rx));
+
+            // Watchdog fed by the sensor monitor, reporting sensors that go silent
+            let watchdog = (config.sensor.watchdog_timeout_ms > 0).then(|| {
+                Arc::new(Mutex::new(sensor::watchdog::SensorWatchdog::new(
+                    std::time::Duration::from_millis(config.sensor.watchdog_timeout_ms),
+                )))
+            });
+            if let Some(watchdog) = &watchdog {
+                let watchdog = Arc::clone(watchdog);
+                let watchdog_feedback_tx = feedback_tx.clone();
+                tokio::spawn(async move {
+                    sensor::watchdog::run_watchdog(watchdog, watchdog_feedback_tx).await;
+                });
+            }
+
+            // Sensor monitor subscribed to the bus, tracking arrival rates and
+            // feeding the watchdog
+            let monitor_throughput = Arc::clone(&throughput);
+            tokio::task::spawn_blocking(move || {
+                while let Ok(data) = sensor_rx_monitor.recv() {
+                    let now = std::time::Instant::now();
+                    monitor_throughput
+                        .lock()
+                        .unwrap()
+                        .record(&data.sensor_id, now);
+                    if let Some(watchdog) = &watchdog {
+                        watchdog.lock().unwrap().observe(&data.sensor_id, now);
+                    }
                 }
             });
 
-            // Spawn a dispatcher task that reads from sensor_rx_main and forwards to actuator and processor channels
-            tokio::spawn(async move {
-                loop {
-                    match sensor_rx_main.recv() {
-                        Ok(data) => {
-                            // Clone data to send to both consumers
-                            let _ = sensor_tx_actuator.send(data.clone());
-                            let _ = sensor_tx_processor.send(data);
-                        }
-                        Err(err) => {
-                            eprintln!("Sensor dispatcher channel closed: {:?}", err);
-                            break;
-                        }
+            // Spawn a dispatcher task that reads from sensor_rx_main and publishes to the sensor bus
+            let dispatcher = tokio::task::spawn_blocking(move || {
+                while let Ok(data) = sensor_rx_main.recv() {
+                    if sensor_publisher.publish(data).is_err() {
+                        warn!("All sensor consumers dropped, stopping dispatcher.");
+                        return;
                     }
                 }
+                info!("Sensor channel closed, stopping dispatcher.");
             });
 
-            // Spawn feedback listener task
-            tokio::spawn(async move {
+            // Spawn feedback listener task, summarizing feedback per actuator
+            let summary_interval =
+                std::time::Duration::from_millis(config.metrics.report_interval_ms);
+            tokio::task::spawn_blocking(move || {
+                let mut aggregator = common::feedback::FeedbackAggregator::new();
+                let mut last_summary = std::time::Instant::now();
+
                 while let Ok(feedback) = feedback_rx.recv() {
-                    println!("Received actuator feedback: {:?}", feedback);
-                    // Handle the feedback (e.g., log it, update UI, etc.)
+                    debug!("Received actuator feedback: {:?}", feedback);
+                    aggregator.record(&feedback);
+
+                    if last_summary.elapsed() >= summary_interval {
+                        log_feedback_summary(&aggregator);
+                        last_summary = std::time::Instant::now();
+                    }
                 }
+                info!("Feedback channel closed, stopping feedback listener.");
+                log_feedback_summary(&aggregator);
             });
 
+            // Spawn heartbeat task, which the actuator's receiver echoes back
+            let heartbeat = if config.transmitter.heartbeat_interval_ms > 0 {
+                let capacity = sensor::heartbeat::HEARTBEAT_QUEUE_CAPACITY;
+                let (heartbeat_tx, heartbeat_rx) = bounded(capacity);
+                let (ack_tx, ack_rx) = bounded(capacity);
+                let interval =
+                    std::time::Duration::from_millis(config.transmitter.heartbeat_interval_ms);
+                let heartbeat_metrics_tx = metrics_tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    sensor::heartbeat::run_heartbeat(
+                        interval,
+                        heartbeat_tx,
+                        ack_rx,
+                        heartbeat_metrics_tx,
+                    );
+                });
+                Some((heartbeat_rx, ack_tx))
+            } else {
+                None
+            };
+
             // Spawn actuator system task with actuator's sensor receiver
-            tokio::spawn(async move {
-                run_actuator_system(sensor_rx_actuator, feedback_tx).await;
+            let actuator_config = config.actuator.clone();
+            let actuator_shutdown = Arc::new(AtomicBool::new(false));
+            let actuator_shutdown_flag = Arc::clone(&actuator_shutdown);
+            let actuator_metrics_tx = metrics_tx.clone();
+            let actuator = tokio::spawn(async move {
+                run_actuator_system(
+                    &actuator_config,
+                    sensor_rx_actuator,
+                    feedback_tx,
+                    actuator_metrics_tx,
+                    actuator_control_rx,
+                    actuator_shutdown_flag,
+                    heartbeat,
+                )
+                .await;
             });
 
             // Spawn metrics collector task
             let metrics_config = config.metrics.clone();
+            let collector_report = Arc::clone(&latest_report);
+            let final_report = Arc::clone(&latest_report);
             tokio::spawn(async move {
-                common::metrics::run_metrics_collector(&metrics_config, metrics_rx).await;
+                common::metrics::run_metrics_collector(
+                    &metrics_config,
+                    metrics_rx,
+                    collector_report,
+                    throughput,
+                )
+                .await;
             });
 
-            // Spawn sensor generator task
-            let sensor_config = config.sensor.clone();
-            let sensor_metrics_tx = metrics_tx.clone();
-            tokio::spawn(async move {
-                sensor::generator::run_sensor_array(&sensor_config, sensor_tx, sensor_metrics_tx)
+            // Spawn sensor generator task, or replay recorded data instead
+            if let Some(path) = replay {
+                let readings = sensor::replay::load_csv(path.to_str().unwrap())?;
+                info!("Replaying {} readings at {}x", readings.len(), replay_speed);
+                tokio::spawn(async move {
+                    sensor::replay::run_replay(readings, replay_speed, sensor_tx).await;
+                });
+            } else {
+                let sensor_config = config.sensor.clone();
+                let sensor_metrics_tx = metrics_tx.clone();
+                tokio::spawn(async move {
+                    sensor::generator::run_sensor_array(
+                        &sensor_config,
+                        sensor_tx,
+                        sensor_metrics_tx,
+                    )
                     .await;
-            });
+                });
+            }
 
-            // Clone actuator_tx for processor and transmitter
+            // Split actuator_tx between processor and transmitter so the command
+            // channel closes once both have stopped
             let actuator_tx_for_processor = actuator_tx.clone();
-            let actuator_tx_for_transmitter = actuator_tx.clone();
+            let actuator_tx_for_transmitter = actuator_tx;
 
-            // Spawn processor task with processor's sensor receiver
-            let processor_config = config.processor.clone();
-            let processor_metrics_tx = metrics_tx.clone();
-            tokio::spawn(async move {
-                sensor::processor::run_processor(
-                    &processor_config,
-                    sensor_rx_processor,
-                    processed_tx,
-                    processor_metrics_tx,
-                    actuator_tx_for_processor,
-                )
-                .await;
-            });
+            // Spawn processor task with processor's sensor receiver, or forward
+            // raw readings to the transmitter when the processor is bypassed
+            let processor = if config.processor.passthrough {
+                // Nothing will send commands or accept runtime adjustments
+                drop(actuator_tx_for_processor);
+                drop(processor_control_rx);
+                tokio::task::spawn_blocking(move || {
+                    sensor::processor::run_passthrough(sensor_rx_processor, processed_tx);
+                })
+            } else {
+                let processor_config = config.processor.clone();
+                let channels = sensor::processor::ProcessorChannels {
+                    rx: sensor_rx_processor,
+                    tx: processed_tx,
+                    metrics_tx: metrics_tx.clone(),
+                    actuator_tx: actuator_tx_for_processor,
+                    control_rx: processor_control_rx,
+                    anomaly_log: anomaly_log.clone(),
+                    sensor_stats: Arc::clone(&sensor_stats),
+                };
+                tokio::spawn(async move {
+                    sensor::processor::run_processor_pool(&processor_config, channels).await;
+                })
+            };
 
             // Spawn transmitter task
             let transmitter_config = config.transmitter.clone();
-            let transmitter_metrics_tx = metrics_tx.clone();
+            let transmitter_metrics_tx = metrics_tx;
             let feedback_tx_for_transmitter = feedback_tx_clone;
-            tokio::spawn(async move {
+            let retry_queue_depth = sensor::transmitter::SharedQueueDepth::default();
+            let transmitter_retry_queue_depth = Arc::clone(&retry_queue_depth);
+            let transmitter = tokio::spawn(async move {
                 sensor::transmitter::run_transmitter(
                     &transmitter_config,
                     processed_rx,
                     Some(actuator_tx_for_transmitter),
                     transmitter_metrics_tx,
                     Some(feedback_tx_for_transmitter),
+                    transmitter_retry_queue_depth,
                 )
                 .await;
             });
 
-            // Keep running
-            println!("System running. Press Ctrl+C to stop.");
-            tokio::signal::ctrl_c().await?;
-            println!("Shutting down...");
+            // Optionally expose the control API
+            let control = common::control::ControlHandles {
+                config: Arc::new(Mutex::new(config.clone())),
+                latest_report,
+                anomaly_log,
+                sensor_stats,
+                retry_queue_depth,
+                processor_tx: processor_control_tx,
+                actuator_tx: actuator_control_tx,
+            };
+            #[cfg(unix)]
+            tokio::spawn(reload_on_sighup(config_paths, file_config, control.clone()));
+            if let Some(addr) = control_addr {
+                #[cfg(feature = "control-api")]
+                tokio::spawn(async move {
+                    if let Err(e) = control_api::run_control_api(&addr, control).await {
+                        warn!("Control API stopped: {}", e);
+                    }
+                });
+                #[cfg(not(feature = "control-api"))]
+                {
+                    drop(control);
+                    warn!(
+                        "Ignoring --control-addr {}: built without the control-api feature",
+                        addr
+                    );
+                }
+            }
+
+            // Keep running until Ctrl+C or SIGTERM, or until every pipeline stage has drained
+            info!("System running. Press Ctrl+C to stop.");
+            let drained = async {
+                for stage in [dispatcher, processor, transmitter, command_router] {
+                    let _ = stage.await;
+                }
+            };
+            tokio::select! {
+                signal = shutdown_signal() => {
+                    info!("Received {}, shutting down...", signal?);
+                }
+                _ = drained => {
+                    info!("Pipeline drained, shutting down.");
+                }
+            }
+
+            // Let the actuator finish the reading it is handling
+            actuator_shutdown.store(true, Ordering::Relaxed);
+            let _ = actuator.await;
+
+            log_final_metrics(&final_report.lock().unwrap());
+        }
+
+        #[cfg(feature = "tui")]
+        Commands::Dashboard { config, refresh_ms } => {
+            let mut config = load_config(&config)?;
+            config.apply_env_overrides(config::ENV_PREFIX)?;
+
+            // Log lines would draw over the dashboard
+            log::set_max_level(LevelFilter::Off);
+
+            let PipelineChannels {
+                sensor: (sensor_tx, sensor_rx),
+                processed: (processed_tx, processed_rx),
+                actuator: (actuator_tx, actuator_rx),
+            } = PipelineChannels::new(&config);
+            let (metrics_tx, metrics_rx) =
+                common::metrics::metrics_channel(config.metrics.channel_capacity);
+            let (_processor_control_tx, processor_control_rx) =
+                unbounded::<common::control::ProcessorControl>();
+
+            // Nothing executes commands here, but the processor stops if they can't be sent
+            tokio::task::spawn_blocking(move || while actuator_rx.recv().is_ok() {});
+
+            let sensor_config = config.sensor.clone();
+            let sensor_metrics_tx = metrics_tx.clone();
+            tokio::spawn(async move {
+                sensor::generator::run_sensor_array(&sensor_config, sensor_tx, sensor_metrics_tx)
+                    .await;
+            });
+
+            let processor_config = config.processor.clone();
+            let channels = sensor::processor::ProcessorChannels {
+                rx: sensor_rx,
+                tx: processed_tx,
+                metrics_tx,
+                actuator_tx,
+                control_rx: processor_control_rx,
+                anomaly_log: sensor::anomaly_log::AnomalyLog::new(
+                    config.processor.anomaly_log_size,
+                ),
+                sensor_stats: sensor::processor::SharedSensorStats::default(),
+            };
+            tokio::spawn(async move {
+                sensor::processor::run_processor_pool(&processor_config, channels).await;
+            });
+
+            // Dropping processed_rx on return winds the pipeline down
+            let refresh = std::time::Duration::from_millis(refresh_ms);
+            tokio::task::spawn_blocking(move || {
+                dashboard::run_dashboard(processed_rx, metrics_rx, refresh)
+            })
+            .await??;
+        }
+
+        Commands::Serve { endpoint, format } => {
+            actuator::tcp_server::run_actuator_tcp_server(&endpoint, format).await?;
+        }
+
+        Commands::Info => {
+            let processor = sensor::processor::DataProcessor::new(20);
+            println!("Sensor types (default anomaly threshold):");
+            for sensor_type in common::data_types::SensorType::ALL {
+                println!(
+                    "  {:<12} {:.1}",
+                    sensor_type.name(),
+                    processor.threshold(sensor_type)
+                );
+            }
+            println!("Connection types:");
+            for connection_type in sensor::transmitter::ConnectionType::ALL {
+                println!("  {}", connection_type.name());
+            }
+        }
+
+        Commands::Schema { output } => {
+            let schemas = common::data_types::wire_schemas();
+            let json = serde_json::to_string_pretty(&schemas)?;
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, json)?;
+                    println!("Schemas saved to {:?}", output);
+                }
+                None => println!("{}", json),
+            }
+        }
+
+        Commands::Sla {
+            input,
+            max_latency_ms,
+            min_success_rate,
+        } => {
+            let policy = common::sla::SlaPolicy {
+                max_latency_ms,
+                min_success_rate,
+            };
+            let records = common::sla::read_records(&input)?;
+            let results = common::sla::evaluate(&records, &policy);
+
+            println!(
+                "{:<30} | {:>8} | {:>9} | {:>14} | Result",
+                "Operation", "Count", "Success %", "Within bound %"
+            );
+            println!("{:-<82}", "");
+            for result in &results {
+                println!(
+                    "{:<30} | {:>8} | {:>9.2} | {:>14} | {}",
+                    result.operation,
+                    result.total,
+                    result.success_rate() * 100.0,
+                    result
+                        .latency_compliance()
+                        .map_or_else(|| "n/a".to_string(), |c| format!("{:.2}", c * 100.0)),
+                    if result.met(&policy) { "PASS" } else { "FAIL" }
+                );
+            }
+
+            let violations = results.iter().filter(|r| !r.met(&policy)).count();
+            if violations > 0 {
+                eprintln!(
+                    "SLA violated by {} of {} operations (max {}ms, min {:.2}%)",
+                    violations,
+                    results.len(),
+                    max_latency_ms,
+                    min_success_rate * 100.0
+                );
+                std::process::exit(1);
+            }
+            println!("SLA met by all {} operations.", results.len());
         }
 
         Commands::GenConfig { output } => {
@@ -211,8 +654,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("Default configuration saved to {:?}", output);
         }
 
-        Commands::Benchmark { iterations, output } => {
-            println!("Running benchmarks with {} iterations", iterations);
+        Commands::Benchmark {
+            iterations,
+            seed,
+            output,
+        } => {
+            println!("Running benchmarks with {} iterations (seed {})", iterations, seed);
 
             let _config = config::Config::default();
             let (_sensor_tx, _sensor_rx) = bounded::<common::data_types::SensorData>(100);
@@ -227,10 +674,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 10.0,
                 0.2,
                 0.01,
-            );
+            )
+            .with_seed(seed);
 
             // Setup benchmarking processor
-            let mut processor = sensor::processor::DataProcessor::new(20);
+            let mut processor =
+                sensor::processor::DataProcessor::new(20).with_anomaly_logging(false);
 
             // Benchmark sensor data generation
             println!("Benchmarking sensor data generation...");
@@ -240,12 +689,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
             let generation_time = start.elapsed();
 
-            // Generate test data for processing
-            let mut test_data = Vec::with_capacity(iterations as usize);
-            for _ in 0..iterations {
-                let (data, _timestamp) = generator.generate_reading();
-                test_data.push(data);
-            }
+            // Replayable test data for processing, independent of the run above
+            let test_data = sensor::generator::benchmark_dataset(seed, iterations);
 
             // Benchmark data processing
             println!("Benchmarking data processing...");
@@ -274,9 +719,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let results = format!(
                 "Benchmark Results:\n\
          Iterations: {}\n\
+         Seed: {}\n\
          Sensor data generation: {:?} ({:?} per iteration)\n\
          Data processing: {:?} ({:?} per iteration)\n",
                 iterations,
+                seed,
                 generation_time,
                 generation_time / iterations as u32,
                 processing_time,
@@ -290,3 +737,315 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+// Bounded pipeline channels, sized by the config's channel capacities
+struct PipelineChannels {
+    sensor: Channel<common::data_types::SensorData>, // Raw readings
+    processed: Channel<common::data_types::SensorData>, // Processor output
+    actuator: Channel<common::data_types::ActuatorCommand>, // Commands for the actuator
+}
+
+type Channel<T> = (Sender<T>, Receiver<T>);
+
+impl PipelineChannels {
+    fn new(config: &config::Config) -> Self {
+        Self {
+            sensor: bounded(config.sensor.channel_capacity),
+            processed: bounded(config.processor.channel_capacity),
+            actuator: bounded(config.processor.channel_capacity),
+        }
+    }
+}
+
+// Default configuration, or the given files merged left to right
+fn load_config(paths: &[PathBuf]) -> Result<config::Config, Box<dyn std::error::Error>> {
+    match paths {
+        [] => Ok(config::Config::default()),
+        [path] => config::Config::from_file(path.to_str().unwrap()),
+        _ => {
+            let paths: Vec<&str> = paths.iter().map(|path| path.to_str().unwrap()).collect();
+            config::Config::from_files(&paths)
+        }
+    }
+}
+
+// Reload the config files on every SIGHUP. Changes that can be made live are
+// pushed into the running processor and actuator; the rest are logged and ignored.
+#[cfg(unix)]
+async fn reload_on_sighup(
+    paths: Vec<PathBuf>,
+    mut running: config::Config,
+    control: common::control::ControlHandles,
+) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(e) => {
+            warn!("Can't listen for SIGHUP, config reload disabled: {}", e);
+            return;
+        }
+    };
+
+    while sighup.recv().await.is_some() {
+        let reloaded = load_config(&paths).and_then(|mut reloaded| {
+            reloaded.apply_env_overrides(config::ENV_PREFIX)?;
+            Ok(reloaded)
+        });
+        let reloaded = match reloaded {
+            Ok(reloaded) => reloaded,
+            Err(e) => {
+                warn!("Config reload failed, keeping the running config: {}", e);
+                continue;
+            }
+        };
+
+        let reload = common::control::ConfigReload::plan(&running, &reloaded);
+        for field in &reload.ignored {
+            warn!("Config reload: ignoring change to {}, it needs a restart", field);
+        }
+        reload.apply_to(&mut control.config.lock().unwrap());
+        info!(
+            "Config reloaded: {} processor and {} actuator adjustment(s)",
+            reload.processor.len(),
+            reload.actuator.len()
+        );
+        for adjustment in reload.processor {
+            let _ = control.processor_tx.send(adjustment);
+        }
+        for adjustment in reload.actuator {
+            let _ = control.actuator_tx.send(adjustment);
+        }
+        running = reloaded;
+    }
+}
+
+// Resolves on Ctrl+C (SIGINT) or, on unix, SIGTERM, returning the signal's name
+async fn shutdown_signal() -> std::io::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut sigterm = signal(SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map(|()| "SIGINT"),
+            _ = sigterm.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.map(|()| "SIGINT")
+    }
+}
+
+// One line per operation from the most recent metrics report
+fn log_final_metrics(report: &HashMap<String, common::metrics::OperationStats>) {
+    info!("Final metrics summary:");
+    for stats in report.values() {
+        info!(
+            "[Metrics] {}: total={}, success={:.2}%, avg_ms={}, max_ms={}, missed_deadlines={}",
+            stats.operation,
+            stats.total_operations,
+            stats.success_rate,
+            common::metrics::format_ms(stats.avg_duration),
+            common::metrics::format_ms(stats.max_duration),
+            stats.missed_deadlines
+        );
+    }
+}
+
+// Summary of the settings a run starts with
+fn print_run_config(config: &config::Config) {
+    println!("Starting sensor system with configuration:");
+    println!("  Sample rate: {}ms", config.sensor.sample_rate_ms);
+    if config.processor.passthrough {
+        println!("  Processor: bypassed (raw passthrough)");
+    } else {
+        println!(
+            "  Filter: {}, anomaly method: {}",
+            config.processor.filter.name(),
+            config.processor.anomaly_method.name()
+        );
+    }
+    println!("  Connection type: {}", config.transmitter.connection_type);
+    if config.transmitter.connection_type == "tcp" {
+        println!("  Endpoint: {}", config.transmitter.endpoint);
+    } else if config.transmitter.connection_type == "shared_memory" {
+        println!(
+            "  Shared memory name: {}",
+            config.transmitter.shared_mem_name
+        );
+    }
+}
+
+fn log_feedback_summary(aggregator: &common::feedback::FeedbackAggregator) {
+    for (actuator_id, summary) in aggregator.summary() {
+        info!(
+            "[Feedback] {}: normal={}, adjusting={}, warning={}, error={}, last_seen={}",
+            actuator_id,
+            summary.normal,
+            summary.adjusting,
+            summary.warning,
+            summary.error,
+            summary.last_seen
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(seq: u64, value: f64) -> common::data_types::SensorData {
+        common::data_types::SensorData {
+            timestamp: 1_000 + seq as u128 * 10,
+            sensor_id: common::data_types::SensorId::new("force_1"),
+            reading_type: common::data_types::SensorType::Force,
+            value,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        }
+    }
+
+    #[test]
+    fn log_level_is_parsed_from_the_command_line() {
+        let cli = Cli::try_parse_from(["sensor_system", "--log-level", "debug", "info"]).unwrap();
+        assert_eq!(cli.log_level, LevelFilter::Debug);
+        let cli = Cli::try_parse_from(["sensor_system", "info"]).unwrap();
+        assert_eq!(cli.log_level, LevelFilter::Info);
+    }
+
+    #[test]
+    fn an_invalid_log_level_is_a_clear_error() {
+        let err = Cli::try_parse_from(["sensor_system", "--log-level", "loud", "info"])
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+        let message = err.to_string();
+        assert!(
+            message.contains(
+                "invalid log level 'loud', expected one of: off, error, warn, info, debug, trace"
+            ),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn filter_flag_gives_the_processor_an_ewma_filter() {
+        let cli = Cli::try_parse_from([
+            "sensor_system",
+            "run",
+            "--filter",
+            "ewma",
+            "--anomaly-method",
+            "mad",
+        ])
+        .unwrap();
+        let Commands::Run {
+            filter,
+            anomaly_method,
+            ..
+        } = cli.command
+        else {
+            panic!("expected the run subcommand");
+        };
+        assert_eq!(anomaly_method, Some(sensor::processor::AnomalyMethod::Mad));
+
+        let mut config = config::Config::default().processor;
+        config.window_size = 3;
+        config.filter = filter.unwrap();
+        let mut processor =
+            sensor::processor::DataProcessor::<sensor::stats::MovingWindow>::from_config(&config);
+        let outputs: Vec<_> = [0.0, 10.0, 10.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| processor.process(reading(i as u64 + 1, value)).0.value)
+            .collect();
+        // EWMA with alpha 2/(3+1); the window mean would end on 20/3
+        assert_eq!(outputs, [0.0, 5.0, 7.5]);
+    }
+
+    #[test]
+    fn unknown_filter_and_anomaly_method_are_rejected() {
+        let err = Cli::try_parse_from(["sensor_system", "run", "--filter", "kalman"])
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("unknown filter 'kalman'"),
+            "{}",
+            err
+        );
+        let err = Cli::try_parse_from(["sensor_system", "run", "--anomaly-method", "magic"])
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("unknown anomaly method 'magic'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn pipeline_channels_hold_the_configured_number_of_messages() {
+        let mut config = config::Config::default();
+        config.sensor.channel_capacity = 3;
+        config.processor.channel_capacity = 5;
+        let channels = PipelineChannels::new(&config);
+
+        // How many messages fit before the channel reports itself full
+        fn fill<T: Clone>(tx: &Sender<T>, message: T) -> usize {
+            let mut sent = 0;
+            while tx.try_send(message.clone()).is_ok() {
+                sent += 1;
+                assert!(sent <= 1_000, "channel is unbounded");
+            }
+            sent
+        }
+        let command = common::data_types::ActuatorCommand::from_sensor_data(&reading(1, 1.0));
+        assert_eq!(fill(&channels.sensor.0, reading(1, 1.0)), 3);
+        assert_eq!(fill(&channels.processed.0, reading(1, 1.0)), 5);
+        assert_eq!(fill(&channels.actuator.0, command), 5);
+    }
+
+    #[test]
+    fn sensors_flag_limits_the_generators_to_those_types() {
+        use common::data_types::SensorType;
+
+        let cli =
+            Cli::try_parse_from(["sensor_system", "run", "--sensors", "force,Position"]).unwrap();
+        let Commands::Run { sensors, .. } = cli.command else {
+            panic!("expected the run subcommand");
+        };
+        let mut config = config::Config::default().sensor;
+        config.num_sensors = 4;
+        config.sensor_types = sensors.unwrap();
+
+        let layout = sensor::generator::sensor_layout(&config);
+        let types: Vec<_> = layout.iter().map(|(_, sensor_type)| *sensor_type).collect();
+        assert_eq!(
+            types,
+            [
+                SensorType::Force,
+                SensorType::Position,
+                SensorType::Force,
+                SensorType::Position
+            ]
+        );
+
+        let err = Cli::try_parse_from(["sensor_system", "run", "--sensors", "force,pressure"])
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains(
+                "unknown sensor type 'pressure', expected one of: force, position, velocity, temperature"
+            ),
+            "{}",
+            err
+        );
+    }
+}
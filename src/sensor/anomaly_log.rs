@@ -0,0 +1,126 @@
+use crate::common::data_types::SensorId;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+// Which check flagged an anomaly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyReason {
+    // Too far from the window's statistics for the configured method
+    Statistical,
+    // Changed faster than max_rate_of_change
+    RateOfChange,
+    // Above the sensor type's alarm limit, whatever the statistics say
+    HardLimit,
+}
+
+impl fmt::Display for AnomalyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AnomalyReason::Statistical => "statistical outlier",
+            AnomalyReason::RateOfChange => "rate of change exceeded",
+            AnomalyReason::HardLimit => "hard limit exceeded",
+        })
+    }
+}
+
+// A single detected anomaly
+#[derive(Debug, Clone, Serialize)]
+pub struct AnomalyEvent {
+    pub sensor_id: SensorId,
+    pub timestamp: u128, // Timestamp of the reading in milliseconds
+    pub value: f64,      // Raw (calibrated) reading that was flagged
+    pub mean: f64,       // Window mean it was scored against (median for the MAD method)
+    pub std_dev: f64,    // Window spread it was scored against (scaled MAD for the MAD method)
+    pub z_score: f64,
+    pub confidence: f64,
+    pub reason: AnomalyReason,
+}
+
+// Ring buffer of the most recent anomalies. Clones share the same history,
+// so a handle can be kept to query it while the processor runs.
+#[derive(Debug, Clone)]
+pub struct AnomalyLog {
+    events: Arc<Mutex<VecDeque<AnomalyEvent>>>,
+    capacity: usize,
+    subscribers: Arc<Mutex<Vec<crossbeam_channel::Sender<AnomalyEvent>>>>,
+}
+
+impl AnomalyLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    // Channel receiving every event pushed from now on, whatever the capacity.
+    // Dropping the receiver unsubscribes.
+    pub fn subscribe(&self) -> crossbeam_channel::Receiver<AnomalyEvent> {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    // Record an event, evicting the oldest one when full
+    pub fn push(&self, event: AnomalyEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|tx| tx.send(event.clone()).is_ok());
+        if self.capacity == 0 {
+            return;
+        }
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    // Up to `n` most recent events, oldest first
+    pub fn recent(&self, n: usize) -> Vec<AnomalyEvent> {
+        let events = self.events.lock().unwrap();
+        let skip = events.len().saturating_sub(n);
+        events.iter().skip(skip).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp: u128) -> AnomalyEvent {
+        AnomalyEvent {
+            sensor_id: SensorId::new("force_1"),
+            timestamp,
+            value: 100.0,
+            mean: 10.0,
+            std_dev: 1.0,
+            z_score: 90.0,
+            confidence: 0.1,
+            reason: AnomalyReason::Statistical,
+        }
+    }
+
+    fn timestamps(events: &[AnomalyEvent]) -> Vec<u128> {
+        events.iter().map(|event| event.timestamp).collect()
+    }
+
+    #[test]
+    fn only_the_most_recent_capacity_events_are_kept() {
+        let log = AnomalyLog::new(10);
+        for timestamp in 1..=15 {
+            log.push(event(timestamp));
+        }
+
+        assert_eq!(
+            timestamps(&log.recent(usize::MAX)),
+            (6..=15).collect::<Vec<_>>()
+        );
+        assert_eq!(timestamps(&log.recent(3)), [13, 14, 15]);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::common::data_types::SensorData;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+// Failure signature a generator can play back
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FaultKind {
+    // Drift away by `rate_per_s` units per second, then go silent after `fail_after_ms`
+    RampToFailure {
+        rate_per_s: f64,
+        fail_after_ms: u64,
+    },
+    // Report `value` from the onset on, or the value at the onset if omitted
+    StuckAt {
+        #[serde(default)]
+        value: Option<f64>,
+    },
+    // Lose the first `dropout_ms` of every `period_ms`
+    IntermittentDropout {
+        period_ms: u64,
+        dropout_ms: u64,
+    },
+}
+
+// A fault and when it starts, in milliseconds after the sensor's first reading
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FaultScenario {
+    pub start_ms: u64,
+    pub fault: FaultKind,
+}
+
+// Plays a FaultScenario over the readings of one generator
+#[derive(Debug, Clone)]
+pub struct FaultPlayback {
+    scenario: FaultScenario,
+    started: Option<Instant>, // First reading, where the timeline starts
+    stuck_value: Option<f64>,
+}
+
+impl FaultPlayback {
+    pub fn new(scenario: FaultScenario) -> Self {
+        Self {
+            scenario,
+            started: None,
+            stuck_value: None,
+        }
+    }
+
+    // Time since the fault began, None before its onset
+    fn since_onset(&self, now: Instant) -> Option<Duration> {
+        now.saturating_duration_since(self.started?)
+            .checked_sub(Duration::from_millis(self.scenario.start_ms))
+    }
+
+    // Turn a healthy reading taken at `now` into what the faulty sensor reports
+    pub fn apply(&mut self, now: Instant, data: &mut SensorData) {
+        self.started.get_or_insert(now);
+        let Some(since_onset) = self.since_onset(now) else {
+            return;
+        };
+
+        match self.scenario.fault {
+            FaultKind::RampToFailure { rate_per_s, .. } => {
+                data.value += rate_per_s * since_onset.as_secs_f64();
+            }
+            FaultKind::StuckAt { value } => {
+                data.value = *self.stuck_value.get_or_insert(value.unwrap_or(data.value));
+                // A stuck sensor can't spike
+                data.is_anomaly = false;
+            }
+            FaultKind::IntermittentDropout { .. } => {}
+        }
+    }
+
+    // Whether the reading taken at `now` is lost
+    pub fn drops(&self, now: Instant) -> bool {
+        let Some(since_onset) = self.since_onset(now) else {
+            return false;
+        };
+
+        match self.scenario.fault {
+            FaultKind::RampToFailure { fail_after_ms, .. } => {
+                since_onset >= Duration::from_millis(fail_after_ms)
+            }
+            FaultKind::StuckAt { .. } => false,
+            FaultKind::IntermittentDropout {
+                period_ms,
+                dropout_ms,
+            } => (since_onset.as_millis() % period_ms.max(1) as u128) < dropout_ms as u128,
+        }
+    }
+}
@@ -1,18 +1,118 @@
-use crate::common::data_types::{PerformanceMetrics, SensorData, SensorType};
+use crate::common::clock::{real_clock, Clock, MockClock};
+use crate::common::data_types::{PerformanceMetrics, SensorData, SensorId, SensorType};
+use crate::common::metrics::MetricsSender;
+use crate::sensor::fault::{FaultPlayback, FaultScenario};
+use log::{info, warn};
 use rand::rngs::SmallRng; // This now works with the `small_rng` feature
 use rand::{Rng, SeedableRng}; // Added SeedableRng
 use rand_distr::{Distribution, Normal}; // Correct source of Normal
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::time;
 
+// How the generator paces its readings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum SampleMode {
+    // One reading every `period_ms`
+    Continuous { period_ms: u64 },
+    // `count` readings `burst_period_ms` apart, then idle for `idle_ms`
+    Burst {
+        count: usize,
+        burst_period_ms: u64,
+        idle_ms: u64,
+    },
+}
+
+// A sample interval counts as on time (a successful sampling metric) up to this
+// fraction over the target period
+pub const SAMPLING_TOLERANCE: f64 = 0.1;
+
+// Smallest anomaly spike, in noise standard deviations. Spikes scale with the
+// value, which alone would make them vanish for sensors centred on zero.
+const MIN_SPIKE_NOISE_MULTIPLE: f64 = 10.0;
+
+// How many intervals are averaged into each logged sampling rate summary
+const SAMPLING_SUMMARY_EVERY: u32 = 1000;
+
+// Realized intervals between a sensor's samples, compared against the target
+// period to reveal scheduler starvation under load
+struct SamplingTracker {
+    target: Duration,
+    last_tick: Option<Instant>,
+    intervals: u32,
+    total: Duration,
+}
+
+impl SamplingTracker {
+    fn new(target: Duration) -> Self {
+        Self {
+            target,
+            last_tick: None,
+            intervals: 0,
+            total: Duration::ZERO,
+        }
+    }
+
+    // Record a tick at `now`. Returns the interval since the previous tick as a
+    // "sampling:<sensor_id>" metric, or None on the first tick.
+    fn tick(&mut self, sensor_id: &str, now: Instant) -> Option<PerformanceMetrics> {
+        let last = self.last_tick.replace(now)?;
+        let gap = now.saturating_duration_since(last);
+        let on_time = gap.as_secs_f64() <= self.target.as_secs_f64() * (1.0 + SAMPLING_TOLERANCE);
+
+        self.intervals += 1;
+        self.total += gap;
+        if self.intervals == SAMPLING_SUMMARY_EVERY {
+            self.log_summary(sensor_id);
+        }
+
+        Some(PerformanceMetrics {
+            operation: format!("sampling:{}", sensor_id),
+            start_time: last,
+            end_time: Some(now),
+            duration_ms: Some(gap.as_secs_f64() * 1000.0),
+            success: on_time,
+            attempts: 1,
+            error_kind: (!on_time).then(|| "late".to_string()),
+            label: None,
+            count: 1,
+        })
+    }
+
+    // Log the actual rate and drift over the intervals since the last summary
+    fn log_summary(&mut self, sensor_id: &str) {
+        let actual = self.total / self.intervals;
+        let drift_ms = (actual.as_secs_f64() - self.target.as_secs_f64()) * 1000.0;
+        let rate_hz = |period: Duration| 1.0 / period.as_secs_f64().max(f64::EPSILON);
+        info!(
+            "[Sampling] {}: target {:.1} Hz, actual {:.1} Hz, drift {:+.3} ms per sample",
+            sensor_id,
+            rate_hz(self.target),
+            rate_hz(actual),
+            drift_ms
+        );
+        self.intervals = 0;
+        self.total = Duration::ZERO;
+    }
+}
+
 pub struct SensorGenerator {
-    sensor_id: String,
+    sensor_id: SensorId,
     sensor_type: SensorType,
-    sample_rate_ms: u64,
+    sample_mode: SampleMode,
     drift_factor: f64,
+    base_value: f64,
+    reversion_rate: Option<f64>,
     rng: SmallRng,
     normal_dist: Normal<f64>,
     last_value: f64,
+    value_min: Option<f64>,
+    value_max: Option<f64>,
+    jitter: Duration,
+    seq: u64, // Sequence number of the last reading generated
+    fault: Option<FaultPlayback>,
+    clock: Arc<dyn Clock>,
 }
 
 impl SensorGenerator {
@@ -27,20 +127,113 @@ impl SensorGenerator {
         let normal_dist = Normal::new(0.0, noise_level).unwrap();
 
         Self {
-            sensor_id: sensor_id.to_string(),
+            sensor_id: SensorId::new(sensor_id),
             sensor_type,
-            sample_rate_ms,
-
+            sample_mode: SampleMode::Continuous {
+                period_ms: sample_rate_ms,
+            },
             drift_factor,
+            base_value,
+            reversion_rate: None,
             rng: SmallRng::from_entropy(), // Initialize with entropy
             normal_dist,
             last_value: base_value,
+            value_min: None,
+            value_max: None,
+            jitter: Duration::ZERO,
+            seq: 0,
+            fault: None,
+            clock: real_clock(),
+        }
+    }
+
+    // Generator using the sensor type's default base value, noise and drift
+    pub fn for_type(sensor_id: &str, sensor_type: SensorType, sample_rate_ms: u64) -> Self {
+        let (base_value, noise_level, drift_factor, _unit) = sensor_type.default_params();
+        Self::new(
+            sensor_id,
+            sensor_type,
+            sample_rate_ms,
+            base_value,
+            noise_level,
+            drift_factor,
+        )
+    }
+
+    // Use a fixed seed so the generated sequence is reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = SmallRng::seed_from_u64(seed);
+        self
+    }
+
+    // Replace the continuous sample rate given to `new`
+    pub fn with_sample_mode(mut self, sample_mode: SampleMode) -> Self {
+        self.sample_mode = sample_mode;
+        self
+    }
+
+    // Pull the drifting value back toward the base value by `rate` (0.0-1.0) of the
+    // gap each reading (Ornstein-Uhlenbeck style) instead of a pure random walk
+    pub fn with_mean_reversion(mut self, rate: f64) -> Self {
+        self.reversion_rate = Some(rate);
+        self
+    }
+
+    // Centre the sensor on `base_value` instead of its type's default. Negative
+    // and zero bases are fine, e.g. a bidirectional force sensor.
+    pub fn with_base_value(mut self, base_value: f64) -> Self {
+        self.base_value = base_value;
+        self.last_value = base_value;
+        self
+    }
+
+    // Lengthen or shorten each sample interval by a random amount up to
+    // ±jitter_ms, like a sensor with an imperfect clock
+    pub fn with_jitter(mut self, jitter_ms: u64) -> Self {
+        self.jitter = Duration::from_millis(jitter_ms);
+        self
+    }
+
+    // `period` perturbed by up to ±jitter, never below zero
+    fn jittered(&mut self, period: Duration) -> Duration {
+        if self.jitter.is_zero() {
+            return period;
         }
+        let jitter = self.jitter.as_secs_f64();
+        let offset = self.rng.gen_range(-jitter..=jitter);
+        Duration::from_secs_f64((period.as_secs_f64() + offset).max(0.0))
+    }
+
+    // Clamp generated values (anomalies included) to the sensor's physical range
+    pub fn with_value_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.value_min = min;
+        self.value_max = max;
+        self
+    }
+
+    // Play back `scenario` over this sensor's readings
+    pub fn with_fault_scenario(mut self, scenario: FaultScenario) -> Self {
+        self.fault = Some(FaultPlayback::new(scenario));
+        self
+    }
+
+    // Timestamp readings from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Saturate a value at the configured range limits
+    fn saturate(&self, value: f64) -> f64 {
+        let value = self.value_min.map_or(value, |min| value.max(min));
+        self.value_max.map_or(value, |max| value.min(max))
     }
 
     // Generate a single sensor reading
     pub fn generate_reading(&mut self) -> (SensorData, PerformanceMetrics) {
-        let mut metrics = PerformanceMetrics::new("sensor_reading_generation");
+        let mut metrics =
+            PerformanceMetrics::new_with_clock("sensor_reading_generation", &*self.clock)
+                .with_label(&self.sensor_id);
 
         // Add some random noise
         let noise = self.normal_dist.sample(&mut self.rng);
@@ -48,6 +241,9 @@ impl SensorGenerator {
         // Add some drift to simulate real sensor behavior
         let drift = (self.rng.gen_range(0.0..1.0) - 0.5) * self.drift_factor;
         self.last_value += drift;
+        if let Some(rate) = self.reversion_rate {
+            self.last_value += rate * (self.base_value - self.last_value);
+        }
 
         // Calculate the final value
         let value = self.last_value + noise;
@@ -60,24 +256,35 @@ impl SensorGenerator {
             1.0
         };
 
-        let final_value = value * anomaly_factor;
+        // Push the value further from zero in its own direction, by at least
+        // MIN_SPIKE_NOISE_MULTIPLE noise deviations so near-zero sensors spike too
+        let spike_scale = value
+            .abs()
+            .max(MIN_SPIKE_NOISE_MULTIPLE * self.normal_dist.std_dev());
+        let spike = (anomaly_factor - 1.0) * spike_scale * value.signum();
+        let final_value = self.saturate(value + spike);
 
         // Get current timestamp in milliseconds
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
+        let timestamp = self.clock.epoch_ms();
+        self.seq += 1;
 
-        let sensor_data = SensorData {
+        let mut sensor_data = SensorData {
             timestamp,
             sensor_id: self.sensor_id.clone(),
             reading_type: self.sensor_type,
             value: final_value,
             is_anomaly,
             confidence: 1.0, // Will be adjusted by processor
+            confidence_lower: None,
+            confidence_upper: None,
+            seq: self.seq,
+            rejected: false,
         };
+        if let Some(fault) = self.fault.as_mut() {
+            fault.apply(self.clock.now(), &mut sensor_data);
+        }
 
-        metrics.complete(true);
+        metrics.complete_with_clock(true, &*self.clock);
         (sensor_data, metrics)
     }
 
@@ -85,93 +292,424 @@ impl SensorGenerator {
     pub async fn run(
         &mut self,
         tx: crossbeam_channel::Sender<SensorData>,
-        metrics_tx: crossbeam_channel::Sender<PerformanceMetrics>,
+        metrics_tx: MetricsSender,
     ) {
-        let mut interval = time::interval(Duration::from_millis(self.sample_rate_ms));
-
-        loop {
-            // Wait until the next tick
-            interval.tick().await;
+        match self.sample_mode {
+            SampleMode::Continuous { period_ms } => {
+                let target = Duration::from_millis(period_ms);
+                let mut interval = time::interval(target);
+                let mut next_tick = time::Instant::now();
+                let mut sampling = SamplingTracker::new(target);
 
-            // Generate reading and send it
-            let (data, metrics) = self.generate_reading();
+                loop {
+                    // Wait until the next tick, scheduled from the previous one
+                    // when jittered so the perturbations don't accumulate delay
+                    if self.jitter.is_zero() {
+                        interval.tick().await;
+                    } else {
+                        time::sleep_until(next_tick).await;
+                        next_tick += self.jittered(target);
+                    }
 
-            // Send the metrics
-            let _ = metrics_tx.send(metrics);
+                    // Measure how far the realized rate is from the target
+                    if let Some(metrics) = sampling.tick(&self.sensor_id, self.clock.now()) {
+                        metrics_tx.send(metrics);
+                    }
 
-            // Send the sensor data
-            if tx.send(data).is_err() {
-                println!("Receiver has been dropped, stopping sensor generation.");
-                break;
+                    if !self.emit_reading(&tx, &metrics_tx) {
+                        break;
+                    }
+                }
             }
+            SampleMode::Burst {
+                count,
+                burst_period_ms,
+                idle_ms,
+            } => loop {
+                for i in 0..count {
+                    if i > 0 {
+                        let period = self.jittered(Duration::from_millis(burst_period_ms));
+                        time::sleep(period).await;
+                    }
+                    if !self.emit_reading(&tx, &metrics_tx) {
+                        return;
+                    }
+                }
+
+                // Idle between bursts
+                time::sleep(Duration::from_millis(idle_ms)).await;
+            },
+        }
+    }
+
+    // Generate a reading and send it, returning false once the receiver is gone
+    fn emit_reading(
+        &mut self,
+        tx: &crossbeam_channel::Sender<SensorData>,
+        metrics_tx: &MetricsSender,
+    ) -> bool {
+        let (data, metrics) = self.generate_reading();
+
+        // Send the metrics
+        metrics_tx.send(metrics);
+
+        // A dropout loses the reading after it is taken, leaving a gap in seq
+        if self
+            .fault
+            .as_ref()
+            .is_some_and(|fault| fault.drops(self.clock.now()))
+        {
+            return true;
+        }
+
+        // Send the sensor data
+        if tx.send(data).is_err() {
+            info!("Receiver has been dropped, stopping sensor generation.");
+            return false;
         }
+        true
+    }
+}
+
+// Sensor ids and types run_sensor_array will start, assigning the configured
+// types round-robin across num_sensors
+pub fn sensor_layout(config: &crate::config::SensorConfig) -> Vec<(String, SensorType)> {
+    if config.sensor_types.is_empty() {
+        return Vec::new();
     }
+    (0..config.num_sensors)
+        .map(|i| {
+            let sensor_type = config.sensor_types[i % config.sensor_types.len()];
+            (format!("sensor_{}", i), sensor_type)
+        })
+        .collect()
+}
+
+// Wall-clock start of the benchmark dataset's timestamps
+pub const BENCHMARK_EPOCH_MS: u128 = 1_700_000_000_000;
+
+// Fixed sequence of readings for benchmarking. The same seed always yields the
+// same values and timestamps (one per millisecond), so runs are comparable.
+pub fn benchmark_dataset(seed: u64, count: usize) -> Vec<SensorData> {
+    let clock = MockClock::new(BENCHMARK_EPOCH_MS);
+    let mut generator = SensorGenerator::for_type("bench_sensor", SensorType::Force, 1)
+        .with_seed(seed)
+        .with_clock(Arc::new(clock.clone()));
+
+    (0..count)
+        .map(|_| {
+            clock.advance(Duration::from_millis(1));
+            generator.generate_reading().0
+        })
+        .collect()
 }
 
 // Create multiple sensors and run them concurrently
 pub async fn run_sensor_array(
     config: &crate::config::SensorConfig,
     tx: crossbeam_channel::Sender<SensorData>,
-    metrics_tx: crossbeam_channel::Sender<PerformanceMetrics>,
+    metrics_tx: MetricsSender,
 ) {
-    let mut handles = vec![];
-
-    // Create a force sensor
-    let mut force_sensor = SensorGenerator::new(
-        "force_sensor_1",
-        SensorType::Force,
-        config.sample_rate_ms,
-        10.0, // Base value (10 Newtons)
-        0.2,  // Noise level
-        0.01, // Drift factor
-    );
-
-    // Create a position sensor
-    let mut position_sensor = SensorGenerator::new(
-        "position_sensor_1",
-        SensorType::Position,
-        config.sample_rate_ms,
-        100.0, // Base value (100 mm)
-        0.5,   // Noise level
-        0.005, // Drift factor
-    );
-
-    // Create a temperature sensor (slower sample rate)
-    let mut temp_sensor = SensorGenerator::new(
-        "temp_sensor_1",
-        SensorType::Temperature,
-        config.sample_rate_ms * 2, // Slower sampling for temperature
-        25.0,                      // Base value (25 degrees C)
-        0.1,                       // Noise level
-        0.002,                     // Drift factor
-    );
-
-    handles.push(tokio::spawn({
-        let tx = tx.clone();
-        let metrics_tx = metrics_tx.clone();
-        async move {
-            force_sensor.run(tx, metrics_tx).await;
-        }
-    }));
-
-    handles.push(tokio::spawn({
-        let tx = tx.clone();
-        let metrics_tx = metrics_tx.clone();
-        async move {
-            position_sensor.run(tx, metrics_tx).await;
-        }
-    }));
-
-    handles.push(tokio::spawn({
-        let tx = tx.clone();
-        let metrics_tx = metrics_tx.clone();
-        async move {
-            temp_sensor.run(tx, metrics_tx).await;
-        }
-    }));
+    let layout = sensor_layout(config);
+    if layout.is_empty() {
+        warn!("num_sensors is 0 or no sensor types are selected, no sensors will be started.");
+        return;
+    }
+
+    let mut handles = Vec::with_capacity(layout.len());
+
+    for (sensor_id, sensor_type) in layout {
+        // Temperature changes slowly, so sample it at half the rate
+        let sample_rate_ms = match sensor_type {
+            SensorType::Temperature => config.sample_rate_ms * 2,
+            _ => config.sample_rate_ms,
+        };
+
+        let mut sensor = SensorGenerator::for_type(&sensor_id, sensor_type, sample_rate_ms);
+        if let Some(sample_mode) = config.sample_mode {
+            sensor = sensor.with_sample_mode(sample_mode);
+        }
+        if let Some(rate) = config.drift_reversion_rate {
+            sensor = sensor.with_mean_reversion(rate);
+        }
+        if config.jitter_ms > 0 {
+            sensor = sensor.with_jitter(config.jitter_ms);
+        }
+        if let Some(&base_value) = config.base_values.get(&sensor_id) {
+            sensor = sensor.with_base_value(base_value);
+        }
+        if let Some(range) = config.value_ranges.get(&sensor_id) {
+            sensor = sensor.with_value_range(range.min, range.max);
+        }
+        if let Some(&scenario) = config.fault_scenarios.get(&sensor_id) {
+            sensor = sensor.with_fault_scenario(scenario);
+        }
+
+        handles.push(tokio::spawn({
+            let tx = tx.clone();
+            let metrics_tx = metrics_tx.clone();
+            async move {
+                sensor.run(tx, metrics_tx).await;
+            }
+        }));
+    }
 
     // Wait for all sensors to complete (they run indefinitely in this case)
     for handle in handles {
         let _ = handle.await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::clock::MockClock;
+    use crate::sensor::fault::FaultKind;
+
+    #[test]
+    fn benchmark_dataset_is_timestamped_by_its_clock() {
+        let readings = benchmark_dataset(7, 5);
+        let timestamps: Vec<_> = readings.iter().map(|r| r.timestamp).collect();
+        let expected: Vec<_> = (1..=5).map(|ms| BENCHMARK_EPOCH_MS + ms).collect();
+        assert_eq!(timestamps, expected);
+    }
+
+    #[test]
+    fn benchmark_dataset_repeats_for_a_seed() {
+        let values = |seed| -> Vec<f64> {
+            benchmark_dataset(seed, 50)
+                .iter()
+                .map(|r| r.value)
+                .collect()
+        };
+        assert_eq!(values(7), values(7));
+        assert_ne!(values(7), values(8));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn run_sensor_array_starts_num_sensors_distinct_sensors() {
+        let mut config = crate::config::Config::default().sensor;
+        config.num_sensors = 10;
+        config.sample_rate_ms = 1;
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let (metrics_tx, _metrics_rx) = crate::common::metrics::metrics_channel(1024);
+        let array = tokio::spawn(async move { run_sensor_array(&config, tx, metrics_tx).await });
+
+        let ids = tokio::task::spawn_blocking(move || {
+            let mut ids = std::collections::HashSet::new();
+            // Sensors run forever, so stop once every one of them has reported
+            while ids.len() < 10 {
+                let reading = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+                ids.insert(reading.sensor_id.to_string());
+            }
+            ids
+        })
+        .await
+        .unwrap();
+        array.abort();
+
+        let expected: std::collections::HashSet<_> =
+            (0..10).map(|i| format!("sensor_{}", i)).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn run_sensor_array_with_no_sensors_returns_at_once() {
+        let mut config = crate::config::Config::default().sensor;
+        config.num_sensors = 0;
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let (metrics_tx, _metrics_rx) = crate::common::metrics::metrics_channel(16);
+
+        run_sensor_array(&config, tx, metrics_tx).await;
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn burst_mode_sends_count_readings_then_idles() {
+        let mut sensor = SensorGenerator::for_type("force_1", SensorType::Force, 1)
+            .with_sample_mode(SampleMode::Burst {
+                count: 3,
+                burst_period_ms: 5,
+                idle_ms: 200,
+            });
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let (metrics_tx, _metrics_rx) = crate::common::metrics::metrics_channel(64);
+        let generator = tokio::spawn(async move { sensor.run(tx, metrics_tx).await });
+
+        // Arrival times of three bursts
+        let arrivals = tokio::task::spawn_blocking(move || {
+            (0..9)
+                .map(|_| {
+                    rx.recv_timeout(Duration::from_secs(5)).unwrap();
+                    Instant::now()
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap();
+        generator.abort();
+
+        for (i, gap) in arrivals.windows(2).map(|w| w[1] - w[0]).enumerate() {
+            if i % 3 == 2 {
+                assert!(
+                    gap >= Duration::from_millis(150),
+                    "idle gap {} was {:?}",
+                    i,
+                    gap
+                );
+            } else {
+                assert!(
+                    gap < Duration::from_millis(100),
+                    "burst gap {} was {:?}",
+                    i,
+                    gap
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn value_range_clamps_anomaly_spikes_to_value_max() {
+        let mut generator = SensorGenerator::for_type("force_1", SensorType::Force, 1)
+            .with_seed(3)
+            .with_base_value(10.0)
+            .with_value_range(Some(5.0), Some(12.0));
+        let readings: Vec<_> = (0..2_000).map(|_| generator.generate_reading().0).collect();
+
+        assert!(readings.iter().all(|r| (5.0..=12.0).contains(&r.value)));
+        let spikes: Vec<_> = readings.iter().filter(|r| r.is_anomaly).collect();
+        assert!(!spikes.is_empty());
+        assert!(spikes.iter().all(|r| r.value == 12.0));
+    }
+
+    #[test]
+    fn mean_reversion_keeps_the_long_run_mean_near_base_value() {
+        let long_run_mean = |generator: SensorGenerator| {
+            let mut generator = generator.with_seed(11);
+            let values: Vec<_> = (0..20_000)
+                .map(|_| generator.generate_reading().0)
+                .filter(|r| !r.is_anomaly)
+                .map(|r| r.value)
+                .collect();
+            values.iter().sum::<f64>() / values.len() as f64
+        };
+        let sensor = || SensorGenerator::new("force_1", SensorType::Force, 1, 100.0, 0.1, 5.0);
+
+        let reverting = long_run_mean(sensor().with_mean_reversion(0.1));
+        assert!((reverting - 100.0).abs() < 1.0, "{}", reverting);
+        // A pure random walk wanders far from where it started
+        let wandering = long_run_mean(sensor());
+        assert!((wandering - 100.0).abs() > 10.0, "{}", wandering);
+    }
+
+    #[test]
+    fn for_type_generates_around_the_type_defaults() {
+        assert_eq!(
+            SensorType::Temperature.default_params(),
+            (25.0, 0.1, 0.002, "°C")
+        );
+
+        let mut generator =
+            SensorGenerator::for_type("temperature_1", SensorType::Temperature, 1).with_seed(5);
+        let readings: Vec<_> = (0..500).map(|_| generator.generate_reading().0).collect();
+        assert!(readings
+            .iter()
+            .all(|r| r.reading_type == SensorType::Temperature));
+        // Noise of 0.1 and slight drift keep ordinary readings close to 25
+        assert!(readings
+            .iter()
+            .filter(|r| !r.is_anomaly)
+            .all(|r| (r.value - 25.0).abs() < 1.0));
+    }
+
+    #[test]
+    fn slowed_sampling_is_reported_as_late() {
+        let target = Duration::from_millis(10);
+        let mut sampling = SamplingTracker::new(target);
+        let start = Instant::now();
+
+        assert!(sampling.tick("force_1", start).is_none());
+        let on_time = sampling
+            .tick("force_1", start + Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(on_time.operation, "sampling:force_1");
+        assert!(on_time.success);
+
+        // 15ms is half again the target, well past the tolerance
+        let late = sampling
+            .tick("force_1", start + Duration::from_millis(25))
+            .unwrap();
+        assert!(!late.success);
+        assert_eq!(late.error_kind.as_deref(), Some("late"));
+        assert!(late.duration_ms.unwrap() > target.as_secs_f64() * 1000.0);
+    }
+
+    #[test]
+    fn signed_sensors_spike_away_from_zero() {
+        let spikes_and_ordinary = |base_value: f64| {
+            let mut generator =
+                SensorGenerator::new("force_1", SensorType::Force, 1, 0.0, 0.1, 0.0)
+                    .with_seed(7)
+                    .with_base_value(base_value);
+            let readings: Vec<_> = (0..2_000).map(|_| generator.generate_reading().0).collect();
+            let (spikes, ordinary): (Vec<_>, Vec<_>) =
+                readings.into_iter().partition(|r| r.is_anomaly);
+            assert!(!spikes.is_empty());
+            assert!(ordinary.iter().all(|r| (r.value - base_value).abs() < 1.0));
+            spikes
+        };
+
+        // Centred on zero, spikes are still at least 20 noise deviations out
+        let spikes = spikes_and_ordinary(0.0);
+        assert!(spikes.iter().all(|r| r.value.abs() >= 1.5), "{:?}", spikes);
+        // A negative sensor spikes further negative
+        let spikes = spikes_and_ordinary(-50.0);
+        assert!(spikes.iter().all(|r| r.value < -100.0), "{:?}", spikes);
+    }
+
+    #[test]
+    fn jitter_varies_the_sample_intervals() {
+        let period = Duration::from_millis(10);
+        let intervals = |jitter_ms: u64| {
+            let mut generator = SensorGenerator::for_type("force_1", SensorType::Force, 10)
+                .with_seed(9)
+                .with_jitter(jitter_ms);
+            (0..200)
+                .map(|_| generator.jittered(period).as_secs_f64() * 1000.0)
+                .collect::<Vec<_>>()
+        };
+        let variance = |intervals: &[f64]| {
+            let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+            intervals.iter().map(|i| (i - mean).powi(2)).sum::<f64>() / intervals.len() as f64
+        };
+
+        let jittered = intervals(3);
+        assert!(variance(&jittered) > 0.0);
+        assert!(jittered.iter().all(|&i| (7.0..=13.0).contains(&i)));
+        assert_eq!(variance(&intervals(0)), 0.0);
+    }
+
+    #[test]
+    fn stuck_at_holds_the_value_from_the_fault_onset() {
+        let clock = MockClock::new(1_000);
+        let mut generator = SensorGenerator::for_type("force_1", SensorType::Force, 10)
+            .with_seed(4)
+            .with_clock(Arc::new(clock.clone()))
+            .with_fault_scenario(FaultScenario {
+                start_ms: 50,
+                fault: FaultKind::StuckAt { value: None },
+            });
+        let values: Vec<_> = (0..20)
+            .map(|_| {
+                let value = generator.generate_reading().0.value;
+                clock.advance(Duration::from_millis(10));
+                value
+            })
+            .collect();
+
+        // Healthy for the first 50ms, then frozen at the value read at the onset
+        let (healthy, stuck) = values.split_at(5);
+        assert!(healthy.windows(2).any(|pair| pair[0] != pair[1]));
+        assert!(stuck.iter().all(|&value| value == stuck[0]), "{:?}", stuck);
+    }
+}
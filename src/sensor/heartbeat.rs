@@ -0,0 +1,186 @@
+use crate::common::data_types::{Heartbeat, PerformanceMetrics};
+use crate::common::metrics::MetricsSender;
+use crossbeam_channel::{select, Receiver, Sender, TrySendError};
+use log::{info, warn};
+use std::time::{Duration, Instant};
+
+// Used when no heartbeat interval is configured
+pub const DEFAULT_HEARTBEAT_INTERVAL_MS: u64 = 1000;
+
+// Intervals without an acknowledgement before the link is considered down
+pub const MISSED_HEARTBEATS_LINK_DOWN: u32 = 3;
+
+// Heartbeats sent but not yet picked up by the actuator. The link is down long
+// before this fills, so extra heartbeats are skipped rather than queued.
+pub const HEARTBEAT_QUEUE_CAPACITY: usize = 4;
+
+// Numbers outgoing heartbeats and tracks whether the actuator is still answering
+pub struct HeartbeatMonitor {
+    interval: Duration,
+    next_sequence: u64,
+    last_ack: Instant,
+    link_up: bool,
+}
+
+impl HeartbeatMonitor {
+    // The link counts as up at `now`, so it has MISSED_HEARTBEATS_LINK_DOWN
+    // intervals to answer the first heartbeat
+    pub fn new(interval: Duration, now: Instant) -> Self {
+        Self {
+            interval,
+            next_sequence: 0,
+            last_ack: now,
+            link_up: true,
+        }
+    }
+
+    pub fn is_link_up(&self) -> bool {
+        self.link_up
+    }
+
+    // Next heartbeat to send, stamped with `now`
+    pub fn next(&mut self, now: Instant) -> Heartbeat {
+        let heartbeat = Heartbeat {
+            sequence: self.next_sequence,
+            sent_at: now,
+        };
+        self.next_sequence += 1;
+        heartbeat
+    }
+
+    // An echoed heartbeat arrived at `now`; returns its round-trip time
+    pub fn acknowledge(&mut self, ack: Heartbeat, now: Instant) -> Duration {
+        self.last_ack = now;
+        self.link_up = true;
+        now.saturating_duration_since(ack.sent_at)
+    }
+
+    // Mark the link down once nothing has been acknowledged for too long.
+    // Returns true only on the transition, so each outage is reported once.
+    pub fn check(&mut self, now: Instant) -> bool {
+        let silent_for = now.saturating_duration_since(self.last_ack);
+        if self.link_up && silent_for > self.interval * MISSED_HEARTBEATS_LINK_DOWN {
+            self.link_up = false;
+            return true;
+        }
+        false
+    }
+}
+
+// Send a heartbeat every `interval` and record the round-trip time of each
+// acknowledgement as a "heartbeat_rtt" metric. Runs until either channel closes.
+pub fn run_heartbeat(
+    interval: Duration,
+    tx: Sender<Heartbeat>,
+    ack_rx: Receiver<Heartbeat>,
+    metrics_tx: MetricsSender,
+) {
+    let ticker = crossbeam_channel::tick(interval);
+    let mut monitor = HeartbeatMonitor::new(interval, Instant::now());
+
+    loop {
+        select! {
+            recv(ticker) -> _ => {
+                let now = Instant::now();
+                if monitor.check(now) {
+                    warn!(
+                        "No heartbeat acknowledged for {} intervals, actuator link is down.",
+                        MISSED_HEARTBEATS_LINK_DOWN
+                    );
+                }
+                match tx.try_send(monitor.next(now)) {
+                    Ok(()) | Err(TrySendError::Full(_)) => {}
+                    Err(TrySendError::Disconnected(_)) => {
+                        info!("Heartbeat channel closed, stopping heartbeat.");
+                        return;
+                    }
+                }
+            }
+            recv(ack_rx) -> ack => {
+                let Ok(ack) = ack else {
+                    info!("Heartbeat ack channel closed, stopping heartbeat.");
+                    return;
+                };
+                let was_down = !monitor.is_link_up();
+                let now = Instant::now();
+                let rtt = monitor.acknowledge(ack, now);
+                if was_down {
+                    info!("Heartbeat {} acknowledged, actuator link is up.", ack.sequence);
+                }
+
+                metrics_tx.send(PerformanceMetrics {
+                    operation: "heartbeat_rtt".to_string(),
+                    start_time: ack.sent_at,
+                    end_time: Some(now),
+                    duration_ms: Some(rtt.as_secs_f64() * 1000.0),
+                    success: true,
+                    attempts: 1,
+                    error_kind: None,
+                    label: None,
+                    count: 1,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::metrics::metrics_channel;
+
+    #[test]
+    fn heartbeats_follow_the_interval_and_record_rtt() {
+        let interval = Duration::from_millis(20);
+        let (tx, rx) = crossbeam_channel::bounded(HEARTBEAT_QUEUE_CAPACITY);
+        let (ack_tx, ack_rx) = crossbeam_channel::unbounded();
+        let (metrics_tx, metrics_rx) = metrics_channel(16);
+        let heartbeat = std::thread::spawn(move || run_heartbeat(interval, tx, ack_rx, metrics_tx));
+
+        // Echo a few back the way the actuator receiver does
+        let heartbeats: Vec<Heartbeat> = (0..4)
+            .map(|_| {
+                let heartbeat = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+                ack_tx.send(heartbeat).unwrap();
+                heartbeat
+            })
+            .collect();
+        let sequences: Vec<_> = heartbeats.iter().map(|h| h.sequence).collect();
+        assert_eq!(sequences, [0, 1, 2, 3]);
+        for pair in heartbeats.windows(2) {
+            let gap = pair[1].sent_at.duration_since(pair[0].sent_at);
+            assert!(gap >= interval / 2, "heartbeats {:?} apart", gap);
+        }
+
+        // Closing the ack channel stops the task, after any pending acks
+        drop(ack_tx);
+        heartbeat.join().unwrap();
+        let mut rtts = Vec::new();
+        while let Ok(metrics) = metrics_rx.try_recv() {
+            assert_eq!(metrics.operation, "heartbeat_rtt");
+            rtts.push(metrics.duration_ms.unwrap());
+        }
+        assert_eq!(rtts.len(), 4);
+        assert!(rtts.iter().all(|&rtt| rtt >= 0.0));
+    }
+
+    #[test]
+    fn link_goes_down_after_missed_heartbeats() {
+        let interval = Duration::from_millis(100);
+        let start = Instant::now();
+        let mut monitor = HeartbeatMonitor::new(interval, start);
+
+        assert!(!monitor.check(start + interval * MISSED_HEARTBEATS_LINK_DOWN));
+        assert!(monitor.is_link_up());
+        let late = start + interval * MISSED_HEARTBEATS_LINK_DOWN + interval;
+        assert!(monitor.check(late));
+        assert!(!monitor.is_link_up());
+        // Reported once per outage
+        assert!(!monitor.check(late + interval));
+
+        let heartbeat = monitor.next(late);
+        let rtt = monitor.acknowledge(heartbeat, late + Duration::from_millis(5));
+        assert_eq!(rtt, Duration::from_millis(5));
+        assert!(monitor.is_link_up());
+    }
+}
@@ -1,3 +1,11 @@
+pub mod anomaly_log;
+pub mod fault;
 pub mod generator;
+pub mod heartbeat;
 pub mod processor;
+pub mod replay;
+pub mod shed;
+pub mod stats;
 pub mod transmitter;
+pub mod transport;
+pub mod watchdog;
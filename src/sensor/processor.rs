@@ -1,48 +1,324 @@
+use crate::common::clock::{real_clock, Clock};
+use crate::common::control::ProcessorControl;
 use crate::common::data_types::{
-    ActuatorCommand, ControlCommand, PerformanceMetrics, SensorData, SensorType,
+    ActuatorCommand, CommandPayload, ControlCommand, PerformanceMetrics, SensorData, SensorId,
+    SensorType,
 };
-use rolling_stats::Stats;
+use crate::common::metrics::MetricsSender;
+use crate::config::{Calibration, HardLimits};
+use crate::sensor::anomaly_log::{AnomalyEvent, AnomalyLog, AnomalyReason};
+use crate::sensor::shed::LoadShedder;
+use crate::sensor::stats::{
+    CumulativeStats, EwmaStats, MovingWindow, StatsBackend, StatsBackendKind, MAD_TO_STD_DEV,
+};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How readings are smoothed before being passed on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterKind {
+    // Mean of the moving window
+    #[default]
+    Window,
+    // Exponentially weighted moving average, alpha = 2 / (window_size + 1)
+    Ewma,
+}
+
+impl FilterKind {
+    pub const ALL: [FilterKind; 2] = [FilterKind::Window, FilterKind::Ewma];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterKind::Window => "window",
+            FilterKind::Ewma => "ewma",
+        }
+    }
+}
+
+impl FromStr for FilterKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FilterKind::ALL
+            .into_iter()
+            .find(|filter| filter.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = FilterKind::ALL.iter().map(|f| f.name()).collect();
+                format!("unknown filter '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
 
-pub struct DataProcessor {
-    moving_averages: HashMap<String, Stats<f64>>,
-    _window_size: usize,
+// How readings are scored against the moving window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyMethod {
+    // Distance from the window mean in standard deviations
+    #[default]
+    ZScore,
+    // Distance from the window median in scaled median absolute deviations
+    Mad,
+    // Only the rate-of-change limit (max_rate_of_change)
+    RateOfChange,
+}
+
+impl AnomalyMethod {
+    pub const ALL: [AnomalyMethod; 3] = [
+        AnomalyMethod::ZScore,
+        AnomalyMethod::Mad,
+        AnomalyMethod::RateOfChange,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnomalyMethod::ZScore => "zscore",
+            AnomalyMethod::Mad => "mad",
+            AnomalyMethod::RateOfChange => "rate_of_change",
+        }
+    }
+}
+
+impl FromStr for AnomalyMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AnomalyMethod::ALL
+            .into_iter()
+            .find(|method| method.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = AnomalyMethod::ALL.iter().map(|m| m.name()).collect();
+                format!("unknown anomaly method '{}', expected one of: {}", s, names.join(", "))
+            })
+    }
+}
+
+// What to do with a reading whose timestamp is not after the sensor's previous
+// one (e.g. the wall clock stepped back on an NTP adjustment)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampGuard {
+    // Log it and leave it out of the rate-of-change and velocity calculations
+    #[default]
+    Flag,
+    // Move the timestamp forward to 1ms after the previous one
+    Clamp,
+}
+
+// Summary of one sensor's current window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SensorStats {
+    pub samples: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl SensorStats {
+    fn from_window(window: &impl StatsBackend) -> Self {
+        Self {
+            samples: window.len(),
+            mean: window.mean(),
+            std_dev: window.std_dev(),
+            min: window.min(),
+            max: window.max(),
+        }
+    }
+}
+
+// Point-in-time state of one sensor inside a running processor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SensorSnapshot {
+    pub samples: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub last_value: f64, // Latest calibrated reading, before smoothing
+}
+
+// Point-in-time state of every sensor a processor (or processor pool) has seen
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ProcessorSnapshot {
+    pub sensors: HashMap<SensorId, SensorSnapshot>,
+    pub decimation: usize, // 1 in N readings processed per sensor (highest across workers)
+}
+
+// Latest stats per sensor_id, shared with anything that wants to read them at runtime
+pub type SharedSensorStats = Arc<Mutex<HashMap<SensorId, SensorStats>>>;
+
+// Anomalies kept in history unless configured otherwise
+pub const DEFAULT_ANOMALY_LOG_SIZE: usize = 100;
+
+// Samples each sensor needs before anomalies are flagged, unless configured otherwise
+pub const DEFAULT_WARMUP_SAMPLES: usize = 10;
+
+// Confidence band half-width in standard deviations unless configured otherwise
+pub const DEFAULT_CONFIDENCE_BAND_K: f64 = 2.0;
+
+// Anomaly threshold per sensor type, in standard deviations, unless configured otherwise
+pub fn default_thresholds() -> HashMap<SensorType, f64> {
+    HashMap::from([
+        (SensorType::Force, 2.5),
+        (SensorType::Position, 3.0),
+        (SensorType::Velocity, 2.8),
+        (SensorType::Temperature, 3.5),
+    ])
+}
+
+// Statistics per sensor come from `B`, a sliding window unless another backend
+// is chosen with DataProcessor::new_with_backend
+pub struct DataProcessor<B: StatsBackend = MovingWindow> {
+    moving_averages: HashMap<SensorId, B>,
+    window_size: usize,
+    filter: FilterKind,
+    ewma: HashMap<SensorId, f64>,
+    anomaly_method: AnomalyMethod,
+    warmup_samples: usize,
+    samples_seen: HashMap<SensorId, usize>, // Plausible readings per sensor, for warm-up
     anomaly_thresholds: HashMap<SensorType, f64>,
+    anomaly_cooldowns: HashMap<SensorType, u64>, // Milliseconds
+    hard_limits: HashMap<SensorType, HardLimits>,
+    last_anomalies: HashMap<SensorId, u128>,
+    calibrations: HashMap<SensorId, Calibration>,
+    update_on_anomaly: bool,
+    confidence_band_k: f64,
+    min_confidence: Option<f64>,
+    max_rate_of_change: Option<f64>,
+    last_readings: HashMap<SensorId, (f64, u128)>,
+    last_values: HashMap<SensorId, f64>,
+    timestamp_guard: TimestampGuard,
+    last_timestamps: HashMap<SensorId, u128>,
+    last_seqs: HashMap<SensorId, u64>,
+    missing_readings: u64, // Gaps seen since the last take_missing_readings
+    derive_velocity: bool,
+    last_positions: HashMap<SensorId, (f64, u128)>,
+    anomaly_log: AnomalyLog,
+    log_anomalies: bool,
+    anomaly_handlers: Vec<Box<dyn AnomalyHandler>>,
+    shedder: Option<LoadShedder>,
+    clock: Arc<dyn Clock>,
+}
+
+// Reaction to a detected anomaly (alerting, webhooks, ...) registered on a DataProcessor
+pub trait AnomalyHandler: Send {
+    // Called once per anomalous reading, before it is smoothed
+    fn on_anomaly(&self, data: &SensorData);
 }
-fn current_timestamp_ms() -> u64 {
-    let now = SystemTime::now();
-    now.duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_millis() as u64
+
+impl<F> AnomalyHandler for F
+where
+    F: Fn(&SensorData) + Send,
+{
+    fn on_anomaly(&self, data: &SensorData) {
+        self(data)
+    }
 }
 
 impl DataProcessor {
-    pub fn new(_window_size: usize) -> Self {
-        let mut anomaly_thresholds = HashMap::new();
-
-        anomaly_thresholds.insert(SensorType::Force, 2.5);
-        anomaly_thresholds.insert(SensorType::Position, 3.0);
-        anomaly_thresholds.insert(SensorType::Velocity, 2.8);
-        anomaly_thresholds.insert(SensorType::Temperature, 3.5);
+    pub fn new(window_size: usize) -> Self {
+        DataProcessor::new_with_backend(window_size)
+    }
+}
 
+impl<B: StatsBackend> DataProcessor<B> {
+    // Processor keeping each sensor's statistics in a `B`
+    pub fn new_with_backend(window_size: usize) -> Self {
         Self {
             moving_averages: HashMap::new(),
-            _window_size,
-            anomaly_thresholds,
+            window_size,
+            filter: FilterKind::default(),
+            ewma: HashMap::new(),
+            anomaly_method: AnomalyMethod::default(),
+            warmup_samples: DEFAULT_WARMUP_SAMPLES,
+            samples_seen: HashMap::new(),
+            anomaly_thresholds: default_thresholds(),
+            anomaly_cooldowns: HashMap::new(),
+            hard_limits: HashMap::new(),
+            last_anomalies: HashMap::new(),
+            calibrations: HashMap::new(),
+            update_on_anomaly: false,
+            confidence_band_k: DEFAULT_CONFIDENCE_BAND_K,
+            min_confidence: None,
+            max_rate_of_change: None,
+            last_readings: HashMap::new(),
+            last_values: HashMap::new(),
+            timestamp_guard: TimestampGuard::default(),
+            last_timestamps: HashMap::new(),
+            last_seqs: HashMap::new(),
+            missing_readings: 0,
+            derive_velocity: false,
+            last_positions: HashMap::new(),
+            anomaly_log: AnomalyLog::new(DEFAULT_ANOMALY_LOG_SIZE),
+            log_anomalies: true,
+            anomaly_handlers: Vec::new(),
+            shedder: None,
+            clock: real_clock(),
         }
     }
 
+    // Processor set up from the config file's processor section. `B` is left to
+    // the caller; run_processor picks it from config.stats_backend.
+    pub fn from_config(config: &crate::config::ProcessorConfig) -> Self {
+        DataProcessor::new_with_backend(config.window_size)
+            .with_filter(config.filter)
+            .with_anomaly_method(config.anomaly_method)
+            .with_warmup_samples(config.warmup_samples)
+            .with_anomaly_log(AnomalyLog::new(config.anomaly_log_size))
+            .with_rate_of_change_limit(config.max_rate_of_change)
+            .with_update_on_anomaly(config.update_on_anomaly)
+            .with_calibrations(config.calibrations.clone())
+            .with_anomaly_cooldowns(config.anomaly_cooldown_ms.clone())
+            .with_hard_limits(config.hard_limits.clone())
+            .with_thresholds(config.thresholds.clone())
+            .with_derived_velocity(config.derive_velocity)
+            .with_confidence_band(config.confidence_band_k)
+            .with_min_confidence(config.min_confidence)
+            .with_timestamp_guard(config.timestamp_guard)
+            .with_output_deadline(
+                config.output_deadline_us.map(Duration::from_micros),
+                config.max_decimation,
+            )
+    }
+
     pub fn process(&mut self, mut raw_data: SensorData) -> (SensorData, PerformanceMetrics) {
-        let mut metrics = PerformanceMetrics::new("data_processing");
+        let mut metrics = PerformanceMetrics::new_with_clock("data_processing", &*self.clock)
+            .with_label(&raw_data.sensor_id);
 
+        // Calibrate before anything looks at the value
+        raw_data.value = self.calibrate(&raw_data.sensor_id, raw_data.value);
+        self.guard_timestamp(&mut raw_data);
+        self.check_sequence(&raw_data);
+
+        // Impossible values never reach the window, filters or anomaly checks
+        if !raw_data.reading_type.is_plausible(raw_data.value) {
+            warn!(
+                "[INVALID] Sensor: {}, Value: {} impossible for {}",
+                raw_data.sensor_id,
+                raw_data.value,
+                raw_data.reading_type.name()
+            );
+            raw_data.rejected = true;
+            raw_data.is_anomaly = false;
+            metrics.complete_with_clock(false, &*self.clock);
+            metrics.error_kind = Some("implausible_value".to_string());
+            return (raw_data, metrics);
+        }
+
+        self.last_values
+            .insert(raw_data.sensor_id.clone(), raw_data.value);
+        let in_cooldown = self.in_anomaly_cooldown(&raw_data);
+
+        let window_size = self.window_size;
         let moving_avg = self
             .moving_averages
             .entry(raw_data.sensor_id.clone())
-            .or_default();
-
-        moving_avg.update(raw_data.value);
-        let filtered_value = moving_avg.mean;
+            .or_insert_with(|| B::from_window_size(window_size));
 
         let threshold = self
             .anomaly_thresholds
@@ -50,48 +326,657 @@ impl DataProcessor {
             .cloned()
             .unwrap_or(3.0);
 
+        // Score the raw reading against the window before it is added
+        let mean = moving_avg.mean();
+        let (center, std_dev) = match self.anomaly_method {
+            AnomalyMethod::ZScore => (mean, moving_avg.std_dev()),
+            AnomalyMethod::Mad => {
+                let (median, mad) = moving_avg.median_and_mad();
+                (median, mad * MAD_TO_STD_DEV)
+            }
+            AnomalyMethod::RateOfChange => (mean, 0.0),
+        };
+        let z_score = match self.anomaly_method {
+            AnomalyMethod::RateOfChange => {
+                raw_data.is_anomaly = false;
+                0.0
+            }
+            _ => raw_data.detect_anomaly(center, std_dev, threshold),
+        };
+
+        // Statistics over the first few samples are too unreliable to flag against.
+        // Counted apart from the window, which can be smaller than the warm-up.
+        let samples_seen = self
+            .samples_seen
+            .entry(raw_data.sensor_id.clone())
+            .or_insert(0);
+        let warming_up = *samples_seen < self.warmup_samples;
+        *samples_seen += 1;
+        if warming_up {
+            raw_data.is_anomaly = false;
+        }
+        let z_score_anomaly = raw_data.is_anomaly;
+        let mut reason = AnomalyReason::Statistical;
+
+        // Flag sudden slope changes that the z-score can miss
+        if let Some(limit) = self.max_rate_of_change {
+            let previous = self.last_readings.get(&raw_data.sensor_id).copied();
+            // Out-of-order readings are skipped, they would give a negative or zero dt
+            if !matches!(previous, Some((_, prev)) if raw_data.timestamp <= prev) {
+                if let Some((prev_value, prev_timestamp)) = previous {
+                    let dt_s = (raw_data.timestamp - prev_timestamp) as f64 / 1000.0;
+                    let rate = (raw_data.value - prev_value) / dt_s;
+                    if rate.abs() > limit && !warming_up && !raw_data.is_anomaly {
+                        raw_data.is_anomaly = true;
+                        reason = AnomalyReason::RateOfChange;
+                    }
+                }
+                self.last_readings.insert(
+                    raw_data.sensor_id.clone(),
+                    (raw_data.value, raw_data.timestamp),
+                );
+            }
+        }
+
+        // Hard limits apply from the first reading, warm-up or not
+        if let Some(limits) = self.hard_limits.get(&raw_data.reading_type) {
+            if limits.alarm.is_some_and(|alarm| raw_data.value > alarm) {
+                raw_data.is_anomaly = true;
+                reason = AnomalyReason::HardLimit;
+            } else if limits.warn.is_some_and(|warn| raw_data.value > warn) && self.log_anomalies {
+                warn!(
+                    "[LIMIT] Sensor: {}, Value: {:.2} above warning limit",
+                    raw_data.sensor_id, raw_data.value
+                );
+            }
+        }
+
+        // A sustained fault would otherwise be flagged on every reading
+        if raw_data.is_anomaly && in_cooldown {
+            raw_data.is_anomaly = false;
+        }
+
+        if raw_data.is_anomaly {
+            self.last_anomalies
+                .insert(raw_data.sensor_id.clone(), raw_data.timestamp);
+            self.anomaly_log.push(AnomalyEvent {
+                sensor_id: raw_data.sensor_id.clone(),
+                timestamp: raw_data.timestamp,
+                value: raw_data.value,
+                mean: center,
+                std_dev,
+                z_score,
+                confidence: raw_data.confidence,
+                reason,
+            });
+            if self.log_anomalies {
+                warn!(
+                    "[ANOMALY] Sensor: {}, Value: {:.2}, Mean: {:.2}, StdDev: {:.2}, Confidence: {:.2}, Reason: {}",
+                    raw_data.sensor_id, raw_data.value, center, std_dev, raw_data.confidence, reason
+                );
+            }
+            for handler in &self.anomaly_handlers {
+                handler.on_anomaly(&raw_data);
+            }
+        }
+
+        // Keep spikes out of the window unless configured otherwise, so one
+        // outlier doesn't bias the mean used for the following readings. They're
+        // skipped rather than replaced, which would shrink the std dev instead.
+        let outlier = z_score_anomaly && !self.update_on_anomaly;
+        if !outlier {
+            moving_avg.update(raw_data.value);
+        }
+
         // Update value with filtered (smoothed) value
-        raw_data.value = filtered_value;
+        raw_data.value = match self.filter {
+            FilterKind::Window => moving_avg.mean(),
+            FilterKind::Ewma => {
+                let alpha = 2.0 / (self.window_size.max(1) + 1) as f64;
+                let smoothed = self.ewma.entry(raw_data.sensor_id.clone());
+                if outlier {
+                    *smoothed.or_insert(mean)
+                } else {
+                    let value = raw_data.value;
+                    *smoothed
+                        .and_modify(|s| *s += alpha * (value - *s))
+                        .or_insert(value)
+                }
+            }
+        };
 
-        // Call the unified anomaly detection method on SensorData
-        raw_data.detect_anomaly(filtered_value, moving_avg.std_dev, threshold);
+        // Band of ±k standard deviations around the smoothed value
+        if moving_avg.len() > 1 {
+            let half_width = self.confidence_band_k * moving_avg.std_dev();
+            raw_data.confidence_lower = Some(raw_data.value - half_width);
+            raw_data.confidence_upper = Some(raw_data.value + half_width);
+        }
 
-        metrics.complete(true);
+        metrics.complete_with_clock(true, &*self.clock);
         (raw_data, metrics)
     }
+
+    // Whether the sensor was last flagged less than its type's cooldown ago
+    fn in_anomaly_cooldown(&self, data: &SensorData) -> bool {
+        let Some(&cooldown_ms) = self.anomaly_cooldowns.get(&data.reading_type) else {
+            return false;
+        };
+        self.last_anomalies
+            .get(&data.sensor_id)
+            .is_some_and(|&last| data.timestamp.saturating_sub(last) < cooldown_ms as u128)
+    }
+
+    // Check the reading's timestamp moves forward for its sensor, clamping or
+    // flagging it per the configured guard
+    fn guard_timestamp(&mut self, data: &mut SensorData) {
+        if let Some(&prev_timestamp) = self.last_timestamps.get(&data.sensor_id) {
+            if data.timestamp <= prev_timestamp {
+                match self.timestamp_guard {
+                    TimestampGuard::Flag => {
+                        warn!(
+                            "Non-monotonic timestamp for {}: {} after {}",
+                            data.sensor_id, data.timestamp, prev_timestamp
+                        );
+                        return;
+                    }
+                    TimestampGuard::Clamp => data.timestamp = prev_timestamp + 1,
+                }
+            }
+        }
+        self.last_timestamps
+            .insert(data.sensor_id.clone(), data.timestamp);
+    }
+
+    // Count sequence numbers skipped since the sensor's previous reading. A number
+    // at or below the previous one means the sensor restarted (or a reading was
+    // redelivered), so counting starts over from it.
+    fn check_sequence(&mut self, data: &SensorData) {
+        if data.seq == 0 {
+            return;
+        }
+        if let Some(prev_seq) = self.last_seqs.insert(data.sensor_id.clone(), data.seq) {
+            if data.seq > prev_seq + 1 {
+                let missing = data.seq - prev_seq - 1;
+                warn!(
+                    "Sequence gap for {}: {} reading(s) missing between {} and {}",
+                    data.sensor_id, missing, prev_seq, data.seq
+                );
+                self.missing_readings += missing;
+            } else if data.seq <= prev_seq {
+                debug!(
+                    "Sequence for {} restarted at {} after {}",
+                    data.sensor_id, data.seq, prev_seq
+                );
+            }
+        }
+    }
+
+    // Readings found missing from sequence gaps since the last call
+    pub fn take_missing_readings(&mut self) -> u64 {
+        std::mem::take(&mut self.missing_readings)
+    }
+
+    // Choose how readings are smoothed
+    pub fn with_filter(mut self, filter: FilterKind) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    // Choose how readings are scored for anomalies
+    pub fn with_anomaly_method(mut self, anomaly_method: AnomalyMethod) -> Self {
+        self.anomaly_method = anomaly_method;
+        self
+    }
+
+    // Never flag a sensor's first `samples` readings, whatever the method
+    pub fn with_warmup_samples(mut self, samples: usize) -> Self {
+        self.warmup_samples = samples;
+        self
+    }
+
+    // Enable or disable logging of detected anomalies
+    pub fn with_anomaly_logging(mut self, enabled: bool) -> Self {
+        self.log_anomalies = enabled;
+        self
+    }
+
+    // Apply the sensor's calibration to a raw value (identity if uncalibrated)
+    pub fn calibrate(&self, sensor_id: &str, raw: f64) -> f64 {
+        match self.calibrations.get(sensor_id) {
+            Some(calibration) => calibration.apply(raw),
+            None => raw,
+        }
+    }
+
+    pub fn with_calibrations(mut self, calibrations: HashMap<SensorId, Calibration>) -> Self {
+        self.calibrations = calibrations;
+        self
+    }
+
+    // After flagging an anomaly, don't flag the same sensor again for this many
+    // milliseconds (per sensor type). Readings in the cooldown are still processed.
+    pub fn with_anomaly_cooldowns(mut self, cooldowns: HashMap<SensorType, u64>) -> Self {
+        self.anomaly_cooldowns = cooldowns;
+        self
+    }
+
+    // Override the default anomaly threshold of each given sensor type
+    pub fn with_thresholds(mut self, thresholds: HashMap<SensorType, f64>) -> Self {
+        self.anomaly_thresholds.extend(thresholds);
+        self
+    }
+
+    // Absolute warn/alarm limits per sensor type, checked whatever the statistics say
+    pub fn with_hard_limits(mut self, hard_limits: HashMap<SensorType, HardLimits>) -> Self {
+        self.hard_limits = hard_limits;
+        self
+    }
+
+    // Record anomalies into `anomaly_log` (a shared handle) instead of a private one
+    pub fn with_anomaly_log(mut self, anomaly_log: AnomalyLog) -> Self {
+        self.anomaly_log = anomaly_log;
+        self
+    }
+
+    // Whether readings flagged by the z-score still update the moving average
+    pub fn with_update_on_anomaly(mut self, update_on_anomaly: bool) -> Self {
+        self.update_on_anomaly = update_on_anomaly;
+        self
+    }
+
+    // Flag readings whose |Δvalue/Δt| (units per second) exceeds `limit`
+    pub fn with_rate_of_change_limit(mut self, limit: Option<f64>) -> Self {
+        self.max_rate_of_change = limit;
+        self
+    }
+
+    // Width of the confidence band, in standard deviations either side of the value
+    pub fn with_confidence_band(mut self, k: f64) -> Self {
+        self.confidence_band_k = k;
+        self
+    }
+
+    // Withhold non-anomalous readings whose confidence is below `min` (None forwards all)
+    pub fn with_min_confidence(mut self, min: Option<f64>) -> Self {
+        self.min_confidence = min;
+        self
+    }
+
+    // Whether a processed reading should be passed on. Anomalies always are,
+    // rejected readings never.
+    pub fn should_forward(&self, data: &SensorData) -> bool {
+        !data.rejected
+            && (data.is_anomaly
+                || !matches!(self.min_confidence, Some(min) if data.confidence < min))
+    }
+
+    // Choose how readings with non-monotonic timestamps are handled
+    pub fn with_timestamp_guard(mut self, guard: TimestampGuard) -> Self {
+        self.timestamp_guard = guard;
+        self
+    }
+
+    // Derive a velocity reading from each pair of successive position readings
+    pub fn with_derived_velocity(mut self, enabled: bool) -> Self {
+        self.derive_velocity = enabled;
+        self
+    }
+
+    // Velocity (Δposition/Δt, units per second) from this and the previous position
+    // reading of the same sensor. None if disabled, not a valid position reading,
+    // or there is no earlier reading to compare with.
+    pub fn derive_velocity(&mut self, position: &SensorData) -> Option<SensorData> {
+        if !self.derive_velocity
+            || position.reading_type != SensorType::Position
+            || position.rejected
+        {
+            return None;
+        }
+
+        // Out-of-order readings are skipped rather than replacing the previous
+        // position, which would give a negative or zero dt
+        let previous = self.last_positions.get(&position.sensor_id).copied();
+        if matches!(previous, Some((_, prev)) if position.timestamp <= prev) {
+            return None;
+        }
+        self.last_positions.insert(
+            position.sensor_id.clone(),
+            (position.value, position.timestamp),
+        );
+        let (prev_value, prev_timestamp) = previous?;
+
+        let dt_s = (position.timestamp - prev_timestamp) as f64 / 1000.0;
+        Some(SensorData {
+            timestamp: position.timestamp,
+            sensor_id: SensorId::new(format!("{}_velocity", position.sensor_id)),
+            reading_type: SensorType::Velocity,
+            value: (position.value - prev_value) / dt_s,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq: position.seq,
+            rejected: false,
+        })
+    }
+
+    // Shed readings whenever processing them takes longer than `deadline` on
+    // average, thinning each sensor to as little as 1 in `max_decimation`
+    pub fn with_output_deadline(
+        mut self,
+        deadline: Option<Duration>,
+        max_decimation: usize,
+    ) -> Self {
+        self.shedder = deadline.map(|deadline| LoadShedder::new(deadline, max_decimation));
+        self
+    }
+
+    // Whether to process this reading, or shed it to keep up
+    pub fn admit(&mut self, sensor_id: &SensorId) -> bool {
+        self.shedder
+            .as_mut()
+            .is_none_or(|shedder| shedder.admit(sensor_id))
+    }
+
+    // Feed the time taken to handle a reading to the load shedder, if any
+    pub fn record_processing_time(&mut self, elapsed: Duration) {
+        let Some(shedder) = self.shedder.as_mut() else {
+            return;
+        };
+        if let Some(level) = shedder.record(elapsed) {
+            info!(
+                "Processing {} deadline, now handling 1 in {} readings per sensor",
+                if level > 1 { "behind" } else { "back within" },
+                level
+            );
+        }
+    }
+
+    // Readings per sensor collapsed into one by load shedding (1 when none are shed)
+    pub fn decimation(&self) -> usize {
+        self.shedder.as_ref().map_or(1, LoadShedder::level)
+    }
+
+    // Take time from `clock` instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Invoke `callback` with the raw reading whenever an anomaly is detected
+    pub fn with_anomaly_callback<F>(self, callback: F) -> Self
+    where
+        F: Fn(&SensorData) + Send + 'static,
+    {
+        self.with_anomaly_handler(callback)
+    }
+
+    // Register a handler to run on every anomaly, after any already registered
+    pub fn with_anomaly_handler<H>(mut self, handler: H) -> Self
+    where
+        H: AnomalyHandler + 'static,
+    {
+        self.anomaly_handlers.push(Box::new(handler));
+        self
+    }
+
     pub fn generate_actuator_command(&self, sensor_data: &SensorData) -> Option<ActuatorCommand> {
         if sensor_data.is_anomaly {
             Some(ActuatorCommand {
-                actuator_id: sensor_data.sensor_id.clone(),
+                actuator_id: sensor_data.sensor_id.actuator(),
                 control_command: ControlCommand {
                     command_type: "adjust_position".to_string(),
-                    payload: Some("new_target_position".to_string()),
-                    timestamp: current_timestamp_ms() as u128,
+                    payload: Some(CommandPayload {
+                        value: Some(sensor_data.value),
+                        target: Some("new_target_position".to_string()),
+                    }),
+                    timestamp: self.clock.epoch_ms(),
                     value: sensor_data.value,
                 },
                 priority: 1,
-                deadline: Instant::now() + Duration::from_millis(2),
+                deadline: self.clock.now() + Duration::from_millis(2),
+                command_id: sensor_data.reading_id().map(|id| format!("{}/anomaly", id)),
             })
         } else {
             None
         }
     }
 
-    #[allow(dead_code)]
+    // Stats over the sensor's current window, None until it has a reading
+    pub fn sensor_stats(&self, sensor_id: &str) -> Option<SensorStats> {
+        self.moving_averages
+            .get(sensor_id)
+            .filter(|window| !window.is_empty())
+            .map(SensorStats::from_window)
+    }
+
+    // Mean, std-dev, sample count and last value of every sensor with a reading
+    pub fn snapshot(&self) -> ProcessorSnapshot {
+        let sensors = self
+            .moving_averages
+            .iter()
+            .filter(|(_, window)| !window.is_empty())
+            .map(|(sensor_id, window)| {
+                let snapshot = SensorSnapshot {
+                    samples: window.len(),
+                    mean: window.mean(),
+                    std_dev: window.std_dev(),
+                    last_value: self.last_values.get(sensor_id).copied().unwrap_or(f64::NAN),
+                };
+                (sensor_id.clone(), snapshot)
+            })
+            .collect();
+        ProcessorSnapshot {
+            sensors,
+            decimation: self.decimation(),
+        }
+    }
+
+    // Apply a runtime adjustment received on the control channel
+    pub fn apply_control(&mut self, control: ProcessorControl) {
+        match control {
+            ProcessorControl::SetThreshold {
+                sensor_type,
+                threshold,
+            } => self.adjust_threshold(sensor_type, threshold),
+            ProcessorControl::SetWindowSize(window_size) => self.set_window_size(window_size),
+            ProcessorControl::Reset(Some(sensor_id)) => self.reset_sensor(&sensor_id),
+            ProcessorControl::Reset(None) => self.reset_all(),
+            ProcessorControl::Snapshot(reply) => {
+                // The requester may have given up waiting
+                let _ = reply.send(self.snapshot());
+            }
+        }
+    }
+
+    // Forget a sensor's history (window, filter and rate-of-change state) so it
+    // warms up from scratch, e.g. after a known disturbance or recalibration
+    pub fn reset_sensor(&mut self, sensor_id: &str) {
+        self.moving_averages.remove(sensor_id);
+        self.samples_seen.remove(sensor_id);
+        self.ewma.remove(sensor_id);
+        self.last_readings.remove(sensor_id);
+        self.last_values.remove(sensor_id);
+        self.last_timestamps.remove(sensor_id);
+        self.last_seqs.remove(sensor_id);
+        self.last_anomalies.remove(sensor_id);
+        self.last_positions.remove(sensor_id);
+    }
+
+    // Forget the history of every sensor
+    pub fn reset_all(&mut self) {
+        self.moving_averages.clear();
+        self.samples_seen.clear();
+        self.ewma.clear();
+        self.last_readings.clear();
+        self.last_values.clear();
+        self.last_timestamps.clear();
+        self.last_seqs.clear();
+        self.last_anomalies.clear();
+        self.last_positions.clear();
+    }
+
+    // Resize every sensor window, keeping the most recent samples
+    pub fn set_window_size(&mut self, window_size: usize) {
+        self.window_size = window_size;
+        for window in self.moving_averages.values_mut() {
+            window.resize(window_size);
+        }
+    }
+
+    pub fn threshold(&self, sensor_type: SensorType) -> f64 {
+        self.anomaly_thresholds
+            .get(&sensor_type)
+            .cloned()
+            .unwrap_or(3.0)
+    }
+
     pub fn adjust_threshold(&mut self, sensor_type: SensorType, new_threshold: f64) {
         self.anomaly_thresholds.insert(sensor_type, new_threshold);
     }
 }
 
-pub async fn run_processor(
+// Run a processor over `readings` synchronously, returning each processed reading
+// with the actuator command it produced, if any. No channels or runtime needed.
+pub fn process_stream(
     config: &crate::config::ProcessorConfig,
-    rx: crossbeam_channel::Receiver<SensorData>,
-    tx: crossbeam_channel::Sender<SensorData>,
-    metrics_tx: crossbeam_channel::Sender<PerformanceMetrics>,
-    actuator_tx: crossbeam_channel::Sender<ActuatorCommand>, // New channel sender for actuator commands
-) {
-    let mut processor = DataProcessor::new(config.window_size);
+    readings: impl Iterator<Item = SensorData>,
+) -> Vec<(SensorData, Option<ActuatorCommand>)> {
+    match config.stats_backend {
+        StatsBackendKind::Window => {
+            stream_through(DataProcessor::<MovingWindow>::from_config(config), readings)
+        }
+        StatsBackendKind::Cumulative => stream_through(
+            DataProcessor::<CumulativeStats>::from_config(config),
+            readings,
+        ),
+        StatsBackendKind::Ewma => {
+            stream_through(DataProcessor::<EwmaStats>::from_config(config), readings)
+        }
+    }
+}
+
+fn stream_through<B: StatsBackend>(
+    mut processor: DataProcessor<B>,
+    readings: impl Iterator<Item = SensorData>,
+) -> Vec<(SensorData, Option<ActuatorCommand>)> {
+    let mut outputs = Vec::new();
+    for reading in readings {
+        let (processed, _metrics) = processor.process(reading);
+        let derived = processor.derive_velocity(&processed);
+
+        let command = processor.generate_actuator_command(&processed);
+        if processor.should_forward(&processed) {
+            outputs.push((processed, command));
+        }
+
+        if let Some(velocity) = derived {
+            let (processed, _metrics) = processor.process(velocity);
+            let command = processor.generate_actuator_command(&processed);
+            if processor.should_forward(&processed) {
+                outputs.push((processed, command));
+            }
+        }
+    }
+    outputs
+}
+
+// Everything run_processor sent out for a fixed batch of readings
+#[derive(Debug, Default)]
+pub struct ProcessorRun {
+    pub outputs: Vec<SensorData>, // Forwarded to the transmitter, in order
+    pub commands: Vec<ActuatorCommand>, // Sent to the actuator, in order
+    pub metrics: Vec<PerformanceMetrics>, // Anything beyond the channel's capacity is lost
+}
+
+// Drive run_processor over `readings` and collect what it sends once the input
+// channel is drained and closed. Wires up all of its channels, so callers (tests
+// in particular) only provide the config and readings.
+pub async fn run_processor_with_readings(
+    config: &crate::config::ProcessorConfig,
+    readings: Vec<SensorData>,
+) -> ProcessorRun {
+    let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+    for reading in readings {
+        // raw_rx is still alive here, so this can't fail
+        let _ = raw_tx.send(reading);
+    }
+    drop(raw_tx);
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    let (actuator_tx, actuator_rx) = crossbeam_channel::unbounded();
+    let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+    let (metrics_tx, metrics_rx) = crate::common::metrics::metrics_channel(raw_rx.len().max(1) * 8);
+
+    let channels = ProcessorChannels {
+        rx: raw_rx,
+        tx,
+        metrics_tx,
+        actuator_tx,
+        control_rx,
+        anomaly_log: AnomalyLog::new(config.anomaly_log_size),
+        sensor_stats: SharedSensorStats::default(),
+    };
+    run_processor(config, channels).await;
+
+    let mut run = ProcessorRun {
+        outputs: rx.try_iter().collect(),
+        commands: actuator_rx.try_iter().collect(),
+        metrics: Vec::new(),
+    };
+    while let Ok(metrics) = metrics_rx.try_recv() {
+        run.metrics.push(metrics);
+    }
+    run
+}
+
+// What a processor reads from and sends to, and the state it shares with the
+// rest of the pipeline
+pub struct ProcessorChannels {
+    pub rx: crossbeam_channel::Receiver<SensorData>, // Raw readings
+    pub tx: crossbeam_channel::Sender<SensorData>,   // Processed readings, to the transmitter
+    pub metrics_tx: MetricsSender,
+    pub actuator_tx: crossbeam_channel::Sender<ActuatorCommand>, // Commands for anomalies
+    pub control_rx: crossbeam_channel::Receiver<ProcessorControl>, // Runtime adjustments
+    pub anomaly_log: AnomalyLog,
+    pub sensor_stats: SharedSensorStats,
+}
 
+pub async fn run_processor(config: &crate::config::ProcessorConfig, channels: ProcessorChannels) {
+    if config.anomaly_method == AnomalyMethod::RateOfChange && config.max_rate_of_change.is_none() {
+        warn!("Anomaly method is rate_of_change but max_rate_of_change is not set.");
+    }
+
+    match config.stats_backend {
+        StatsBackendKind::Window => drive_processor(
+            DataProcessor::<MovingWindow>::from_config(config)
+                .with_anomaly_log(channels.anomaly_log.clone()),
+            channels,
+        ),
+        StatsBackendKind::Cumulative => drive_processor(
+            DataProcessor::<CumulativeStats>::from_config(config)
+                .with_anomaly_log(channels.anomaly_log.clone()),
+            channels,
+        ),
+        StatsBackendKind::Ewma => drive_processor(
+            DataProcessor::<EwmaStats>::from_config(config)
+                .with_anomaly_log(channels.anomaly_log.clone()),
+            channels,
+        ),
+    }
+}
+
+// run_processor's loop, once the processor has been built
+fn drive_processor<B: StatsBackend>(mut processor: DataProcessor<B>, channels: ProcessorChannels) {
+    let ProcessorChannels {
+        rx,
+        tx,
+        metrics_tx,
+        actuator_tx,
+        control_rx,
+        sensor_stats,
+        ..
+    } = channels;
     let mut prev_duration = None;
     let mut durations = vec![];
     let max_samples = 1000;
@@ -99,14 +984,36 @@ pub async fn run_processor(
     loop {
         match rx.recv() {
             Ok(raw_data) => {
+                // Apply any pending runtime adjustments first
+                while let Ok(control) = control_rx.try_recv() {
+                    processor.apply_control(control);
+                }
+
+                // Falling behind, so only every Nth reading of each sensor is handled
+                if !processor.admit(&raw_data.sensor_id) {
+                    metrics_tx.send(shed_reading());
+                    continue;
+                }
+
                 let start = Instant::now();
 
                 let (processed_data, metrics) = processor.process(raw_data);
+                let missing = processor.take_missing_readings();
+                if missing > 0 {
+                    metrics_tx.send(missing_readings(missing));
+                }
+                if processed_data.rejected {
+                    metrics_tx.send(metrics);
+                    processor.record_processing_time(start.elapsed());
+                    continue;
+                }
+                let derived = processor.derive_velocity(&processed_data);
+                publish_stats(&processor, &sensor_stats, &processed_data.sensor_id);
 
                 // Generate actuator command if anomaly detected
                 if let Some(act_cmd) = processor.generate_actuator_command(&processed_data) {
                     if actuator_tx.send(act_cmd).is_err() {
-                        println!("❌ Actuator command channel closed, stopping processor.");
+                        warn!("Actuator command channel closed, stopping processor.");
                         break;
                     }
                 }
@@ -116,17 +1023,13 @@ pub async fn run_processor(
 
                 // Calculate jitter if previous duration exists
                 if let Some(prev) = prev_duration {
-                    let jitter = if elapsed_ns > prev {
-                        elapsed_ns - prev
-                    } else {
-                        prev - elapsed_ns
-                    };
-                    println!(
+                    let jitter = elapsed_ns.abs_diff(prev);
+                    debug!(
                         "[Processor Timing] Processing time: {} ns, Jitter: {} ns",
                         elapsed_ns, jitter
                     );
                 } else {
-                    println!("[Processor Timing] Processing time: {} ns", elapsed_ns);
+                    debug!("[Processor Timing] Processing time: {} ns", elapsed_ns);
                 }
 
                 prev_duration = Some(elapsed_ns);
@@ -142,7 +1045,7 @@ pub async fn run_processor(
                     let min = durations.iter().min().unwrap();
                     let max = durations.iter().max().unwrap();
                     let avg = durations.iter().sum::<u128>() / durations.len() as u128;
-                    println!(
+                    info!(
                         "[Processor Stats] Min: {} ns, Max: {} ns, Avg: {} ns, Samples: {}",
                         min,
                         max,
@@ -151,17 +1054,954 @@ pub async fn run_processor(
                     );
                 }
 
-                let _ = metrics_tx.send(metrics);
+                metrics_tx.send(metrics);
 
-                if tx.send(processed_data).is_err() {
-                    println!("❌ Transmitter has been dropped, stopping processor.");
+                if !processor.should_forward(&processed_data) {
+                    metrics_tx.send(low_confidence_drop());
+                } else if tx.send(processed_data).is_err() {
+                    warn!("Transmitter has been dropped, stopping processor.");
                     break;
                 }
+
+                // Derived readings go through the same processing as physical ones
+                if let Some(velocity) = derived {
+                    let (velocity, metrics) = processor.process(velocity);
+                    publish_stats(&processor, &sensor_stats, &velocity.sensor_id);
+                    if let Some(act_cmd) = processor.generate_actuator_command(&velocity) {
+                        if actuator_tx.send(act_cmd).is_err() {
+                            warn!("Actuator command channel closed, stopping processor.");
+                            break;
+                        }
+                    }
+                    metrics_tx.send(metrics);
+                    if !processor.should_forward(&velocity) {
+                        metrics_tx.send(low_confidence_drop());
+                    } else if tx.send(velocity).is_err() {
+                        warn!("Transmitter has been dropped, stopping processor.");
+                        break;
+                    }
+                }
+
+                // Includes waiting on a full output channel, the usual sign of falling behind
+                processor.record_processing_time(start.elapsed());
             }
             Err(_) => {
-                println!("❌ Sensor channel closed, stopping processor.");
+                info!("Sensor channel closed, stopping processor.");
                 break;
             }
         }
     }
 }
+
+// Worker that handles `sensor_id` in a pool of `workers` processors. Stable for
+// a given build, so each sensor's filter state lives on exactly one worker.
+pub fn worker_for(sensor_id: &str, workers: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    sensor_id.hash(&mut hasher);
+    (hasher.finish() % workers.max(1) as u64) as usize
+}
+
+// Shard readings across `config.workers` processors by sensor_id, keeping each
+// sensor's readings in order on one worker. Every worker sends into the shared
+// output channels, and runtime adjustments are broadcast to all of them.
+pub async fn run_processor_pool(
+    config: &crate::config::ProcessorConfig,
+    channels: ProcessorChannels,
+) {
+    let workers = config.workers.max(1);
+    if workers == 1 {
+        run_processor(config, channels).await;
+        return;
+    }
+    let ProcessorChannels {
+        rx,
+        tx,
+        metrics_tx,
+        actuator_tx,
+        control_rx,
+        anomaly_log,
+        sensor_stats,
+    } = channels;
+
+    // Each worker blocks on its shard, so it gets a thread from the blocking pool
+    let runtime = tokio::runtime::Handle::current();
+    let mut shard_txs = Vec::with_capacity(workers);
+    let mut control_txs = Vec::with_capacity(workers);
+    let mut handles = Vec::with_capacity(workers);
+    for _ in 0..workers {
+        let (shard_tx, shard_rx) = crossbeam_channel::bounded(config.channel_capacity);
+        let (control_tx, worker_control_rx) = crossbeam_channel::unbounded();
+        shard_txs.push(shard_tx);
+        control_txs.push(control_tx);
+
+        let config = config.clone();
+        let channels = ProcessorChannels {
+            rx: shard_rx,
+            tx: tx.clone(),
+            metrics_tx: metrics_tx.clone(),
+            actuator_tx: actuator_tx.clone(),
+            control_rx: worker_control_rx,
+            anomaly_log: anomaly_log.clone(),
+            sensor_stats: Arc::clone(&sensor_stats),
+        };
+        let runtime = runtime.clone();
+        handles.push(tokio::task::spawn_blocking(move || {
+            runtime.block_on(run_processor(&config, channels));
+        }));
+    }
+
+    // The outputs close once every worker has stopped
+    drop(tx);
+    drop(actuator_tx);
+
+    while let Ok(data) = rx.recv() {
+        while let Ok(control) = control_rx.try_recv() {
+            broadcast_control(&control_txs, control);
+        }
+
+        let worker = worker_for(&data.sensor_id, workers);
+        if shard_txs[worker].send(data).is_err() {
+            warn!("Processor worker {} stopped, stopping processor pool.", worker);
+            break;
+        }
+    }
+
+    // Let the workers drain their shards and stop
+    drop(shard_txs);
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
+
+// Pass a runtime adjustment to every worker. Snapshot requests go to each worker
+// with its own reply channel, and the shards' snapshots are merged into one reply.
+fn broadcast_control(
+    control_txs: &[crossbeam_channel::Sender<ProcessorControl>],
+    control: ProcessorControl,
+) {
+    let ProcessorControl::Snapshot(reply) = control else {
+        for control_tx in control_txs {
+            if let Some(control) = control.try_clone() {
+                let _ = control_tx.send(control);
+            }
+        }
+        return;
+    };
+
+    let shard_replies: Vec<_> = control_txs
+        .iter()
+        .filter_map(|control_tx| {
+            let (shard_tx, shard_rx) = tokio::sync::oneshot::channel();
+            control_tx
+                .send(ProcessorControl::Snapshot(shard_tx))
+                .ok()
+                .map(|()| shard_rx)
+        })
+        .collect();
+    // Workers only answer between readings, so don't hold up the shard loop
+    tokio::spawn(async move {
+        let mut merged = ProcessorSnapshot::default();
+        for shard_rx in shard_replies {
+            if let Ok(snapshot) = shard_rx.await {
+                merged.sensors.extend(snapshot.sensors);
+                merged.decimation = merged.decimation.max(snapshot.decimation);
+            }
+        }
+        let _ = reply.send(merged);
+    });
+}
+
+// Forward raw readings to the transmitter untouched, for when smoothing isn't
+// wanted. Anomaly flags set by the generator pass through as they are.
+pub fn run_passthrough(
+    rx: crossbeam_channel::Receiver<SensorData>,
+    tx: crossbeam_channel::Sender<SensorData>,
+) {
+    while let Ok(data) = rx.recv() {
+        if tx.send(data).is_err() {
+            warn!("Transmitter has been dropped, stopping passthrough.");
+            return;
+        }
+    }
+    info!("Sensor channel closed, stopping passthrough.");
+}
+
+// Metric recording a reading withheld for low confidence
+fn low_confidence_drop() -> PerformanceMetrics {
+    let mut metrics = PerformanceMetrics::new("low_confidence_drop");
+    metrics.complete(true);
+    metrics
+}
+
+// One reading skipped by load shedding
+fn shed_reading() -> PerformanceMetrics {
+    let mut metrics = PerformanceMetrics::new("load_shed");
+    metrics.complete(true);
+    metrics
+}
+
+// Readings lost upstream, found from gaps in their sensors' sequence numbers
+fn missing_readings(count: u64) -> PerformanceMetrics {
+    let mut metrics = PerformanceMetrics::new("missing_reading").with_count(count as usize);
+    metrics.complete_with_error("sequence_gap");
+    metrics
+}
+
+// Copy a sensor's latest stats into the shared map
+fn publish_stats<B: StatsBackend>(
+    processor: &DataProcessor<B>,
+    sensor_stats: &SharedSensorStats,
+    sensor_id: &SensorId,
+) {
+    if let Some(stats) = processor.sensor_stats(sensor_id) {
+        sensor_stats
+            .lock()
+            .unwrap()
+            .insert(sensor_id.clone(), stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::clock::MockClock;
+    use crate::common::test_log;
+    use crate::config::Config;
+    use std::collections::HashSet;
+
+    fn reading(seq: u64, value: f64) -> SensorData {
+        SensorData {
+            timestamp: 1_000 + seq as u128 * 10,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Force,
+            value,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        }
+    }
+
+    // Processor config with a 4 sample window that never flags anything
+    fn quiet_config(stats_backend: StatsBackendKind) -> crate::config::ProcessorConfig {
+        let mut config = Config::default().processor;
+        config.window_size = 4;
+        config.warmup_samples = usize::MAX;
+        config.stats_backend = stats_backend;
+        config
+    }
+
+    fn last_value(stats_backend: StatsBackendKind) -> f64 {
+        let readings = (1..=10).map(|seq| reading(seq, seq as f64));
+        let outputs = process_stream(&quiet_config(stats_backend), readings);
+        outputs.last().unwrap().0.value
+    }
+
+    #[test]
+    fn stats_backend_comes_from_config() {
+        // Mean of 7..=10 against the mean of 1..=10
+        assert_eq!(last_value(StatsBackendKind::Window), 8.5);
+        assert_eq!(last_value(StatsBackendKind::Cumulative), 5.5);
+
+        // Weighted toward the latest readings, but not a 4 sample window
+        let ewma = last_value(StatsBackendKind::Ewma);
+        assert!(ewma > 5.5 && ewma < 10.0 && ewma != 8.5, "{}", ewma);
+    }
+
+    #[test]
+    fn metrics_are_timed_on_the_given_clock() {
+        let clock = MockClock::new(5_000);
+        let mut processor = DataProcessor::new(4).with_clock(Arc::new(clock.clone()));
+
+        let (_, metrics) = processor.process(reading(1, 1.0));
+        assert_eq!(metrics.start_time, clock.now());
+        assert_eq!(metrics.duration_ms, Some(0.0));
+
+        clock.advance(Duration::from_millis(40));
+        let (_, metrics) = processor.process(reading(2, 2.0));
+        assert_eq!(metrics.end_time, Some(clock.now()));
+    }
+
+    #[test]
+    fn anomaly_handlers_run_once_per_anomaly_in_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let first = Arc::clone(&calls);
+        let second = Arc::clone(&calls);
+        let mut processor = DataProcessor::new(20)
+            .with_anomaly_logging(false)
+            .with_anomaly_handler(move |data: &SensorData| {
+                first.lock().unwrap().push(("first", data.seq))
+            })
+            .with_anomaly_callback(move |data| second.lock().unwrap().push(("second", data.seq)));
+
+        // Steady readings around 10, then one spike and back to normal
+        for seq in 1..=20 {
+            processor.process(reading(seq, 10.0 + (seq % 2) as f64 * 0.2));
+        }
+        let (spike, _) = processor.process(reading(21, 100.0));
+        processor.process(reading(22, 10.0));
+
+        assert!(spike.is_anomaly);
+        assert_eq!(*calls.lock().unwrap(), [("first", 21), ("second", 21)]);
+    }
+
+    #[test]
+    fn warm_up_outlasts_a_smaller_window() {
+        let mut processor = DataProcessor::new(5)
+            .with_warmup_samples(20)
+            .with_anomaly_logging(false);
+        let steady = |seq: u64| reading(seq, 10.0 + (seq % 2) as f64 * 0.2);
+
+        // The window has been full since reading 5, but warm-up lasts 20 readings
+        for seq in 1..=19 {
+            processor.process(steady(seq));
+        }
+        let (spike, _) = processor.process(reading(20, 100.0));
+        assert!(!spike.is_anomaly);
+
+        for seq in 21..=29 {
+            processor.process(steady(seq));
+        }
+        let (spike, _) = processor.process(reading(30, 100.0));
+        assert!(spike.is_anomaly);
+
+        // Starting over means warming up again
+        processor.reset_sensor("force_1");
+        for seq in 31..=49 {
+            processor.process(steady(seq));
+        }
+        let (spike, _) = processor.process(reading(50, 100.0));
+        assert!(!spike.is_anomaly);
+    }
+
+    #[test]
+    fn backends_count_samples_differently() {
+        let mut window =
+            DataProcessor::<MovingWindow>::from_config(&quiet_config(StatsBackendKind::Window));
+        let mut cumulative = DataProcessor::<CumulativeStats>::from_config(&quiet_config(
+            StatsBackendKind::Cumulative,
+        ));
+        for seq in 1..=10 {
+            window.process(reading(seq, seq as f64));
+            cumulative.process(reading(seq, seq as f64));
+        }
+
+        let window = window.sensor_stats("force_1").unwrap();
+        let cumulative = cumulative.sensor_stats("force_1").unwrap();
+        assert_eq!((window.samples, window.min, window.max), (4, 7.0, 10.0));
+        assert_eq!(
+            (cumulative.samples, cumulative.min, cumulative.max),
+            (10, 1.0, 10.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_sequence_gap_is_one_metric_counting_the_missing_readings() {
+        // Readings 3, 4 and 5 never arrive
+        let readings = [1, 2, 6, 7].map(|seq| reading(seq, 10.0)).to_vec();
+        let config = quiet_config(StatsBackendKind::Window);
+        let run = run_processor_with_readings(&config, readings).await;
+
+        let missing: Vec<_> = run
+            .metrics
+            .iter()
+            .filter(|m| m.operation == "missing_reading")
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].count, 3);
+        assert_eq!(missing[0].error_kind.as_deref(), Some("sequence_gap"));
+    }
+
+    #[tokio::test]
+    async fn a_gap_ending_on_a_rejected_reading_is_still_counted() {
+        let mut readings = [1, 2, 5].map(|seq| reading(seq, 10.0)).to_vec();
+        readings[2].value = f64::NAN;
+        let config = quiet_config(StatsBackendKind::Window);
+        let run = run_processor_with_readings(&config, readings).await;
+
+        let missing: Vec<_> = run
+            .metrics
+            .iter()
+            .filter(|m| m.operation == "missing_reading")
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].count, 2);
+        assert_eq!(run.outputs.len(), 2);
+    }
+
+    #[test]
+    fn a_rejected_spike_leaves_the_window_alone() {
+        let mut processor = DataProcessor::new(10).with_anomaly_logging(false);
+        for seq in 1..=10 {
+            processor.process(reading(seq, 10.0 + (seq % 2) as f64 * 0.2));
+        }
+        let before = processor.sensor_stats("force_1").unwrap();
+
+        let (spike, _) = processor.process(reading(11, 100.0));
+        assert!(spike.is_anomaly);
+        let after = processor.sensor_stats("force_1").unwrap();
+        assert_eq!(
+            (after.mean, after.std_dev, after.samples),
+            (before.mean, before.std_dev, before.samples)
+        );
+
+        // Fed in when configured to, the spike drags the mean up
+        let mut processor = DataProcessor::new(10)
+            .with_anomaly_logging(false)
+            .with_update_on_anomaly(true);
+        for seq in 1..=10 {
+            processor.process(reading(seq, 10.0 + (seq % 2) as f64 * 0.2));
+        }
+        processor.process(reading(11, 100.0));
+        assert!(processor.sensor_stats("force_1").unwrap().mean > 18.0);
+    }
+
+    #[tokio::test]
+    async fn an_anomaly_is_forwarded_flagged_and_sends_one_command() {
+        let mut config = Config::default().processor;
+        config.window_size = 10;
+        config.warmup_samples = 5;
+        let mut readings: Vec<_> = (1..=10)
+            .map(|seq| reading(seq, 10.0 + (seq % 2) as f64 * 0.2))
+            .collect();
+        readings.push(reading(11, 100.0));
+        readings.push(reading(12, 10.1));
+
+        let run = run_processor_with_readings(&config, readings).await;
+
+        assert_eq!(run.outputs.len(), 12);
+        let flagged: Vec<_> = run.outputs.iter().filter(|r| r.is_anomaly).collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].seq, 11);
+
+        assert_eq!(run.commands.len(), 1);
+        let command = &run.commands[0];
+        assert_eq!(command.control_command.command_type, "adjust_position");
+        assert_eq!(command.actuator_id, SensorId::new("force_1").actuator());
+        // The command carries the forwarded (smoothed) value, not the raw spike
+        assert_eq!(command.control_command.value, flagged[0].value);
+        assert_eq!(command.command_id.as_deref(), Some("force_1#11/anomaly"));
+    }
+
+    #[test]
+    fn an_anomaly_is_logged_as_a_warning_and_reaches_the_handler() {
+        test_log::capture();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let handler_seen = seen.clone();
+        let mut processor = DataProcessor::new(10)
+            .with_warmup_samples(0)
+            .with_anomaly_callback(move |data: &SensorData| {
+                handler_seen.lock().unwrap().push(data.seq)
+            });
+        let logged = |seq, value| SensorData {
+            sensor_id: SensorId::new("logged_force"),
+            ..reading(seq, value)
+        };
+        for seq in 1..=10 {
+            processor.process(logged(seq, 10.0 + (seq % 2) as f64 * 0.2));
+        }
+        let (spike, _) = processor.process(logged(11, 100.0));
+        assert!(spike.is_anomaly);
+        assert_eq!(*seen.lock().unwrap(), vec![11]);
+
+        let anomalies = test_log::records_starting_with("[ANOMALY] Sensor: logged_force");
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].0, log::Level::Warn);
+    }
+
+    #[test]
+    fn rate_of_change_ignores_a_smooth_ramp_and_flags_a_jump() {
+        let anomaly_log = AnomalyLog::new(8);
+        let mut processor = DataProcessor::new(10)
+            .with_anomaly_method(AnomalyMethod::RateOfChange)
+            .with_rate_of_change_limit(Some(500.0))
+            .with_warmup_samples(0)
+            .with_anomaly_log(anomaly_log.clone());
+
+        // One unit every 10ms is 100/s, well under the limit
+        for seq in 1..=10 {
+            let (ramp, _) = processor.process(reading(seq, seq as f64));
+            assert!(!ramp.is_anomaly, "ramp flagged at {}", seq);
+        }
+        // 20 units in 10ms is 2000/s
+        let (jump, _) = processor.process(reading(11, 30.0));
+        assert!(jump.is_anomaly);
+
+        let events = anomaly_log.recent(8);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].reason, AnomalyReason::RateOfChange);
+    }
+
+    #[test]
+    fn calibration_scales_and_offsets_the_raw_value() {
+        let calibrations = HashMap::from([(
+            SensorId::new("force_1"),
+            Calibration {
+                scale: 2.0,
+                offset: 1.0,
+            },
+        )]);
+        let mut processor = DataProcessor::new(4).with_calibrations(calibrations);
+        assert_eq!(processor.calibrate("force_1", 10.0), 21.0);
+        assert_eq!(processor.calibrate("force_2", 10.0), 10.0);
+
+        // The window (and so the anomaly check) only ever sees calibrated values
+        let (output, _) = processor.process(reading(1, 10.0));
+        assert_eq!(output.value, 21.0);
+        assert_eq!(processor.sensor_stats("force_1").unwrap().mean, 21.0);
+    }
+
+    #[test]
+    fn process_stream_returns_each_output_with_its_command() {
+        let readings = (1..=6).map(|seq| reading(seq, seq as f64));
+        let outputs = process_stream(&quiet_config(StatsBackendKind::Window), readings);
+        let smoothed: Vec<_> = outputs.iter().map(|(r, _)| (r.seq, r.value)).collect();
+        // Means over the last (up to) 4 readings
+        assert_eq!(
+            smoothed,
+            [(1, 1.0), (2, 1.5), (3, 2.0), (4, 2.5), (5, 3.5), (6, 4.5)]
+        );
+        assert!(outputs.iter().all(|(_, command)| command.is_none()));
+
+        let mut config = Config::default().processor;
+        config.window_size = 10;
+        config.warmup_samples = 5;
+        let readings = (1..=12).map(|seq| match seq {
+            11 => reading(seq, 100.0),
+            _ => reading(seq, 10.0 + (seq % 2) as f64 * 0.2),
+        });
+        let outputs = process_stream(&config, readings);
+        assert_eq!(outputs.len(), 12);
+        for (output, command) in &outputs {
+            assert_eq!(output.is_anomaly, output.seq == 11);
+            assert_eq!(command.is_some(), output.seq == 11);
+        }
+        let command = outputs[10].1.as_ref().unwrap();
+        assert_eq!(command.command_id.as_deref(), Some("force_1#11/anomaly"));
+    }
+
+    #[test]
+    fn velocity_is_derived_from_the_position_slope() {
+        let mut processor = DataProcessor::new(4).with_derived_velocity(true);
+        let position = |seq: u64, timestamp: u128| SensorData {
+            sensor_id: SensorId::new("position_1"),
+            reading_type: SensorType::Position,
+            timestamp,
+            ..reading(seq, seq as f64 * 2.0)
+        };
+
+        // Nothing to derive from until a second reading
+        assert!(processor.derive_velocity(&position(1, 1_000)).is_none());
+        for seq in 2..=5 {
+            let velocity = processor
+                .derive_velocity(&position(seq, 1_000 + (seq as u128 - 1) * 10))
+                .unwrap();
+            assert_eq!(velocity.sensor_id, SensorId::new("position_1_velocity"));
+            assert_eq!(velocity.reading_type, SensorType::Velocity);
+            assert_eq!(velocity.seq, seq);
+            // 2 units every 10ms
+            assert!((velocity.value - 200.0).abs() < 1e-9, "{}", velocity.value);
+        }
+        // An out-of-order reading gives nothing
+        assert!(processor.derive_velocity(&position(6, 1_020)).is_none());
+        // Neither do other reading types
+        assert!(processor.derive_velocity(&reading(7, 1.0)).is_none());
+    }
+
+    #[test]
+    fn confidence_band_widens_with_variance() {
+        let band_width = |spread: f64| {
+            let readings = (1..=8).map(|seq| reading(seq, 10.0 + (seq % 2) as f64 * spread));
+            let outputs = process_stream(&quiet_config(StatsBackendKind::Window), readings);
+            // No band until the window holds two samples
+            assert!(outputs[0].0.confidence_lower.is_none());
+            let last = &outputs.last().unwrap().0;
+            let (lower, upper) = (
+                last.confidence_lower.unwrap(),
+                last.confidence_upper.unwrap(),
+            );
+            assert!((upper - last.value - (last.value - lower)).abs() < 1e-9);
+            upper - lower
+        };
+
+        let narrow = band_width(0.2);
+        let wide = band_width(2.0);
+        assert!(narrow > 0.0);
+        assert!(wide > narrow * 5.0, "{} vs {}", wide, narrow);
+    }
+
+    #[test]
+    fn after_a_reset_a_new_level_is_not_scored_against_stale_history() {
+        let steady = |processor: &mut DataProcessor, sensor: &str| {
+            for seq in 1..=10 {
+                processor.process(SensorData {
+                    sensor_id: SensorId::new(sensor),
+                    ..reading(seq, 10.0 + (seq % 2) as f64 * 0.2)
+                });
+            }
+        };
+        let next = |processor: &mut DataProcessor, sensor: &str| {
+            let (output, _) = processor.process(SensorData {
+                sensor_id: SensorId::new(sensor),
+                ..reading(11, 50.0)
+            });
+            output.is_anomaly
+        };
+        let mut processor = DataProcessor::new(10).with_warmup_samples(5);
+        steady(&mut processor, "force_1");
+        steady(&mut processor, "force_2");
+
+        // After a recalibration to 50, force_1 starts over; force_2 keeps its history
+        processor.apply_control(ProcessorControl::Reset(Some(SensorId::new("force_1"))));
+        assert!(processor.sensor_stats("force_1").is_none());
+        assert!(!next(&mut processor, "force_1"));
+        assert!(next(&mut processor, "force_2"));
+
+        steady(&mut processor, "force_2");
+        processor.apply_control(ProcessorControl::Reset(None));
+        assert!(processor.sensor_stats("force_2").is_none());
+        assert!(!next(&mut processor, "force_2"));
+    }
+
+    #[test]
+    fn sensor_stats_track_the_window_min_and_max() {
+        let mut config = quiet_config(StatsBackendKind::Window);
+        config.window_size = 11;
+        let mut processor = DataProcessor::<MovingWindow>::from_config(&config);
+        for (seq, value) in (5..=15).enumerate() {
+            processor.process(reading(seq as u64 + 1, value as f64));
+        }
+        let stats = processor.sensor_stats("force_1").unwrap();
+        assert_eq!((stats.samples, stats.min, stats.max), (11, 5.0, 15.0));
+        assert_eq!(stats.mean, 10.0);
+
+        // Older values fall out of range as the window moves on
+        processor.set_window_size(4);
+        let stats = processor.sensor_stats("force_1").unwrap();
+        assert_eq!((stats.samples, stats.min, stats.max), (4, 12.0, 15.0));
+        assert!(processor.sensor_stats("force_2").is_none());
+    }
+
+    #[tokio::test]
+    async fn low_confidence_readings_are_withheld_but_anomalies_are_not() {
+        let mut config = Config::default().processor;
+        config.window_size = 10;
+        config.warmup_samples = 5;
+        config.min_confidence = Some(0.9);
+        let readings: Vec<_> = (1..=30)
+            .map(|seq| match seq {
+                25 => reading(seq, 100.0),
+                _ => reading(seq, 10.0 + (seq % 3) as f64 * 0.5),
+            })
+            .collect();
+
+        // Confidence of each reading, as scored without the filter
+        let mut scorer = DataProcessor::<MovingWindow>::from_config(&config);
+        let confident: Vec<_> = readings
+            .iter()
+            .map(|r| scorer.process(r.clone()).0)
+            .filter(|r| r.is_anomaly || r.confidence >= 0.9)
+            .map(|r| r.seq)
+            .collect();
+        assert!(confident.contains(&25));
+        assert!(
+            confident.len() > 1 && confident.len() < 20,
+            "{:?}",
+            confident
+        );
+
+        let run = run_processor_with_readings(&config, readings).await;
+        let forwarded: Vec<_> = run.outputs.iter().map(|r| r.seq).collect();
+        assert_eq!(forwarded, confident);
+        assert!(run
+            .outputs
+            .iter()
+            .all(|r| r.is_anomaly || r.confidence >= 0.9));
+        let drops = run
+            .metrics
+            .iter()
+            .filter(|m| m.operation == "low_confidence_drop")
+            .count();
+        assert_eq!(drops, 30 - forwarded.len());
+    }
+
+    #[test]
+    fn backward_timestamp_is_flagged_and_left_out_of_the_slopes() {
+        let mut processor = DataProcessor::new(10)
+            .with_rate_of_change_limit(Some(500.0))
+            .with_warmup_samples(0)
+            .with_derived_velocity(true)
+            .with_timestamp_guard(TimestampGuard::Flag);
+        let position = |seq: u64, timestamp: u128| SensorData {
+            sensor_id: SensorId::new("position_1"),
+            reading_type: SensorType::Position,
+            timestamp,
+            ..reading(seq, seq as f64)
+        };
+
+        for seq in 1..=5 {
+            let (output, _) = processor.process(position(seq, 1_000 + seq as u128 * 10));
+            processor.derive_velocity(&output);
+        }
+        // The clock steps back 40ms: a negative dt would give a huge slope
+        let (stepped_back, _) = processor.process(position(6, 1_010));
+        assert_eq!(stepped_back.timestamp, 1_010);
+        assert!(!stepped_back.is_anomaly);
+        assert!(processor.derive_velocity(&stepped_back).is_none());
+
+        // Once the clock catches up the slope is taken against the last good reading
+        let (output, _) = processor.process(position(7, 1_070));
+        assert!(!output.is_anomaly);
+        let velocity = processor.derive_velocity(&output).unwrap();
+        assert!(
+            velocity.value > 0.0 && velocity.value < 500.0,
+            "velocity {}",
+            velocity.value
+        );
+    }
+
+    #[test]
+    fn backward_timestamp_is_clamped_forward() {
+        let mut processor = DataProcessor::new(10)
+            .with_rate_of_change_limit(Some(500.0))
+            .with_warmup_samples(0)
+            .with_derived_velocity(true)
+            .with_timestamp_guard(TimestampGuard::Clamp);
+        let position = |timestamp: u128| SensorData {
+            sensor_id: SensorId::new("position_1"),
+            reading_type: SensorType::Position,
+            timestamp,
+            ..reading(1, 5.0)
+        };
+
+        let (first, _) = processor.process(position(1_050));
+        processor.derive_velocity(&first);
+        let (stepped_back, _) = processor.process(position(1_010));
+        assert_eq!(stepped_back.timestamp, 1_051);
+        assert!(!stepped_back.is_anomaly);
+        let velocity = processor.derive_velocity(&stepped_back).unwrap();
+        assert_eq!(velocity.value, 0.0);
+    }
+
+    #[test]
+    fn passthrough_forwards_the_raw_reading_unchanged() {
+        let (sensor_tx, sensor_rx) = crossbeam_channel::unbounded();
+        let (processed_tx, processed_rx) = crossbeam_channel::unbounded();
+        sensor_tx.send(reading(1, 10.0)).unwrap();
+        sensor_tx
+            .send(SensorData {
+                is_anomaly: true,
+                ..reading(2, 123.4)
+            })
+            .unwrap();
+        drop(sensor_tx);
+
+        // Returns once the sensor channel is closed and drained
+        run_passthrough(sensor_rx, processed_tx);
+
+        let forwarded: Vec<_> = processed_rx.try_iter().collect();
+        assert_eq!(forwarded.len(), 2);
+        assert_eq!(forwarded[0].value, 10.0);
+        assert_eq!(forwarded[1].value, 123.4);
+        assert!(forwarded[1].is_anomaly);
+    }
+
+    #[test]
+    fn anomaly_cooldown_lets_one_command_through_per_burst() {
+        let mut processor = DataProcessor::new(10)
+            .with_warmup_samples(5)
+            .with_anomaly_cooldowns(HashMap::from([(SensorType::Force, 100)]));
+        for seq in 1..=10 {
+            processor.process(reading(seq, 10.0 + (seq % 2) as f64 * 0.2));
+        }
+
+        // Spikes 10ms apart: the first starts the cooldown, which mutes the rest
+        let commands: Vec<_> = (11..=15)
+            .map(|seq| {
+                let (spike, _) = processor.process(reading(seq, 100.0));
+                processor.generate_actuator_command(&spike)
+            })
+            .collect();
+        assert!(commands[0].is_some());
+        assert!(commands[1..].iter().all(Option::is_none));
+
+        // 100ms after the first spike the cooldown has run out
+        let (spike, _) = processor.process(reading(21, 100.0));
+        assert!(processor.generate_actuator_command(&spike).is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn worker_pool_keeps_each_sensor_on_one_worker() {
+        let sensor = |n: u64| SensorId::new(format!("force_{}", n));
+        let reading_for = |n: u64, seq: u64| SensorData {
+            sensor_id: sensor(n),
+            ..reading(seq, (n * 100 + seq) as f64)
+        };
+
+        // The shard is a pure function of the sensor_id, and the sensors spread out
+        let workers: HashSet<_> = (0..20)
+            .map(|n| {
+                let worker = worker_for(&sensor(n), 4);
+                assert_eq!(worker, worker_for(&sensor(n), 4));
+                worker
+            })
+            .collect();
+        assert!(workers.len() > 1);
+
+        let mut config = quiet_config(StatsBackendKind::Window);
+        config.workers = 4;
+        let (raw_tx, raw_rx) = crossbeam_channel::unbounded();
+        for seq in 1..=10 {
+            for n in 0..20 {
+                raw_tx.send(reading_for(n, seq)).unwrap();
+            }
+        }
+        drop(raw_tx);
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let (actuator_tx, _actuator_rx) = crossbeam_channel::unbounded();
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+        let (metrics_tx, _metrics_rx) = crate::common::metrics::metrics_channel(2_000);
+        let channels = ProcessorChannels {
+            rx: raw_rx,
+            tx,
+            metrics_tx,
+            actuator_tx,
+            control_rx,
+            anomaly_log: AnomalyLog::new(config.anomaly_log_size),
+            sensor_stats: SharedSensorStats::default(),
+        };
+        run_processor_pool(&config, channels).await;
+
+        // Each sensor's smoothed values match a single processor fed only that
+        // sensor, which only holds if its window never moved between workers
+        let outputs: Vec<_> = rx.try_iter().collect();
+        assert_eq!(outputs.len(), 200);
+        for n in 0..20 {
+            let pooled: Vec<_> = outputs
+                .iter()
+                .filter(|output| output.sensor_id == sensor(n))
+                .map(|output| (output.seq, output.value))
+                .collect();
+            let alone: Vec<_> = process_stream(&config, (1..=10).map(|seq| reading_for(n, seq)))
+                .into_iter()
+                .map(|(output, _)| (output.seq, output.value))
+                .collect();
+            assert_eq!(pooled, alone, "sensor {}", n);
+        }
+    }
+
+    #[test]
+    fn zero_centred_sensor_does_not_storm_anomalies() {
+        let mut processor = DataProcessor::new(10).with_warmup_samples(5);
+        for seq in 1..=200 {
+            let value = ((seq % 2) as f64 - 0.5) * 0.1;
+            let (output, _) = processor.process(reading(seq, value));
+            assert!(!output.is_anomaly, "flagged {} at {}", value, seq);
+        }
+        let (spike, _) = processor.process(reading(201, -1.0));
+        assert!(spike.is_anomaly);
+    }
+
+    #[test]
+    fn snapshot_reports_count_mean_and_last_value() {
+        let mut processor = DataProcessor::new(10);
+        for (seq, value) in [(1, 2.0), (2, 4.0), (3, 6.0), (4, 8.0)] {
+            processor.process(reading(seq, value));
+        }
+
+        let (reply, mut snapshot_rx) = tokio::sync::oneshot::channel();
+        processor.apply_control(ProcessorControl::Snapshot(reply));
+        let snapshot = snapshot_rx.try_recv().unwrap();
+        let sensor = snapshot.sensors[&SensorId::new("force_1")];
+        assert_eq!(sensor.samples, 4);
+        assert_eq!(sensor.mean, 5.0);
+        assert_eq!(sensor.last_value, 8.0);
+        assert!(sensor.std_dev > 0.0);
+    }
+
+    #[test]
+    fn hard_limit_flags_a_statistically_consistent_reading() {
+        let anomaly_log = AnomalyLog::new(8);
+        let limits = HardLimits {
+            warn: None,
+            alarm: Some(80.0),
+        };
+        let mut processor = DataProcessor::new(10)
+            .with_warmup_samples(5)
+            .with_hard_limits(HashMap::from([(SensorType::Temperature, limits)]))
+            .with_anomaly_log(anomaly_log.clone());
+        let temperature = |seq: u64, value: f64| SensorData {
+            sensor_id: SensorId::new("temperature_1"),
+            reading_type: SensorType::Temperature,
+            ..reading(seq, value)
+        };
+
+        // Steady at 90, so the z-score never flags it, but 90 is over the alarm limit
+        for seq in 1..=10 {
+            let (output, _) = processor.process(temperature(seq, 90.0 + (seq % 2) as f64 * 0.2));
+            assert!(output.is_anomaly, "not flagged at {}", seq);
+        }
+        let events = anomaly_log.recent(8);
+        assert_eq!(events.len(), 8);
+        assert!(events
+            .iter()
+            .all(|event| event.reason == AnomalyReason::HardLimit));
+    }
+
+    #[test]
+    fn impossible_reading_is_rejected_and_kept_out_of_the_mean() {
+        let mut processor = DataProcessor::new(10);
+        let temperature = |seq: u64, value: f64| SensorData {
+            sensor_id: SensorId::new("temperature_1"),
+            reading_type: SensorType::Temperature,
+            ..reading(seq, value)
+        };
+        for seq in 1..=4 {
+            processor.process(temperature(seq, 20.0));
+        }
+
+        // Below absolute zero can only be corruption
+        let (rejected, metrics) = processor.process(temperature(5, -300.0));
+        assert!(rejected.rejected);
+        assert!(!rejected.is_anomaly);
+        assert_eq!(rejected.value, -300.0);
+        assert_eq!(metrics.error_kind.as_deref(), Some("implausible_value"));
+
+        let stats = processor.sensor_stats("temperature_1").unwrap();
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.mean, 20.0);
+    }
+
+    #[test]
+    fn subscribers_get_an_event_matching_the_detection_inputs() {
+        let anomaly_log = AnomalyLog::new(8);
+        let events = anomaly_log.subscribe();
+        let mut processor = DataProcessor::new(10)
+            .with_warmup_samples(5)
+            .with_anomaly_log(anomaly_log);
+        for seq in 1..=10 {
+            processor.process(reading(seq, 10.0 + (seq % 2) as f64 * 0.2));
+        }
+        // The spike is scored against the window as it was before the spike
+        let window = processor.sensor_stats("force_1").unwrap();
+        let (spike, _) = processor.process(reading(11, 100.0));
+        assert!(spike.is_anomaly);
+
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.sensor_id, SensorId::new("force_1"));
+        assert_eq!(event.timestamp, reading(11, 100.0).timestamp);
+        assert_eq!(event.value, 100.0);
+        assert_eq!(event.mean, window.mean);
+        assert_eq!(event.std_dev, window.std_dev);
+        assert_eq!(event.z_score, (100.0 - window.mean) / window.std_dev);
+        assert_eq!(event.confidence, spike.confidence);
+        assert_eq!(event.reason, AnomalyReason::Statistical);
+        assert!(events.try_recv().is_err());
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["sensor_id"], "force_1");
+    }
+}
@@ -0,0 +1,153 @@
+use crate::common::data_types::{SensorData, SensorId, SensorType};
+use log::info;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time;
+
+// Load recorded readings from a CSV file with the columns
+// timestamp,sensor_id,reading_type,value (a header line is optional). Each
+// sensor's readings are numbered from 1 in file order.
+pub fn load_csv(path: &str) -> Result<Vec<SensorData>, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut readings = Vec::new();
+    let mut seqs: HashMap<String, u64> = HashMap::new();
+
+    for (line_no, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("timestamp") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 4 {
+            return Err(format!(
+                "Line {}: expected 4 columns, got {}",
+                line_no + 1,
+                fields.len()
+            )
+            .into());
+        }
+
+        let seq = seqs.entry(fields[1].to_string()).or_insert(0);
+        *seq += 1;
+
+        readings.push(SensorData {
+            timestamp: fields[0].parse()?,
+            sensor_id: SensorId::new(fields[1]),
+            reading_type: parse_sensor_type(fields[2]).ok_or_else(|| {
+                format!("Line {}: unknown sensor type {}", line_no + 1, fields[2])
+            })?,
+            value: fields[3].parse()?,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq: *seq,
+            rejected: false,
+        });
+    }
+
+    Ok(readings)
+}
+
+fn parse_sensor_type(name: &str) -> Option<SensorType> {
+    match name {
+        "Force" => Some(SensorType::Force),
+        "Position" => Some(SensorType::Position),
+        "Velocity" => Some(SensorType::Velocity),
+        "Temperature" => Some(SensorType::Temperature),
+        _ => None,
+    }
+}
+
+// Send recorded readings with their original spacing divided by `speed`
+// (2.0 replays twice as fast). A speed of zero or less replays as fast as possible.
+pub async fn run_replay(
+    readings: Vec<SensorData>,
+    speed: f64,
+    tx: crossbeam_channel::Sender<SensorData>,
+) {
+    let mut previous_timestamp = None;
+
+    for data in readings {
+        if let Some(previous) = previous_timestamp {
+            if speed > 0.0 && data.timestamp > previous {
+                let gap_ms = (data.timestamp - previous) as f64 / speed;
+                time::sleep(Duration::from_secs_f64(gap_ms / 1000.0)).await;
+            }
+        }
+        previous_timestamp = Some(data.timestamp);
+
+        if tx.send(data).is_err() {
+            info!("Receiver has been dropped, stopping replay.");
+            return;
+        }
+    }
+
+    info!("Replay finished.");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replayed_readings_are_numbered_per_sensor() {
+        let path = std::env::temp_dir().join(format!("replay_seq_{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "timestamp,sensor_id,reading_type,value\n\
+             1000,s1,Force,1.0\n\
+             1000,s2,Position,2.0\n\
+             1000,s1,Force,3.0\n",
+        )
+        .unwrap();
+        let readings = load_csv(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let ids: Vec<_> = readings.iter().map(|r| r.reading_id().unwrap()).collect();
+        assert_eq!(ids, ["s1#1", "s2#1", "s1#2"]);
+    }
+
+    // Five readings spanning 400ms of recorded time
+    fn recording() -> Vec<SensorData> {
+        (0..5u64)
+            .map(|i| SensorData {
+                timestamp: 1_000 + i as u128 * 100,
+                sensor_id: SensorId::new("s1"),
+                reading_type: SensorType::Force,
+                value: i as f64,
+                is_anomaly: false,
+                confidence: 1.0,
+                confidence_lower: None,
+                confidence_upper: None,
+                seq: i + 1,
+                rejected: false,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replay_at_double_speed_takes_half_the_recorded_span() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let start = std::time::Instant::now();
+        run_replay(recording(), 2.0, tx).await;
+        let elapsed = start.elapsed();
+
+        assert_eq!(rx.try_iter().count(), 5);
+        assert!(elapsed >= Duration::from_millis(200), "took {:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(350), "took {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn replay_without_a_speed_runs_as_fast_as_possible() {
+        for speed in [0.0, -1.0] {
+            let (tx, rx) = crossbeam_channel::unbounded();
+            let start = std::time::Instant::now();
+            run_replay(recording(), speed, tx).await;
+
+            assert_eq!(rx.try_iter().count(), 5);
+            assert!(start.elapsed() < Duration::from_millis(100));
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::common::data_types::SensorId;
+use std::collections::HashMap;
+use std::time::Duration;
+
+// Processed readings between decimation changes, so one slow reading can't
+// flip the level back and forth
+const ADJUST_EVERY: usize = 20;
+
+// Weight of the latest processing time in the running average
+const LATENCY_EWMA_ALPHA: f64 = 0.1;
+
+// Sheds load when processing falls behind its deadline. At decimation level N
+// only every Nth reading of each sensor is processed. The level doubles while
+// the average processing time is over the deadline and halves once it is back
+// under half of it.
+pub struct LoadShedder {
+    deadline: Duration,
+    max_level: usize,
+    level: usize,
+    avg_secs: Option<f64>,
+    since_adjust: usize,
+    seen: HashMap<SensorId, usize>, // Readings per sensor, to pick every Nth
+}
+
+impl LoadShedder {
+    pub fn new(deadline: Duration, max_level: usize) -> Self {
+        Self {
+            deadline,
+            max_level: max_level.max(1),
+            level: 1,
+            avg_secs: None,
+            since_adjust: 0,
+            seen: HashMap::new(),
+        }
+    }
+
+    // Whether this reading of `sensor_id` should be processed at the current level
+    pub fn admit(&mut self, sensor_id: &SensorId) -> bool {
+        let seen = self.seen.entry(sensor_id.clone()).or_insert(0);
+        let admit = seen.is_multiple_of(self.level);
+        *seen += 1;
+        admit
+    }
+
+    // Record how long a processed reading took. Returns the new level when it changes.
+    pub fn record(&mut self, elapsed: Duration) -> Option<usize> {
+        let secs = elapsed.as_secs_f64();
+        let avg = match self.avg_secs {
+            Some(avg) => avg + LATENCY_EWMA_ALPHA * (secs - avg),
+            None => secs,
+        };
+        self.avg_secs = Some(avg);
+
+        self.since_adjust += 1;
+        if self.since_adjust < ADJUST_EVERY {
+            return None;
+        }
+
+        let deadline = self.deadline.as_secs_f64();
+        let level = if avg > deadline {
+            (self.level * 2).min(self.max_level)
+        } else if avg < deadline / 2.0 {
+            (self.level / 2).max(1)
+        } else {
+            self.level
+        };
+        if level == self.level {
+            return None;
+        }
+        self.level = level;
+        self.since_adjust = 0;
+        Some(level)
+    }
+
+    // Current decimation, 1 when nothing is shed
+    pub fn level(&self) -> usize {
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_processing_raises_the_level_and_recovery_lowers_it() {
+        let deadline = Duration::from_millis(1);
+        let mut shedder = LoadShedder::new(deadline, 4);
+        let record_all = |shedder: &mut LoadShedder, elapsed: Duration| {
+            (0..ADJUST_EVERY)
+                .filter_map(|_| shedder.record(elapsed))
+                .last()
+        };
+
+        // Twice the deadline doubles the level, up to the maximum
+        assert_eq!(record_all(&mut shedder, deadline * 2), Some(2));
+        assert_eq!(record_all(&mut shedder, deadline * 2), Some(4));
+        assert_eq!(record_all(&mut shedder, deadline * 2), None);
+        assert_eq!(shedder.level(), 4);
+
+        // Only every 4th reading of a sensor gets through
+        let sensor_id = SensorId::new("force_1");
+        let admitted = (0..8).filter(|_| shedder.admit(&sensor_id)).count();
+        assert_eq!(admitted, 2);
+
+        // Well under the deadline it halves back down once the average catches up
+        let levels: Vec<_> = (0..10)
+            .filter_map(|_| record_all(&mut shedder, Duration::ZERO))
+            .collect();
+        assert_eq!(levels, [2, 1]);
+        assert_eq!(shedder.level(), 1);
+    }
+}
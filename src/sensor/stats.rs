@@ -0,0 +1,440 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Samples between exact recomputations of the running statistics, bounding the
+// rounding error that add/remove updates accumulate over long runs
+const STATS_RECOMPUTE_INTERVAL: usize = 1024;
+
+// Scales a MAD to a standard deviation estimate for normally distributed data
+pub const MAD_TO_STD_DEV: f64 = 1.4826;
+
+// Running statistics over one sensor's readings, which the DataProcessor scores
+// and smooths against. How `window_size` is interpreted is up to the backend.
+pub trait StatsBackend: Send {
+    fn from_window_size(window_size: usize) -> Self
+    where
+        Self: Sized;
+
+    fn update(&mut self, value: f64);
+
+    fn mean(&self) -> f64;
+
+    fn std_dev(&self) -> f64;
+
+    // Samples that contributed to the statistics
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn min(&self) -> f64;
+
+    fn max(&self) -> f64;
+
+    // Median and median absolute deviation. Backends that don't keep their
+    // samples estimate them from the mean and standard deviation.
+    fn median_and_mad(&self) -> (f64, f64) {
+        (self.mean(), self.std_dev() / MAD_TO_STD_DEV)
+    }
+
+    // Change the window size at runtime
+    fn resize(&mut self, window_size: usize);
+}
+
+// Which StatsBackend each sensor's statistics are kept in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsBackendKind {
+    // MovingWindow over the last window_size samples
+    #[default]
+    Window,
+    // CumulativeStats over every sample
+    Cumulative,
+    // EwmaStats, alpha = 2 / (window_size + 1)
+    Ewma,
+}
+
+// Bounded window of the most recent samples for one sensor. Mean and variance are
+// kept up to date with Welford's algorithm as samples enter and leave the window.
+pub struct MovingWindow {
+    samples: VecDeque<f64>,
+    capacity: usize,
+    mean: f64,
+    m2: f64, // Sum of squared deviations from the mean
+    updates_since_recompute: usize,
+}
+
+impl MovingWindow {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            mean: 0.0,
+            m2: 0.0,
+            updates_since_recompute: 0,
+        }
+    }
+
+    // Sample variance over the window
+    pub fn variance(&self) -> f64 {
+        let n = self.samples.len();
+        if n < 2 {
+            return 0.0;
+        }
+        self.m2 / (n - 1) as f64
+    }
+
+    // Welford removal of the oldest sample
+    fn evict_oldest(&mut self) {
+        let Some(oldest) = self.samples.pop_front() else {
+            return;
+        };
+        let n = self.samples.len();
+        if n == 0 {
+            self.mean = 0.0;
+            self.m2 = 0.0;
+            return;
+        }
+        let old_mean = self.mean;
+        self.mean = (old_mean * (n + 1) as f64 - oldest) / n as f64;
+        // Cancellation can leave a tiny negative remainder
+        self.m2 = (self.m2 - (oldest - old_mean) * (oldest - self.mean)).max(0.0);
+    }
+
+    // Rebuild mean and m2 from the buffered samples
+    fn recompute(&mut self) {
+        self.mean = 0.0;
+        self.m2 = 0.0;
+        for (i, &value) in self.samples.iter().enumerate() {
+            let delta = value - self.mean;
+            self.mean += delta / (i + 1) as f64;
+            self.m2 += delta * (value - self.mean);
+        }
+        self.updates_since_recompute = 0;
+    }
+}
+
+impl StatsBackend for MovingWindow {
+    fn from_window_size(window_size: usize) -> Self {
+        MovingWindow::new(window_size)
+    }
+
+    fn update(&mut self, value: f64) {
+        if self.samples.len() == self.capacity {
+            self.evict_oldest();
+        }
+        self.samples.push_back(value);
+
+        // Welford insert
+        let n = self.samples.len() as f64;
+        let delta = value - self.mean;
+        self.mean += delta / n;
+        self.m2 += delta * (value - self.mean);
+
+        self.updates_since_recompute += 1;
+        if self.updates_since_recompute >= STATS_RECOMPUTE_INTERVAL {
+            self.recompute();
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    // Sample standard deviation over the window
+    fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    // Smallest sample in the window
+    fn min(&self) -> f64 {
+        self.samples.iter().copied().fold(f64::INFINITY, f64::min)
+    }
+
+    // Largest sample in the window
+    fn max(&self) -> f64 {
+        self.samples.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    // Median and median absolute deviation over the window
+    fn median_and_mad(&self) -> (f64, f64) {
+        let mut values: Vec<f64> = self.samples.iter().copied().collect();
+        let center = median(&mut values);
+        for value in values.iter_mut() {
+            *value = (*value - center).abs();
+        }
+        (center, median(&mut values))
+    }
+
+    // Drops the oldest samples if the window shrinks
+    fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.samples.len() > self.capacity {
+            self.evict_oldest();
+        }
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+// Welford statistics over every sample since the last reset. Ignores the window
+// size, so it suits sensors with a stable baseline.
+pub struct CumulativeStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl StatsBackend for CumulativeStats {
+    fn from_window_size(_window_size: usize) -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn update(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+
+    fn len(&self) -> usize {
+        self.count
+    }
+
+    fn min(&self) -> f64 {
+        self.min
+    }
+
+    fn max(&self) -> f64 {
+        self.max
+    }
+
+    fn resize(&mut self, _window_size: usize) {}
+}
+
+// Exponentially weighted mean and variance, alpha = 2 / (window_size + 1), so
+// recent samples dominate without buffering any of them. Min and max are over
+// every sample since the last reset.
+pub struct EwmaStats {
+    alpha: f64,
+    count: usize,
+    mean: f64,
+    variance: f64,
+    min: f64,
+    max: f64,
+}
+
+impl EwmaStats {
+    fn alpha(window_size: usize) -> f64 {
+        2.0 / (window_size.max(1) + 1) as f64
+    }
+}
+
+impl StatsBackend for EwmaStats {
+    fn from_window_size(window_size: usize) -> Self {
+        Self {
+            alpha: EwmaStats::alpha(window_size),
+            count: 0,
+            mean: 0.0,
+            variance: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn update(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        if self.count == 1 {
+            self.mean = value;
+            return;
+        }
+        let delta = value - self.mean;
+        let increment = self.alpha * delta;
+        self.mean += increment;
+        self.variance = (1.0 - self.alpha) * (self.variance + delta * increment);
+    }
+
+    fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    fn len(&self) -> usize {
+        self.count
+    }
+
+    fn min(&self) -> f64 {
+        self.min
+    }
+
+    fn max(&self) -> f64 {
+        self.max
+    }
+
+    fn resize(&mut self, window_size: usize) {
+        self.alpha = EwmaStats::alpha(window_size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mean and sample standard deviation computed from scratch
+    fn exact(values: &[f64]) -> (f64, f64) {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        (mean, variance.sqrt())
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    // Deterministic but irregular samples
+    fn samples(count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|i| 100.0 + ((i * 37) % 101) as f64 * 0.73 - (i % 7) as f64 * 3.1)
+            .collect()
+    }
+
+    #[test]
+    fn moving_window_matches_recomputed_window() {
+        let values = samples(500);
+        let mut window = MovingWindow::new(16);
+        for (i, &value) in values.iter().enumerate() {
+            window.update(value);
+
+            let recent = &values[(i + 1).saturating_sub(16)..=i];
+            assert_eq!(window.len(), recent.len());
+            if recent.len() >= 2 {
+                let (mean, std_dev) = exact(recent);
+                assert_close(window.mean(), mean);
+                assert_close(window.std_dev(), std_dev);
+            }
+        }
+    }
+
+    #[test]
+    fn moving_window_stays_exact_past_the_recompute_interval() {
+        let values = samples(3 * STATS_RECOMPUTE_INTERVAL + 5);
+        let mut window = MovingWindow::new(8);
+        for &value in &values {
+            window.update(value);
+        }
+
+        let (mean, std_dev) = exact(&values[values.len() - 8..]);
+        assert_close(window.mean(), mean);
+        assert_close(window.std_dev(), std_dev);
+    }
+
+    #[test]
+    fn shrinking_the_window_drops_the_oldest_samples() {
+        let values = samples(20);
+        let mut window = MovingWindow::new(10);
+        for &value in &values {
+            window.update(value);
+        }
+
+        window.resize(4);
+        let (mean, std_dev) = exact(&values[16..]);
+        assert_eq!(window.len(), 4);
+        assert_close(window.mean(), mean);
+        assert_close(window.std_dev(), std_dev);
+        assert_eq!(
+            window.min(),
+            values[16..].iter().copied().fold(f64::INFINITY, f64::min)
+        );
+
+        // Further samples keep evicting at the new size
+        window.update(1.0);
+        let mut recent = values[17..].to_vec();
+        recent.push(1.0);
+        assert_close(window.mean(), exact(&recent).0);
+    }
+
+    #[test]
+    fn median_and_mad_over_the_window() {
+        let mut window = MovingWindow::new(5);
+        for value in [50.0, 1.0, 2.0, 3.0, 4.0, 100.0] {
+            window.update(value);
+        }
+        // 1, 2, 3, 4, 100: median 3, deviations 2, 1, 0, 1, 97
+        assert_eq!(window.median_and_mad(), (3.0, 1.0));
+    }
+
+    #[test]
+    fn cumulative_stats_cover_every_sample() {
+        let values = samples(300);
+        let mut stats = CumulativeStats::from_window_size(4);
+        for &value in &values {
+            stats.update(value);
+        }
+
+        let (mean, std_dev) = exact(&values);
+        assert_eq!(stats.len(), values.len());
+        assert_close(stats.mean(), mean);
+        assert_close(stats.std_dev(), std_dev);
+    }
+
+    #[test]
+    fn ewma_follows_a_step() {
+        let mut stats = EwmaStats::from_window_size(9); // alpha = 0.2
+        stats.update(10.0);
+        assert_eq!((stats.mean(), stats.std_dev()), (10.0, 0.0));
+
+        stats.update(20.0);
+        assert_close(stats.mean(), 12.0);
+        // (1 - alpha) * alpha * delta^2
+        assert_close(stats.std_dev(), 16.0_f64.sqrt());
+    }
+}
@@ -1,25 +1,212 @@
 use crate::common::data_types::{
     ActuatorCommand, ActuatorFeedback, PerformanceMetrics, SensorData,
 };
-use serde_json;
-use std::error::Error;
+use crate::common::metrics::MetricsSender;
+use crate::common::wire_format::WireError;
+use crate::sensor::transport::{ChannelTransport, SharedMemTransport, TcpTransport, Transport};
+use log::{debug, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// Failures the transmitter can report to its callers
+#[derive(Debug, Error)]
+pub enum TransmitterError {
+    #[error("Not connected to actuator system")]
+    NotConnected,
+    #[error("Timed out connecting to {endpoint} after {timeout_ms}ms")]
+    ConnectTimeout { endpoint: String, timeout_ms: u64 },
+    #[error("Shared memory name not configured")]
+    SharedMemoryNameMissing,
+    #[error("TCP connection not available")]
+    ConnectionUnavailable,
+    #[error("Connection closed by actuator system")]
+    ConnectionClosed,
+    #[error("Feedback not implemented for CrossbeamChannel")]
+    FeedbackUnsupported,
+    #[error("Actuator channel closed")]
+    ChannelClosed,
+    #[error("Failed to serialize data: {0}")]
+    SerializationFailed(#[from] WireError),
+    #[error("Failed to deserialize feedback: {0}")]
+    DeserializationFailed(#[source] WireError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl TransmitterError {
+    // Short, stable name for the failure, recorded as the metrics error_kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TransmitterError::NotConnected => "not_connected",
+            TransmitterError::ConnectTimeout { .. } => "connect_timeout",
+            TransmitterError::SharedMemoryNameMissing => "shared_memory_name_missing",
+            TransmitterError::ConnectionUnavailable => "connection_unavailable",
+            TransmitterError::ConnectionClosed => "connection_closed",
+            TransmitterError::FeedbackUnsupported => "feedback_unsupported",
+            TransmitterError::ChannelClosed => "channel_closed",
+            TransmitterError::SerializationFailed(_) => "serialization_failed",
+            TransmitterError::DeserializationFailed(_) => "deserialization_failed",
+            TransmitterError::Io(_) => "io",
+        }
+    }
+
+    // Whether the connection is gone, so the transport has to reconnect before
+    // anything else can be sent
+    pub fn is_connection_lost(&self) -> bool {
+        matches!(
+            self,
+            TransmitterError::NotConnected
+                | TransmitterError::ConnectTimeout { .. }
+                | TransmitterError::ConnectionUnavailable
+                | TransmitterError::ConnectionClosed
+                | TransmitterError::Io(_)
+        )
+    }
+}
+
+// Wait before the first retry of a send; doubles for each retry after that
+const RETRY_BACKOFF_INITIAL: Duration = Duration::from_millis(100);
+
+// Longest wait between two attempts at a send
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(2);
+
+// How long to wait after failed attempt number `attempt` (from 1)
+fn retry_backoff(attempt: usize) -> Duration {
+    let doublings = attempt.saturating_sub(1).min(16) as u32;
+    (RETRY_BACKOFF_INITIAL * 2u32.pow(doublings)).min(RETRY_BACKOFF_MAX)
+}
+
+// Used when no connect timeout is configured
+pub const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 5000;
+
+// Used when no feedback timeout is configured
+pub const DEFAULT_FEEDBACK_TIMEOUT_MS: u64 = 50;
+
+// Transmissions still waiting for actuator feedback, keyed by the command id
+// the feedback echoes back
+pub struct PendingFeedback {
+    timeout: Duration,
+    pending: HashMap<String, Instant>, // Sent at, per command id
+    unknown: u64,                      // Feedback for ids that weren't pending
+}
+
+impl PendingFeedback {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            pending: HashMap::new(),
+            unknown: 0,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    // Start waiting for feedback on `id`, sent at `sent_at`
+    pub fn register(&mut self, id: String, sent_at: Instant) {
+        self.pending.insert(id, sent_at);
+    }
+
+    // Feedback for `id` arrived; returns when it was sent, or None (and counts
+    // it) if nothing was waiting on that id, e.g. it had already expired
+    pub fn acknowledge(&mut self, id: &str) -> Option<Instant> {
+        let sent_at = self.pending.remove(id);
+        if sent_at.is_none() {
+            self.unknown += 1;
+        }
+        sent_at
+    }
+
+    pub fn unknown(&self) -> u64 {
+        self.unknown
+    }
+
+    // Remove and return every entry that has waited longer than the timeout, oldest first
+    pub fn expire(&mut self, now: Instant) -> Vec<(String, Instant)> {
+        let timeout = self.timeout;
+        let mut expired = Vec::new();
+        self.pending.retain(|id, sent_at| {
+            let waiting = now.saturating_duration_since(*sent_at) <= timeout;
+            if !waiting {
+                expired.push((id.clone(), *sent_at));
+            }
+            waiting
+        });
+        expired.sort_by_key(|(_, sent_at)| *sent_at);
+        expired
+    }
+}
+
+// Readings waiting in the retry queue, shared with anything that wants to read it at runtime
+pub type SharedQueueDepth = Arc<AtomicUsize>;
+
+// Readings that failed every send attempt, held for another try once sending
+// works again. Oldest first; when full, the oldest reading is dropped.
+pub struct RetryQueue {
+    readings: VecDeque<SensorData>,
+    capacity: usize,
+    depth: SharedQueueDepth,
+}
+
+impl RetryQueue {
+    pub fn new(capacity: usize, depth: SharedQueueDepth) -> Self {
+        let capacity = capacity.max(1);
+        depth.store(0, Ordering::Relaxed);
+        Self {
+            readings: VecDeque::with_capacity(capacity),
+            capacity,
+            depth,
+        }
+    }
+
+    // Queue `data` at the back, returning the reading dropped to make room, if any
+    pub fn push(&mut self, data: SensorData) -> Option<SensorData> {
+        let dropped = if self.readings.len() == self.capacity {
+            self.readings.pop_front()
+        } else {
+            None
+        };
+        self.readings.push_back(data);
+        self.publish_depth();
+        dropped
+    }
+
+    pub fn pop_front(&mut self) -> Option<SensorData> {
+        let data = self.readings.pop_front();
+        self.publish_depth();
+        data
+    }
+
+    // Put back a reading that was just popped and failed again
+    pub fn push_front(&mut self, data: SensorData) {
+        self.readings.push_front(data);
+        self.publish_depth();
+    }
+
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    fn publish_depth(&self) {
+        self.depth.store(self.readings.len(), Ordering::Relaxed);
+    }
+}
 
 // Transmitter for sending data to the actuator system
 pub struct DataTransmitter {
-    // Connection options
-    connection_type: ConnectionType,
-    // Connection details (for TCP/UDP)
-    endpoint: Option<String>,
-    // Shared memory name (for shared memory)
-    shared_mem_name: Option<String>,
+    // How readings reach the actuator system
+    transport: Box<dyn Transport>,
     // Connected status
     connected: bool,
-    // TCP connection if using TCP
-    tcp_connection: Option<Arc<Mutex<TcpStream>>>,
 }
 
 // Communication methods supported
@@ -29,199 +216,129 @@ pub enum ConnectionType {
     CrossbeamChannel,
 }
 
+impl ConnectionType {
+    pub const ALL: [ConnectionType; 3] = [
+        ConnectionType::TcpSocket,
+        ConnectionType::SharedMemory,
+        ConnectionType::CrossbeamChannel,
+    ];
+
+    // Name used for `connection_type` in the config and `--mode` on the CLI
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConnectionType::TcpSocket => "tcp",
+            ConnectionType::SharedMemory => "shared_memory",
+            ConnectionType::CrossbeamChannel => "channel",
+        }
+    }
+}
+
 impl DataTransmitter {
-    pub fn new(connection_type: ConnectionType) -> Self {
+    pub fn new(transport: Box<dyn Transport>) -> Self {
         Self {
-            connection_type,
-            endpoint: None,
-            shared_mem_name: None,
+            transport,
             connected: false,
-            tcp_connection: None,
         }
     }
 
-    // Configure TCP connection
-    pub fn with_tcp_endpoint(mut self, endpoint: &str) -> Self {
-        self.endpoint = Some(endpoint.to_string());
-        self
+    // Connect to the actuator system
+    pub async fn connect(&mut self) -> Result<(), TransmitterError> {
+        self.transport.connect().await?;
+        self.connected = true;
+        Ok(())
     }
 
-    // Configure shared memory connection
-    pub fn with_shared_memory(mut self, name: &str) -> Self {
-        self.shared_mem_name = Some(name.to_string());
-        self
+    pub fn is_connected(&self) -> bool {
+        self.connected
     }
 
-    // Connect to the actuator system
-    pub async fn connect(&mut self) -> Result<(), Box<dyn Error>> {
-        match self.connection_type {
-            ConnectionType::TcpSocket => {
-                if let Some(endpoint) = &self.endpoint {
-                    let stream = TcpStream::connect(endpoint).await?;
-                    self.tcp_connection = Some(Arc::new(Mutex::new(stream)));
-                    self.connected = true;
-                } else {
-                    return Err("TCP endpoint not configured".into());
-                }
-            }
-            ConnectionType::SharedMemory => {
-                // This would use a shared memory crate in a real implementation
-                // For simulation purposes, we'll just mark as connected
-                if self.shared_mem_name.is_some() {
-                    self.connected = true;
-                } else {
-                    return Err("Shared memory name not configured".into());
-                }
-            }
-            ConnectionType::CrossbeamChannel => {
-                // For testing with crossbeam channels, always consider connected
-                self.connected = true;
-            }
-        }
-
-        Ok(())
+    // Forget a broken connection, so the next send reconnects first
+    pub fn disconnect(&mut self) {
+        self.connected = false;
     }
 
     // Send data to the actuator system
     pub async fn send_data(
         &self,
         data: &SensorData,
-    ) -> Result<PerformanceMetrics, Box<dyn Error + Send + Sync + 'static>> {
+    ) -> Result<PerformanceMetrics, TransmitterError> {
         let mut metrics = PerformanceMetrics::new("data_transmission");
 
         if !self.connected {
-            metrics.complete(false);
-            return Err("Not connected to actuator system".into());
+            let error = TransmitterError::NotConnected;
+            metrics.complete_with_error(error.kind());
+            return Err(error);
         }
 
-        // Serialize the data
-        let serialized = serde_json::to_string(data)?;
-
-        match self.connection_type {
-            ConnectionType::TcpSocket => {
-                if let Some(conn) = &self.tcp_connection {
-                    let mut stream = conn.lock().await;
-                    stream.write_all(serialized.as_bytes()).await?;
-                    // Add newline as delimiter
-                    stream.write_all(b"\n").await?;
-                }
-            }
-            ConnectionType::SharedMemory => {
-                // In a real implementation, this would write to shared memory
-                // For simulation, we'll just simulate the time it takes
-                tokio::time::sleep(tokio::time::Duration::from_micros(100)).await;
-            }
-            ConnectionType::CrossbeamChannel => {
-                // If we're using a crossbeam channel for direct in-process communication
-                // This would send through the channel (implementation in run_transmitter)
-            }
-        }
+        self.transport.send(data).await?;
 
         metrics.complete(true);
         Ok(metrics)
     }
 
     // Receive feedback from the actuator system
-    pub async fn receive_feedback(&self) -> Result<ActuatorFeedback, Box<dyn Error>> {
+    pub async fn receive_feedback(&self) -> Result<ActuatorFeedback, TransmitterError> {
         if !self.connected {
-            return Err("Not connected to actuator system".into());
+            return Err(TransmitterError::NotConnected);
         }
+        self.transport.recv_feedback().await
+    }
 
-        match self.connection_type {
-            ConnectionType::TcpSocket => {
-                if let Some(conn) = &self.tcp_connection {
-                    let mut stream = conn.lock().await;
-                    let mut buffer = Vec::new();
-                    let mut temp_buf = [0u8; 1024];
-
-                    // Read until newline
-                    let mut found_newline = false;
-                    while !found_newline {
-                        let n = stream.read(&mut temp_buf).await?;
-                        if n == 0 {
-                            break;
-                        }
-
-                        for i in 0..n {
-                            if temp_buf[i] == b'\n' {
-                                buffer.extend_from_slice(&temp_buf[0..i]);
-                                found_newline = true;
-                                break;
-                            }
-                        }
-
-                        if !found_newline {
-                            buffer.extend_from_slice(&temp_buf[0..n]);
-                        }
-                    }
-
-                    // Deserialize the feedback
-                    let feedback: ActuatorFeedback = serde_json::from_slice(&buffer)?;
-                    return Ok(feedback);
-                }
-                Err("TCP connection not available".into())
-            }
-            ConnectionType::SharedMemory => {
-                // In a real implementation, this would read from shared memory
-                // For simulation, just return a dummy feedback
-                let feedback = ActuatorFeedback {
-                    timestamp: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis(),
-                    actuator_id: "sim_actuator".to_string(),
-                    status: crate::common::data_types::ActuatorStatus::Normal,
-                    message: Some("Simulation feedback".to_string()),
-                };
-                Ok(feedback)
-            }
-            ConnectionType::CrossbeamChannel => {
-                // This would be handled in run_transmitter
-                Err("Feedback not implemented for CrossbeamChannel".into())
-            }
-        }
+    // Whether the transport ever delivers feedback
+    pub fn has_feedback(&self) -> bool {
+        self.transport.has_feedback()
     }
 }
 
+// Write `data` to `out` as a single JSON line. The line goes out in one write so
+// concurrent writers to stdout can't split it.
+pub fn write_jsonl(out: &mut impl Write, data: &SensorData) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(data)?;
+    line.push(b'\n');
+    out.write_all(&line)?;
+    out.flush()
+}
+
 // Function to run the transmitter in real-time
 pub async fn run_transmitter(
     config: &crate::config::TransmitterConfig,
     rx: crossbeam_channel::Receiver<SensorData>,
     actuator_tx: Option<crossbeam_channel::Sender<ActuatorCommand>>,
-    metrics_tx: crossbeam_channel::Sender<PerformanceMetrics>,
+    metrics_tx: MetricsSender,
     feedback_tx: Option<crossbeam_channel::Sender<ActuatorFeedback>>,
+    retry_queue_depth: SharedQueueDepth,
 ) {
     // Create and configure transmitter
-    let transmitter = match config.connection_type.as_str() {
-        "tcp" => {
-            let mut tx =
-                DataTransmitter::new(ConnectionType::TcpSocket).with_tcp_endpoint(&config.endpoint);
-
-            // Try to connect
-            if let Err(e) = tx.connect().await {
-                println!("Failed to connect transmitter: {}", e);
-                return;
-            }
-            tx
-        }
-        "shared_memory" => {
-            let mut tx = DataTransmitter::new(ConnectionType::SharedMemory)
-                .with_shared_memory(&config.shared_mem_name);
-
-            // Try to connect
-            if let Err(e) = tx.connect().await {
-                println!("Failed to connect transmitter: {}", e);
-                return;
-            }
-            tx
-        }
-        "channel" => DataTransmitter::new(ConnectionType::CrossbeamChannel),
+    let transport: Box<dyn Transport> = match config.connection_type.as_str() {
+        "tcp" => Box::new(
+            TcpTransport::new(config.endpoint.clone(), config.serialization_format)
+                .with_connect_timeout(Duration::from_millis(config.connect_timeout_ms)),
+        ),
+        "shared_memory" => Box::new(SharedMemTransport::new(&config.shared_mem_name)),
+        "channel" => Box::new(ChannelTransport::new(actuator_tx)),
         _ => {
-            println!("Unknown connection type: {}", config.connection_type);
+            warn!("Unknown connection type: {}", config.connection_type);
             return;
         }
     };
 
+    let mut transmitter = DataTransmitter::new(transport);
+    if let Err(e) = transmitter.connect().await {
+        if !e.is_connection_lost() {
+            warn!("Failed to connect transmitter: {}", e);
+            return;
+        }
+        warn!(
+            "Failed to connect transmitter, retrying on the next send: {}",
+            e
+        );
+    }
+
+    let mut pending_feedback =
+        PendingFeedback::new(Duration::from_millis(config.feedback_timeout_ms));
+    let mut stdout = config.stdout_jsonl.then(std::io::stdout);
+    let mut retry_queue = RetryQueue::new(config.retry_queue_capacity, retry_queue_depth);
+
     // Process and transmit data in real time
     loop {
         // Try to receive processed data
@@ -229,83 +346,553 @@ pub async fn run_transmitter(
             Ok(data) => {
                 let start = std::time::Instant::now();
 
-                if let ConnectionType::CrossbeamChannel = transmitter.connection_type {
-                    if let Some(tx) = &actuator_tx {
-                        let command = ActuatorCommand::from_sensor_data(&data); // You need to implement this conversion
-                        if tx.send(command).is_err() {
-                            println!("Actuator channel closed, stopping transmitter.");
-                            break;
-                        }
+                if let Some(out) = stdout.as_mut() {
+                    if let Err(e) = write_jsonl(out, &data) {
+                        warn!(
+                            "Failed to write reading to stdout, disabling JSONL output: {}",
+                            e
+                        );
+                        stdout = None;
                     }
+                }
+
+                // One initial attempt plus up to retry_attempts retries
+                let max_attempts = config.retry_attempts + 1;
+                let mut attempts = 0;
+                let mut last_error = None;
+                let mut final_metrics = PerformanceMetrics::new("data_transmission");
 
-                    // Record metrics
-                    let mut metrics = PerformanceMetrics::new("data_transmission");
-                    metrics.complete(true);
-                    let _ = metrics_tx.send(metrics);
-                } else {
-                    // For other connection types, use the transmitter
-                    let mut attempts = 0;
-                    let max_attempts = 3;
-                    let mut success = false;
-                    let mut final_metrics = PerformanceMetrics::new("data_transmission");
-
-                    while attempts < max_attempts {
-                        match transmitter.send_data(&data).await {
-                            Ok(metrics) => {
-                                final_metrics = metrics;
-                                final_metrics.complete(true);
-                                success = true;
-                                break;
+                while attempts < max_attempts {
+                    attempts += 1;
+                    let reconnected =
+                        reconnect_if_needed(&mut transmitter, &mut retry_queue, &metrics_tx).await;
+                    let sent = match reconnected {
+                        Ok(()) => transmitter.send_data(&data).await,
+                        Err(e) => Err(e),
+                    };
+                    match sent {
+                        Ok(metrics) => {
+                            final_metrics = metrics;
+                            final_metrics.complete(true);
+                            last_error = None;
+                            break;
+                        }
+                        Err(TransmitterError::ChannelClosed) => {
+                            info!("Actuator channel closed, stopping transmitter.");
+                            return;
+                        }
+                        Err(e) => {
+                            // Convert error to String immediately for Send safety
+                            let err_msg = e.to_string();
+                            warn!(
+                                "Attempt {}/{}: Failed to send data: {}",
+                                attempts, max_attempts, err_msg
+                            );
+                            last_error = Some(e.kind());
+                            if e.is_connection_lost() {
+                                transmitter.disconnect();
                             }
-                            Err(e) => {
-                                // Convert error to String immediately for Send safety
-                                let err_msg = e.to_string();
-                                attempts += 1;
-                                println!(
-                                    "Attempt {}/{}: Failed to send data: {}",
-                                    attempts, max_attempts, err_msg
-                                );
-                                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                            if attempts < max_attempts {
+                                tokio::time::sleep(retry_backoff(attempts)).await;
                             }
                         }
                     }
+                }
+
+                if let Some(error_kind) = last_error {
+                    final_metrics.complete_with_error(error_kind);
+                }
+                final_metrics.attempts = attempts;
+                metrics_tx.send(final_metrics);
 
-                    if !success {
-                        final_metrics.complete(false);
+                // Hold on to a failed reading, or catch up on held ones now sending works
+                if last_error.is_some() && config.retry_queue_capacity > 0 {
+                    if let Some(dropped) = retry_queue.push(data.clone()) {
+                        warn!(
+                            "Retry queue full, dropping reading {}@{}",
+                            dropped.sensor_id, dropped.timestamp
+                        );
+                        let mut metrics = PerformanceMetrics::new("retry_queue_drop");
+                        metrics.complete_with_error("queue_full");
+                        metrics_tx.send(metrics);
+                    }
+                    debug!("Queued reading for retry, {} waiting.", retry_queue.len());
+                } else if last_error.is_none() && !retry_queue.is_empty() {
+                    match drain_retry_queue(&transmitter, &mut retry_queue, &metrics_tx).await {
+                        Err(TransmitterError::ChannelClosed) => {
+                            info!("Actuator channel closed, stopping transmitter.");
+                            return;
+                        }
+                        Err(e) => warn!(
+                            "Retransmission failed, {} readings still queued: {}",
+                            retry_queue.len(),
+                            e
+                        ),
+                        Ok(()) => info!("Retry queue drained."),
                     }
-                    let _ = metrics_tx.send(final_metrics);
                 }
 
-                // Check if transmission took too long
+                // Slow sends are counted against the transmission deadline in the
+                // metrics report; over TCP most take longer than 1ms, so only debug
                 let transmission_time = start.elapsed();
                 if transmission_time.as_millis() > 1 {
-                    println!(
-                        "Warning: Transmission took too long: {:?}",
-                        transmission_time
-                    );
+                    debug!("Transmission took too long: {:?}", transmission_time);
                 }
 
-                // Try to receive feedback
-                if let ConnectionType::CrossbeamChannel = transmitter.connection_type {
-                    // Feedback would come through a separate channel
-                    // No implementation for now
-                } else if let Some(tx) = &feedback_tx {
-                    match transmitter.receive_feedback().await {
-                        Ok(feedback) => {
+                // Try to receive feedback, if the transport can deliver any
+                if let Some(tx) = feedback_tx.as_ref().filter(|_| transmitter.has_feedback()) {
+                    // Only numbered readings get a command id to match feedback against
+                    if let Some(id) = data.reading_id() {
+                        pending_feedback.register(id, start);
+                    }
+
+                    let feedback = tokio::time::timeout(
+                        pending_feedback.timeout(),
+                        transmitter.receive_feedback(),
+                    )
+                    .await;
+                    match feedback {
+                        Ok(Ok(feedback)) => {
+                            if let Some(id) = &feedback.command_id {
+                                if pending_feedback.acknowledge(id).is_none() {
+                                    debug!("Feedback for unknown command {}", id);
+                                }
+                            }
                             if tx.send(feedback).is_err() {
-                                println!("Feedback channel closed.");
+                                warn!("Feedback channel closed.");
                             }
                         }
-                        Err(_) => {
+                        Ok(Err(_)) | Err(_) => {
                             // No feedback available or error
                         }
                     }
+
+                    for (id, sent_at) in pending_feedback.expire(Instant::now()) {
+                        warn!(
+                            "No feedback for {} within {:?}",
+                            id,
+                            pending_feedback.timeout()
+                        );
+                        let mut metrics = PerformanceMetrics::new("feedback_timeout");
+                        metrics.start_time = sent_at;
+                        metrics.complete_with_error("timeout");
+                        metrics_tx.send(metrics);
+                    }
                 }
             }
             Err(_) => {
-                println!("Processor channel closed, stopping transmitter.");
+                info!("Processor channel closed, stopping transmitter.");
                 break;
             }
         }
     }
+
+    if !retry_queue.is_empty() {
+        warn!("{} readings left unsent in the retry queue.", retry_queue.len());
+    }
+    if pending_feedback.unknown() > 0 {
+        info!(
+            "{} feedback messages named commands that weren't pending.",
+            pending_feedback.unknown()
+        );
+    }
+}
+
+// Re-establish a connection dropped by an earlier failure, then resend what
+// queued up while it was down so it still goes out ahead of newer readings
+async fn reconnect_if_needed(
+    transmitter: &mut DataTransmitter,
+    retry_queue: &mut RetryQueue,
+    metrics_tx: &MetricsSender,
+) -> Result<(), TransmitterError> {
+    if transmitter.is_connected() {
+        return Ok(());
+    }
+    transmitter.connect().await?;
+    info!("Transmitter reconnected.");
+    if !retry_queue.is_empty() {
+        drain_retry_queue(transmitter, retry_queue, metrics_tx).await?;
+        info!("Retry queue drained.");
+    }
+    Ok(())
+}
+
+// Resend queued readings oldest first, stopping at the first failure with the
+// failed reading back at the head of the queue
+async fn drain_retry_queue(
+    transmitter: &DataTransmitter,
+    retry_queue: &mut RetryQueue,
+    metrics_tx: &MetricsSender,
+) -> Result<(), TransmitterError> {
+    while let Some(data) = retry_queue.pop_front() {
+        match transmitter.send_data(&data).await {
+            Ok(mut metrics) => {
+                metrics.operation = "retransmission".to_string();
+                metrics_tx.send(metrics);
+            }
+            Err(e) => {
+                retry_queue.push_front(data);
+                let mut metrics = PerformanceMetrics::new("retransmission");
+                metrics.complete_with_error(e.kind());
+                metrics_tx.send(metrics);
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data_types::{SensorId, SensorType};
+    use crate::common::metrics::{metrics_channel, MetricsReceiver};
+    use crate::common::wire_format::SerializationFormat;
+    use crate::config::{Config, Endpoint, TransmitterConfig};
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+
+    fn reading(seq: u64) -> SensorData {
+        SensorData {
+            timestamp: 1_000 + seq as u128,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Force,
+            value: seq as f64,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        }
+    }
+
+    fn tcp_config(
+        port: u16,
+        retry_attempts: usize,
+        retry_queue_capacity: usize,
+    ) -> TransmitterConfig {
+        let mut config = Config::default().transmitter;
+        config.connection_type = "tcp".to_string();
+        config.endpoint = Endpoint::new("127.0.0.1", port);
+        config.connect_timeout_ms = 500;
+        config.retry_attempts = retry_attempts;
+        config.retry_queue_capacity = retry_queue_capacity;
+        config
+    }
+
+    // A port nothing is listening on
+    fn closed_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    // Wait for the next metrics record for `operation`, skipping any others
+    fn next_metrics(metrics_rx: &MetricsReceiver, operation: &str) -> PerformanceMetrics {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            match metrics_rx.try_recv() {
+                Ok(metrics) if metrics.operation == operation => return metrics,
+                Ok(_) => {}
+                Err(_) => std::thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        panic!("no {} metrics", operation);
+    }
+
+    // Read the next reading the transmitter sent over `stream`
+    fn read_reading(stream: &mut TcpStream, buffer: &mut Vec<u8>) -> SensorData {
+        let format = SerializationFormat::Json;
+        loop {
+            if let Some(frame) = format.take_frame(buffer) {
+                return format.decode(&frame).unwrap();
+            }
+            let mut chunk = [0u8; 1024];
+            let n = stream.read(&mut chunk).unwrap();
+            assert!(n > 0, "transmitter closed the connection");
+            buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    // run_transmitter on its own runtime, fed through the returned sender
+    struct Running {
+        tx: crossbeam_channel::Sender<SensorData>, // Dropped first, so the transmitter stops
+        runtime: tokio::runtime::Runtime,
+        handle: tokio::task::JoinHandle<()>,
+        metrics_rx: MetricsReceiver,
+        queue_depth: SharedQueueDepth,
+    }
+
+    fn start(config: TransmitterConfig) -> Running {
+        start_with_feedback(config, None)
+    }
+
+    fn start_with_feedback(
+        config: TransmitterConfig,
+        feedback_tx: Option<crossbeam_channel::Sender<ActuatorFeedback>>,
+    ) -> Running {
+        // run_transmitter blocks its worker on the input channel, so another
+        // one is needed to drive the connection's I/O
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let (metrics_tx, metrics_rx) = metrics_channel(256);
+        let queue_depth = SharedQueueDepth::default();
+        let depth = Arc::clone(&queue_depth);
+        let handle = runtime.spawn(async move {
+            run_transmitter(&config, rx, None, metrics_tx, feedback_tx, depth).await;
+        });
+        Running {
+            tx,
+            runtime,
+            handle,
+            metrics_rx,
+            queue_depth,
+        }
+    }
+
+    impl Running {
+        // Wait until `depth` readings are held in the retry queue
+        fn wait_for_queue_depth(&self, depth: usize) {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while self.queue_depth.load(Ordering::Relaxed) != depth {
+                assert!(
+                    Instant::now() < deadline,
+                    "retry queue never reached {}",
+                    depth
+                );
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+
+        fn stop(self) {
+            drop(self.tx);
+            self.runtime.block_on(self.handle).unwrap();
+        }
+    }
+
+    #[test]
+    fn retry_queue_keeps_oldest_first_and_drops_oldest_when_full() {
+        let depth = SharedQueueDepth::default();
+        let mut queue = RetryQueue::new(3, Arc::clone(&depth));
+        for seq in 1..=3 {
+            assert!(queue.push(reading(seq)).is_none());
+        }
+        assert_eq!(queue.push(reading(4)).map(|r| r.seq), Some(1));
+        assert_eq!(depth.load(Ordering::Relaxed), 3);
+
+        // A reading that fails again goes back to the head
+        let head = queue.pop_front().unwrap();
+        assert_eq!(head.seq, 2);
+        queue.push_front(head);
+
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop_front())
+            .map(|r| r.seq)
+            .collect();
+        assert_eq!(order, [2, 3, 4]);
+        assert_eq!(depth.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn feedback_acknowledges_the_command_it_names() {
+        let sent = Instant::now();
+        let mut pending = PendingFeedback::new(Duration::from_millis(50));
+        pending.register("force_1#1".to_string(), sent);
+        pending.register("force_1#2".to_string(), sent + Duration::from_millis(10));
+
+        // Feedback for the newer command leaves the older one waiting
+        assert!(pending.acknowledge("force_1#2").is_some());
+        assert!(pending.acknowledge("force_1#9").is_none());
+        assert_eq!(pending.unknown(), 1);
+
+        let expired = pending.expire(sent + Duration::from_millis(100));
+        let ids: Vec<_> = expired.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["force_1#1"]);
+        assert!(pending.expire(sent + Duration::from_secs(1)).is_empty());
+    }
+
+    #[test]
+    fn retry_backoff_doubles_up_to_the_cap() {
+        let delays: Vec<_> = (1..=7).map(retry_backoff).collect();
+        let ms: Vec<_> = delays.iter().map(Duration::as_millis).collect();
+        assert_eq!(ms, [100, 200, 400, 800, 1600, 2000, 2000]);
+    }
+
+    #[test]
+    fn failed_send_is_attempted_retry_attempts_plus_one_times() {
+        let running = start(tcp_config(closed_port(), 2, 0));
+        running.tx.send(reading(1)).unwrap();
+
+        let metrics = next_metrics(&running.metrics_rx, "data_transmission");
+        assert_eq!(metrics.attempts, 3);
+        assert!(!metrics.success);
+        assert_eq!(metrics.error_kind.as_deref(), Some("io"));
+        running.stop();
+    }
+
+    #[test]
+    fn sends_reconnect_once_the_actuator_is_reachable() {
+        let port = closed_port();
+        let running = start(tcp_config(port, 1, 0));
+
+        // Nothing listening yet, so both attempts fail to connect
+        running.tx.send(reading(1)).unwrap();
+        let metrics = next_metrics(&running.metrics_rx, "data_transmission");
+        assert_eq!((metrics.attempts, metrics.success), (2, false));
+
+        let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        running.tx.send(reading(2)).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        assert_eq!(read_reading(&mut stream, &mut Vec::new()).seq, 2);
+
+        let metrics = next_metrics(&running.metrics_rx, "data_transmission");
+        assert_eq!((metrics.attempts, metrics.success), (1, true));
+        running.stop();
+    }
+
+    #[test]
+    fn reconnects_and_resends_queued_readings_in_order_after_listener_restarts() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let running = start(tcp_config(port, 1, 8));
+
+        running.tx.send(reading(1)).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        assert_eq!(read_reading(&mut stream, &mut Vec::new()).seq, 1);
+
+        // The actuator goes away; readings sent meanwhile are queued
+        drop(stream);
+        drop(listener);
+        std::thread::sleep(Duration::from_millis(100));
+        running.tx.send(reading(2)).unwrap();
+        running.wait_for_queue_depth(1);
+        running.tx.send(reading(3)).unwrap();
+        running.wait_for_queue_depth(2);
+
+        // Once it's back, the queue goes out ahead of the new reading
+        let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+        running.tx.send(reading(4)).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buffer = Vec::new();
+        let seqs: Vec<_> = (0..3)
+            .map(|_| read_reading(&mut stream, &mut buffer).seq)
+            .collect();
+        assert_eq!(seqs, [2, 3, 4]);
+
+        running.wait_for_queue_depth(0);
+        for _ in 0..2 {
+            assert!(next_metrics(&running.metrics_rx, "retransmission").success);
+        }
+        running.stop();
+    }
+
+    #[test]
+    fn missing_feedback_is_reported_as_a_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (feedback_tx, _feedback_rx) = crossbeam_channel::unbounded();
+        let running = start_with_feedback(tcp_config(port, 0, 0), Some(feedback_tx));
+
+        // The actuator reads the reading but never answers it
+        running.tx.send(reading(1)).unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        assert_eq!(read_reading(&mut stream, &mut Vec::new()).seq, 1);
+
+        let metrics = next_metrics(&running.metrics_rx, "feedback_timeout");
+        assert_eq!(metrics.error_kind.as_deref(), Some("timeout"));
+        running.stop();
+    }
+
+    #[tokio::test]
+    async fn send_data_before_connecting_is_not_connected() {
+        let mut transmitter = DataTransmitter::new(Box::new(ChannelTransport::new(None)));
+
+        let error = transmitter.send_data(&reading(1)).await.unwrap_err();
+        assert!(matches!(error, TransmitterError::NotConnected));
+        assert_eq!(error.kind(), "not_connected");
+        assert!(error.is_connection_lost());
+        assert!(matches!(
+            transmitter.receive_feedback().await,
+            Err(TransmitterError::NotConnected)
+        ));
+
+        transmitter.connect().await.unwrap();
+        assert!(transmitter.send_data(&reading(1)).await.unwrap().success);
+    }
+
+    // Records what it's asked to send, failing once `fail_after` sends have gone out
+    struct MockTransport {
+        sent: Arc<std::sync::Mutex<Vec<u64>>>,
+        fail_after: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for MockTransport {
+        async fn connect(&mut self) -> Result<(), TransmitterError> {
+            Ok(())
+        }
+
+        async fn send(&self, data: &SensorData) -> Result<(), TransmitterError> {
+            let mut sent = self.sent.lock().unwrap();
+            if sent.len() >= self.fail_after {
+                return Err(TransmitterError::ConnectionClosed);
+            }
+            sent.push(data.seq);
+            Ok(())
+        }
+
+        async fn recv_feedback(&self) -> Result<ActuatorFeedback, TransmitterError> {
+            Err(TransmitterError::ConnectionUnavailable)
+        }
+
+        fn has_feedback(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn each_reading_is_sent_once_through_the_transport() {
+        let sent = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let transport = MockTransport {
+            sent: Arc::clone(&sent),
+            fail_after: 3,
+        };
+        let mut transmitter = DataTransmitter::new(Box::new(transport));
+        transmitter.connect().await.unwrap();
+        assert!(!transmitter.has_feedback());
+
+        for seq in 1..=3 {
+            let metrics = transmitter.send_data(&reading(seq)).await.unwrap();
+            assert!(metrics.success);
+        }
+        assert_eq!(*sent.lock().unwrap(), [1, 2, 3]);
+
+        // The transport's error reaches the caller as is
+        let error = transmitter.send_data(&reading(4)).await.unwrap_err();
+        assert!(matches!(error, TransmitterError::ConnectionClosed));
+        assert_eq!(*sent.lock().unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn jsonl_output_is_one_reading_per_line() {
+        let mut out = Vec::new();
+        for seq in 1..=3 {
+            write_jsonl(&mut out, &reading(seq)).unwrap();
+        }
+
+        let readings: Vec<SensorData> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let seqs: Vec<_> = readings.iter().map(|data| data.seq).collect();
+        assert_eq!(seqs, [1, 2, 3]);
+        assert_eq!(readings[2].value, 3.0);
+    }
 }
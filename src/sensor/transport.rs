@@ -0,0 +1,366 @@
+use crate::common::clock::now_millis;
+use crate::common::data_types::{
+    ActuatorCommand, ActuatorFeedback, ActuatorId, ActuatorStatus, SensorData,
+};
+use crate::common::wire_format::SerializationFormat;
+use crate::config::Endpoint;
+use crate::sensor::transmitter::{TransmitterError, DEFAULT_CONNECT_TIMEOUT_MS};
+use async_trait::async_trait;
+use log::warn;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+// A way of getting readings to the actuator system and feedback back from it
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn connect(&mut self) -> Result<(), TransmitterError>;
+
+    async fn send(&self, data: &SensorData) -> Result<(), TransmitterError>;
+
+    async fn recv_feedback(&self) -> Result<ActuatorFeedback, TransmitterError>;
+
+    // False if recv_feedback can never succeed, so callers shouldn't wait on it
+    fn has_feedback(&self) -> bool {
+        true
+    }
+}
+
+// Most unread feedback a TCP connection holds on to; past this the oldest
+// frames are dropped
+const MAX_READ_BUFFER_BYTES: usize = 64 * 1024;
+
+// TCP stream plus any bytes read past the last complete message
+struct TcpConnection {
+    stream: TcpStream,
+    read_buffer: Vec<u8>,
+}
+
+impl TcpConnection {
+    // Buffer whatever the peer has sent without waiting for more, failing if it
+    // has hung up. A write to a closed connection can still succeed locally, so
+    // this is what notices the actuator went away before a reading is lost.
+    fn check_open(&mut self, format: SerializationFormat) -> Result<(), TransmitterError> {
+        let mut temp_buf = [0u8; 1024];
+        loop {
+            match self.stream.try_read(&mut temp_buf) {
+                Ok(0) => return Err(TransmitterError::ConnectionClosed),
+                Ok(n) => {
+                    self.read_buffer.extend_from_slice(&temp_buf[0..n]);
+                    self.trim_read_buffer(format);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    // Drop the oldest buffered frames until the buffer is back under the cap,
+    // e.g. when nothing is reading the actuator's feedback
+    fn trim_read_buffer(&mut self, format: SerializationFormat) {
+        let mut dropped = 0;
+        while self.read_buffer.len() > MAX_READ_BUFFER_BYTES {
+            if format.take_frame(&mut self.read_buffer).is_none() {
+                // A single partial frame over the cap can't be framed any more
+                self.read_buffer.clear();
+            }
+            dropped += 1;
+        }
+        if dropped > 0 {
+            warn!(
+                "Unread feedback over {} bytes, dropped {} frames.",
+                MAX_READ_BUFFER_BYTES, dropped
+            );
+        }
+    }
+}
+
+// Framed messages over a TCP connection to the actuator's server
+pub struct TcpTransport {
+    endpoint: Endpoint,
+    connect_timeout: Duration,
+    format: SerializationFormat,
+    connection: Option<Mutex<TcpConnection>>,
+}
+
+impl TcpTransport {
+    pub fn new(endpoint: Endpoint, format: SerializationFormat) -> Self {
+        Self {
+            endpoint,
+            connect_timeout: Duration::from_millis(DEFAULT_CONNECT_TIMEOUT_MS),
+            format,
+            connection: None,
+        }
+    }
+
+    // Configure how long connect() waits for the endpoint
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl Transport for TcpTransport {
+    async fn connect(&mut self) -> Result<(), TransmitterError> {
+        let connect = TcpStream::connect((self.endpoint.host(), self.endpoint.port()));
+        let stream = tokio::time::timeout(self.connect_timeout, connect)
+            .await
+            .map_err(|_| TransmitterError::ConnectTimeout {
+                endpoint: self.endpoint.to_string(),
+                timeout_ms: self.connect_timeout.as_millis() as u64,
+            })??;
+        self.connection = Some(Mutex::new(TcpConnection {
+            stream,
+            read_buffer: Vec::new(),
+        }));
+        Ok(())
+    }
+
+    async fn send(&self, data: &SensorData) -> Result<(), TransmitterError> {
+        let conn = self
+            .connection
+            .as_ref()
+            .ok_or(TransmitterError::ConnectionUnavailable)?;
+
+        // Serialize and frame the data
+        let frame = self.format.encode_frame(data)?;
+        let mut conn = conn.lock().await;
+        conn.check_open(self.format)?;
+        conn.stream.write_all(&frame).await?;
+        Ok(())
+    }
+
+    async fn recv_feedback(&self) -> Result<ActuatorFeedback, TransmitterError> {
+        let conn = self
+            .connection
+            .as_ref()
+            .ok_or(TransmitterError::ConnectionUnavailable)?;
+        let mut conn = conn.lock().await;
+        let mut temp_buf = [0u8; 1024];
+
+        // Read until a complete frame is buffered, keeping anything after it
+        // for the next call
+        let buffer = loop {
+            if let Some(frame) = self.format.take_frame(&mut conn.read_buffer) {
+                break frame;
+            }
+
+            let n = conn.stream.read(&mut temp_buf).await?;
+            if n == 0 {
+                // Connection closed, use whatever is left
+                break std::mem::take(&mut conn.read_buffer);
+            }
+            conn.read_buffer.extend_from_slice(&temp_buf[0..n]);
+        };
+
+        self.format
+            .decode(&buffer)
+            .map_err(TransmitterError::DeserializationFailed)
+    }
+}
+
+// Simulated shared memory segment
+pub struct SharedMemTransport {
+    name: String,
+    last_sent: Mutex<Option<String>>, // Id of the last reading sent, echoed in feedback
+}
+
+impl SharedMemTransport {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            last_sent: Mutex::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for SharedMemTransport {
+    async fn connect(&mut self) -> Result<(), TransmitterError> {
+        // This would use a shared memory crate in a real implementation
+        // For simulation purposes, we'll just check a segment is named
+        if self.name.is_empty() {
+            return Err(TransmitterError::SharedMemoryNameMissing);
+        }
+        Ok(())
+    }
+
+    async fn send(&self, data: &SensorData) -> Result<(), TransmitterError> {
+        // In a real implementation, this would write to shared memory
+        // For simulation, we'll just simulate the time it takes
+        tokio::time::sleep(Duration::from_micros(100)).await;
+        *self.last_sent.lock().await = data.reading_id();
+        Ok(())
+    }
+
+    async fn recv_feedback(&self) -> Result<ActuatorFeedback, TransmitterError> {
+        // In a real implementation, this would read from shared memory
+        // For simulation, just return a dummy feedback
+        Ok(ActuatorFeedback {
+            timestamp: now_millis(),
+            actuator_id: ActuatorId::new("sim_actuator"),
+            status: ActuatorStatus::Normal,
+            message: Some("Simulation feedback".to_string()),
+            command_id: self.last_sent.lock().await.take(),
+        })
+    }
+}
+
+// In-process delivery of commands over a crossbeam channel
+pub struct ChannelTransport {
+    tx: Option<crossbeam_channel::Sender<ActuatorCommand>>,
+}
+
+impl ChannelTransport {
+    // With no sender, readings are accepted and discarded
+    pub fn new(tx: Option<crossbeam_channel::Sender<ActuatorCommand>>) -> Self {
+        Self { tx }
+    }
+}
+
+#[async_trait]
+impl Transport for ChannelTransport {
+    async fn connect(&mut self) -> Result<(), TransmitterError> {
+        // Nothing to connect, the channel is always available
+        Ok(())
+    }
+
+    async fn send(&self, data: &SensorData) -> Result<(), TransmitterError> {
+        if let Some(tx) = &self.tx {
+            let command = ActuatorCommand::from_sensor_data(data);
+            tx.send(command)
+                .map_err(|_| TransmitterError::ChannelClosed)?;
+        }
+        Ok(())
+    }
+
+    async fn recv_feedback(&self) -> Result<ActuatorFeedback, TransmitterError> {
+        // Feedback comes back through a separate channel
+        Err(TransmitterError::FeedbackUnsupported)
+    }
+
+    fn has_feedback(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::data_types::{SensorId, SensorType};
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    fn reading(seq: u64) -> SensorData {
+        SensorData {
+            timestamp: 1_000 + seq as u128,
+            sensor_id: SensorId::new("force_1"),
+            reading_type: SensorType::Force,
+            value: seq as f64,
+            is_anomaly: false,
+            confidence: 1.0,
+            confidence_lower: None,
+            confidence_upper: None,
+            seq,
+            rejected: false,
+        }
+    }
+
+    fn feedback(id: usize) -> ActuatorFeedback {
+        ActuatorFeedback {
+            timestamp: 1_000,
+            actuator_id: ActuatorId::new("actuator_1"),
+            status: ActuatorStatus::Normal,
+            message: None,
+            command_id: Some(id.to_string()),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn unread_feedback_past_the_cap_drops_the_oldest_frames() {
+        let format = SerializationFormat::Json;
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut transport = TcpTransport::new(Endpoint::new("127.0.0.1", port), format);
+        transport.connect().await.unwrap();
+
+        // The actuator answers with twice the cap's worth of feedback nobody reads
+        let frame_len = format.encode_frame(&feedback(0)).unwrap().len();
+        let frames = 2 * MAX_READ_BUFFER_BYTES / frame_len;
+        let actuator = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            for id in 0..frames {
+                stream
+                    .write_all(&format.encode_frame(&feedback(id)).unwrap())
+                    .unwrap();
+            }
+            stream
+        });
+
+        // Each send buffers whatever feedback has arrived
+        while !actuator.is_finished() {
+            transport.send(&reading(1)).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let _stream = actuator.join().unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        transport.send(&reading(2)).await.unwrap();
+
+        let buffered = {
+            let conn = transport.connection.as_ref().unwrap().lock().await;
+            conn.read_buffer.len()
+        };
+        assert!(
+            buffered <= MAX_READ_BUFFER_BYTES,
+            "{} bytes buffered",
+            buffered
+        );
+
+        // What's left is the newest feedback, still whole frames
+        let oldest: usize = transport
+            .recv_feedback()
+            .await
+            .unwrap()
+            .command_id
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(oldest >= frames / 2 - 1, "oldest kept frame is {}", oldest);
+    }
+
+    #[tokio::test]
+    async fn connect_times_out_on_an_endpoint_that_never_answers() {
+        // A listener that never accepts, with its backlog filled, drops further SYNs
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(1).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mut queued = Vec::new();
+        for _ in 0..2 {
+            queued.push(TcpStream::connect(("127.0.0.1", port)).await.unwrap());
+        }
+
+        let mut transport =
+            TcpTransport::new(Endpoint::new("127.0.0.1", port), SerializationFormat::Json)
+                .with_connect_timeout(Duration::from_millis(200));
+        let start = std::time::Instant::now();
+        let err = transport.connect().await.unwrap_err();
+        let elapsed = start.elapsed();
+
+        assert!(
+            matches!(
+                err,
+                TransmitterError::ConnectTimeout {
+                    timeout_ms: 200,
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+        assert!(elapsed >= Duration::from_millis(200), "took {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(2), "took {:?}", elapsed);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::common::clock::now_millis;
+use crate::common::data_types::{ActuatorFeedback, ActuatorStatus, SensorId};
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time;
+
+// Tracks when each sensor last produced a reading and reports sensors that go silent
+pub struct SensorWatchdog {
+    timeout: Duration,
+    last_seen: HashMap<SensorId, Instant>,
+    stalled: HashSet<SensorId>, // Already reported, so each stall fires once
+}
+
+impl SensorWatchdog {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_seen: HashMap::new(),
+            stalled: HashSet::new(),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    // Note a reading from `sensor_id` at `now`
+    pub fn observe(&mut self, sensor_id: &SensorId, now: Instant) {
+        if self.stalled.remove(sensor_id) {
+            info!("Sensor {} has resumed producing readings.", sensor_id);
+        }
+        match self.last_seen.get_mut(sensor_id) {
+            Some(last_seen) => *last_seen = now,
+            None => {
+                self.last_seen.insert(sensor_id.clone(), now);
+            }
+        }
+    }
+
+    // Sensors that have gone silent since the last check, with how long they've been silent
+    pub fn check(&mut self, now: Instant) -> Vec<(SensorId, Duration)> {
+        let mut newly_stalled = Vec::new();
+        for (sensor_id, &last_seen) in &self.last_seen {
+            let silent_for = now.saturating_duration_since(last_seen);
+            if silent_for > self.timeout && !self.stalled.contains(sensor_id) {
+                newly_stalled.push((sensor_id.clone(), silent_for));
+            }
+        }
+        for (sensor_id, _) in &newly_stalled {
+            self.stalled.insert(sensor_id.clone());
+        }
+        newly_stalled
+    }
+}
+
+// Periodically check the watchdog and emit a Warning feedback for each stalled sensor
+pub async fn run_watchdog(
+    watchdog: Arc<Mutex<SensorWatchdog>>,
+    feedback_tx: crossbeam_channel::Sender<ActuatorFeedback>,
+) {
+    let check_interval = (watchdog.lock().unwrap().timeout() / 2).max(Duration::from_millis(1));
+    let mut interval = time::interval(check_interval);
+
+    loop {
+        interval.tick().await;
+
+        let stalled = watchdog.lock().unwrap().check(Instant::now());
+        for (sensor_id, silent_for) in stalled {
+            warn!(
+                "Sensor {} has been silent for {:?}, it may have stalled.",
+                sensor_id, silent_for
+            );
+
+            let feedback = ActuatorFeedback {
+                timestamp: now_millis(),
+                actuator_id: sensor_id.actuator(),
+                status: ActuatorStatus::Warning,
+                message: Some(format!(
+                    "Sensor {} silent for {} ms",
+                    sensor_id,
+                    silent_for.as_millis()
+                )),
+                command_id: None,
+            };
+            if feedback_tx.send(feedback).is_err() {
+                info!("Feedback channel closed, stopping watchdog.");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_silent_sensor_fires_once_after_the_timeout() {
+        let mut watchdog = SensorWatchdog::new(Duration::from_millis(100));
+        let start = Instant::now();
+        let quiet = SensorId::new("force_1");
+        let busy = SensorId::new("force_2");
+        watchdog.observe(&quiet, start);
+        watchdog.observe(&busy, start);
+
+        watchdog.observe(&busy, start + Duration::from_millis(90));
+        assert!(watchdog
+            .check(start + Duration::from_millis(100))
+            .is_empty());
+
+        let stalled = watchdog.check(start + Duration::from_millis(150));
+        assert_eq!(stalled, [(quiet.clone(), Duration::from_millis(150))]);
+        // Reported once per stall, and again only after the sensor resumes
+        assert!(watchdog
+            .check(start + Duration::from_millis(180))
+            .is_empty());
+        watchdog.observe(&quiet, start + Duration::from_millis(200));
+        let stalled = watchdog.check(start + Duration::from_millis(400));
+        assert_eq!(stalled.len(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn run_watchdog_sends_a_warning_for_a_silent_sensor() {
+        let watchdog = Arc::new(Mutex::new(SensorWatchdog::new(Duration::from_millis(50))));
+        watchdog
+            .lock()
+            .unwrap()
+            .observe(&SensorId::new("force_1"), Instant::now());
+        let (feedback_tx, feedback_rx) = crossbeam_channel::unbounded();
+        let task = tokio::spawn(run_watchdog(Arc::clone(&watchdog), feedback_tx));
+
+        let feedback = tokio::task::spawn_blocking(move || {
+            feedback_rx.recv_timeout(Duration::from_secs(5)).unwrap()
+        })
+        .await
+        .unwrap();
+        task.abort();
+
+        assert_eq!(feedback.actuator_id, SensorId::new("force_1").actuator());
+        assert!(matches!(feedback.status, ActuatorStatus::Warning));
+        assert!(feedback
+            .message
+            .unwrap()
+            .starts_with("Sensor force_1 silent for"));
+    }
+}
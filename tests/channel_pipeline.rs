@@ -0,0 +1,232 @@
+use crossbeam_channel::{unbounded, Receiver};
+use rust_assignment::actuator::router::{run_route, CommandRouter};
+use rust_assignment::actuator::system::run_actuator_system;
+use rust_assignment::common::data_types::{
+    ActuatorFeedback, ActuatorStatus, SensorData, SensorId, SensorType,
+};
+use rust_assignment::common::metrics::metrics_channel;
+use rust_assignment::config::{Config, SetpointSource};
+use rust_assignment::sensor::anomaly_log::AnomalyLog;
+use rust_assignment::sensor::processor::{
+    run_passthrough, run_processor, ProcessorChannels, SharedSensorStats,
+};
+use rust_assignment::sensor::transmitter::{run_transmitter, SharedQueueDepth};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn force_reading(seq: u64, value: f64) -> SensorData {
+    SensorData {
+        timestamp: 1_000 + seq as u128 * 10,
+        sensor_id: SensorId::new("force_1"),
+        reading_type: SensorType::Force,
+        value,
+        is_anomaly: false,
+        confidence: 1.0,
+        confidence_lower: None,
+        confidence_upper: None,
+        seq,
+        rejected: false,
+    }
+}
+
+// Nothing should arrive on `rx` for a while
+fn assert_quiet(rx: &Receiver<ActuatorFeedback>) {
+    if let Ok(feedback) = rx.recv_timeout(Duration::from_millis(200)) {
+        panic!("unexpected feedback: {:?}", feedback);
+    }
+}
+
+// Sensor readings through the processor, transmitter, command router and actuator,
+// all connected by crossbeam channels as in the default "channel" mode. The
+// actuator has no setpoint until a command gives it one, and only the processor's
+// anomaly commands are mapped to a setpoint, so any feedback is down to an anomaly.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn anomaly_command_gets_actuator_feedback_in_channel_mode() {
+    let mut config = Config::default();
+    config.transmitter.connection_type = "channel".to_string();
+    config.actuator.setpoint_source = SetpointSource::External;
+    config.actuator.setpoint_commands =
+        HashMap::from([("adjust_position".to_string(), SensorType::Force)]);
+
+    let (processor_in_tx, processor_in_rx) = unbounded();
+    let (actuator_in_tx, actuator_in_rx) = unbounded();
+    let (processed_tx, processed_rx) = unbounded();
+    let (actuator_tx, actuator_rx) = unbounded();
+    let (feedback_tx, feedback_rx) = unbounded();
+    let (_processor_control_tx, processor_control_rx) = unbounded();
+    let (actuator_control_tx, actuator_control_rx) = unbounded();
+    let (metrics_tx, _metrics_rx) = metrics_channel(10_000);
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let (router, route_receivers) = CommandRouter::new(config.actuator.command_routes.clone(), 100);
+    for (route, route_rx) in route_receivers {
+        let policy = config.actuator.scheduling_policy;
+        let setpoint_commands = config.actuator.setpoint_commands.clone();
+        let setpoint_tx = actuator_control_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            run_route(&route, route_rx, policy, &setpoint_commands, &setpoint_tx)
+        });
+    }
+    drop(actuator_control_tx);
+    let command_router = tokio::task::spawn_blocking(move || router.run(actuator_rx));
+
+    let actuator = {
+        let config = config.actuator.clone();
+        let metrics_tx = metrics_tx.clone();
+        let shutdown = Arc::clone(&shutdown);
+        tokio::spawn(async move {
+            run_actuator_system(
+                &config,
+                actuator_in_rx,
+                feedback_tx,
+                metrics_tx,
+                actuator_control_rx,
+                shutdown,
+                None,
+            )
+            .await;
+        })
+    };
+    let processor = {
+        let config = config.processor.clone();
+        let channels = ProcessorChannels {
+            rx: processor_in_rx,
+            tx: processed_tx,
+            metrics_tx: metrics_tx.clone(),
+            actuator_tx: actuator_tx.clone(),
+            control_rx: processor_control_rx,
+            anomaly_log: AnomalyLog::new(config.anomaly_log_size),
+            sensor_stats: SharedSensorStats::default(),
+        };
+        tokio::spawn(async move { run_processor(&config, channels).await })
+    };
+    let transmitter = {
+        let config = config.transmitter.clone();
+        tokio::spawn(async move {
+            run_transmitter(
+                &config,
+                processed_rx,
+                Some(actuator_tx),
+                metrics_tx,
+                None,
+                SharedQueueDepth::default(),
+            )
+            .await;
+        })
+    };
+
+    let send = |reading: SensorData| {
+        actuator_in_tx.send(reading.clone()).unwrap();
+        processor_in_tx.send(reading).unwrap();
+    };
+
+    // Steady readings give the actuator sensor data but no setpoint
+    for seq in 1..=30 {
+        send(force_reading(seq, 10.0 + (seq % 2) as f64 * 0.2));
+    }
+    assert_quiet(&feedback_rx);
+
+    // A spike is flagged, and its command sets a setpoint the actuator acts on
+    send(force_reading(31, 100.0));
+    let feedback = feedback_rx
+        .recv_timeout(Duration::from_secs(5))
+        .expect("no feedback for the anomaly command");
+    assert_eq!(feedback.actuator_id.to_string(), "actuator_1");
+    assert!(matches!(feedback.status, ActuatorStatus::Normal));
+
+    // Closing the inputs winds the whole pipeline down
+    drop(processor_in_tx);
+    drop(actuator_in_tx);
+    shutdown.store(true, Ordering::Relaxed);
+    let stopped = async {
+        processor.await.unwrap();
+        transmitter.await.unwrap();
+        command_router.await.unwrap();
+        actuator.await.unwrap();
+    };
+    tokio::time::timeout(Duration::from_secs(5), stopped)
+        .await
+        .expect("pipeline didn't stop");
+}
+
+// With no shutdown flag or signal, each channel consumer stops on its own once
+// every sender feeding it is dropped, so the pipeline drains from the front
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn consumers_stop_once_every_sender_is_dropped() {
+    let mut config = Config::default();
+    config.transmitter.connection_type = "channel".to_string();
+
+    let (processor_in_tx, processor_in_rx) = unbounded();
+    let (passthrough_in_tx, passthrough_in_rx) = unbounded();
+    let (processed_tx, processed_rx) = unbounded();
+    let (passed_tx, passed_rx) = unbounded();
+    let (actuator_tx, actuator_rx) = unbounded();
+    let (_processor_control_tx, processor_control_rx) = unbounded();
+    let (metrics_tx, _metrics_rx) = metrics_channel(10_000);
+
+    let (router, route_receivers) = CommandRouter::new(config.actuator.command_routes.clone(), 100);
+    let (setpoint_tx, _setpoint_rx) = unbounded();
+    let routes: Vec<_> = route_receivers
+        .into_iter()
+        .map(|(route, route_rx)| {
+            let policy = config.actuator.scheduling_policy;
+            let setpoint_tx = setpoint_tx.clone();
+            tokio::task::spawn_blocking(move || {
+                run_route(&route, route_rx, policy, &HashMap::new(), &setpoint_tx)
+            })
+        })
+        .collect();
+    let command_router = tokio::task::spawn_blocking(move || router.run(actuator_rx));
+    let passthrough =
+        tokio::task::spawn_blocking(move || run_passthrough(passthrough_in_rx, passed_tx));
+    let processor = {
+        let config = config.processor.clone();
+        let channels = ProcessorChannels {
+            rx: processor_in_rx,
+            tx: processed_tx,
+            metrics_tx: metrics_tx.clone(),
+            actuator_tx: actuator_tx.clone(),
+            control_rx: processor_control_rx,
+            anomaly_log: AnomalyLog::new(config.anomaly_log_size),
+            sensor_stats: SharedSensorStats::default(),
+        };
+        tokio::spawn(async move { run_processor(&config, channels).await })
+    };
+    let transmitter = {
+        let config = config.transmitter.clone();
+        tokio::spawn(async move {
+            run_transmitter(
+                &config,
+                processed_rx,
+                Some(actuator_tx),
+                metrics_tx,
+                None,
+                SharedQueueDepth::default(),
+            )
+            .await;
+        })
+    };
+
+    for seq in 1..=10 {
+        processor_in_tx.send(force_reading(seq, 10.0)).unwrap();
+        passthrough_in_tx.send(force_reading(seq, 10.0)).unwrap();
+    }
+    drop(processor_in_tx);
+    drop(passthrough_in_tx);
+
+    let stopped = async {
+        processor.await.unwrap();
+        transmitter.await.unwrap();
+        command_router.await.unwrap();
+        for route in routes {
+            route.await.unwrap();
+        }
+        passthrough.await.unwrap();
+    };
+    tokio::time::timeout(Duration::from_secs(5), stopped)
+        .await
+        .expect("consumers didn't stop");
+    assert_eq!(passed_rx.try_iter().count(), 10);
+}
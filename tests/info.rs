@@ -0,0 +1,22 @@
+use std::process::Command;
+
+// `info` lists every sensor type with its default threshold and every connection type
+#[test]
+fn info_lists_sensor_and_connection_types() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_assignment"))
+        .arg("info")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let has_line = |fields: &[&str]| {
+        stdout
+            .lines()
+            .any(|line| line.split_whitespace().eq(fields.iter().copied()))
+    };
+    assert!(has_line(&["force", "2.5"]), "{}", stdout);
+    assert!(has_line(&["temperature", "3.5"]), "{}", stdout);
+    assert!(has_line(&["tcp"]), "{}", stdout);
+    assert!(has_line(&["channel"]), "{}", stdout);
+}
@@ -0,0 +1,53 @@
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+// `run` stops on SIGTERM the same way it does on Ctrl+C: the actuator is stopped,
+// the final metrics summary is logged and the process exits cleanly
+#[test]
+fn sigterm_shuts_the_pipeline_down_and_logs_final_metrics() {
+    // Metrics are logged to metrics.log in the working directory
+    let dir = std::env::temp_dir().join(format!("rust_assignment_shutdown_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust_assignment"))
+        .args(["--log-level", "info", "run"])
+        .current_dir(&dir)
+        // Stages block their workers on channel receives, so give them a few
+        .env("TOKIO_WORKER_THREADS", "4")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (lines_tx, lines_rx) = mpsc::channel();
+    let stderr = child.stderr.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+            let _ = lines_tx.send(line);
+        }
+    });
+    let wait_for = |text: &str| loop {
+        match lines_rx.recv_timeout(Duration::from_secs(20)) {
+            Ok(line) if line.contains(text) => return,
+            Ok(_) => {}
+            Err(_) => panic!("never logged {:?}", text),
+        }
+    };
+
+    wait_for("System running");
+    std::thread::sleep(Duration::from_millis(300));
+    let killed = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    wait_for("Received SIGTERM, shutting down");
+    wait_for("Final metrics summary");
+    let status = child.wait().unwrap();
+    assert!(status.success(), "exited with {}", status);
+}
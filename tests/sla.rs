@@ -0,0 +1,46 @@
+use std::process::{Command, Output};
+
+// One successful JSON-lines metrics record, as written via metrics.records_file
+fn record(operation: &str, duration_ms: f64) -> String {
+    format!(
+        r#"{{"operation":"{}","start_time_ms":1000,"end_time_ms":1010,"duration_ms":{},"success":true,"attempts":1}}"#,
+        operation, duration_ms
+    )
+}
+
+// Write `records` to a file of their own and check them against the SLA
+fn check_sla(name: &str, records: &[String]) -> Output {
+    let path = std::env::temp_dir().join(format!("sla_{}_{}.jsonl", name, std::process::id()));
+    std::fs::write(&path, records.join("\n")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust_assignment"))
+        .args(["sla", "--input"])
+        .arg(&path)
+        .args(["--max-latency-ms", "10", "--min-success-rate", "0.9"])
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(&path);
+    output
+}
+
+#[test]
+fn sla_passes_when_every_operation_is_fast_and_successful() {
+    let records: Vec<_> = (0..10).map(|_| record("data_processing", 2.0)).collect();
+    let output = check_sla("met", &records);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
+
+// Half the operations over the latency bound is a violation, so the exit status fails
+#[test]
+fn sla_violation_exits_non_zero() {
+    let records: Vec<_> = (0..10)
+        .map(|i| record("data_processing", if i % 2 == 0 { 2.0 } else { 50.0 }))
+        .collect();
+    let output = check_sla("violated", &records);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("data_processing"), "{}", stdout);
+}